@@ -0,0 +1,347 @@
+//! Crate ruma_events_macros provides procedural macros used by
+//! [ruma_events](https://docs.rs/ruma-events).
+//!
+//! The derives implement the event traits of ruma_events for a struct with the fields of the
+//! corresponding kind of event:
+//!
+//! * `#[derive(Event)]` implements `Event` and requires `content` and `event_type` fields.
+//! * `#[derive(RoomEvent)]` also implements `RoomEvent` and requires `event_id`,
+//!   `origin_server_ts`, `room_id`, `sender` and `unsigned` fields.
+//! * `#[derive(StateEvent)]` also implements `StateEvent` and requires `prev_content` and
+//!   `state_key` fields.
+//!
+//! The type of the `content` field is used as the event's `Content`. Each derive also implements
+//! `Serialize` and `Deserialize`, honoring the `rename`, `skip_serializing_if` and `default` serde
+//! attributes on fields, and `#[derive(StateEvent)]` implements `From` the event for its
+//! `StrippedStateContent`.
+//!
+//! The generated code refers to the traits as `::ruma_events::*` and to the types of other crates
+//! through `::ruma_events::exports`, so crates using the derives need no other dependencies.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{Data, DeriveInput, Error, Field, Fields, Ident, LitStr, Path, Type};
+
+/// Implements `Event`, `Serialize` and `Deserialize` for a struct.
+#[proc_macro_derive(Event, attributes(serde))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    expand(input, EventKind::Event)
+}
+
+/// Implements `Event`, `RoomEvent`, `Serialize` and `Deserialize` for a struct.
+#[proc_macro_derive(RoomEvent, attributes(serde))]
+pub fn derive_room_event(input: TokenStream) -> TokenStream {
+    expand(input, EventKind::RoomEvent)
+}
+
+/// Implements `Event`, `RoomEvent`, `StateEvent`, `Serialize` and `Deserialize` for a struct, and
+/// `From` the struct for its `StrippedStateContent`.
+#[proc_macro_derive(StateEvent, attributes(serde))]
+pub fn derive_state_event(input: TokenStream) -> TokenStream {
+    expand(input, EventKind::StateEvent)
+}
+
+/// The kinds of events, each of which requires the fields of the previous one.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum EventKind {
+    Event,
+    RoomEvent,
+    StateEvent,
+}
+
+impl EventKind {
+    /// The fields a struct must have to derive this kind of event.
+    fn required_fields(self) -> &'static [&'static str] {
+        match self {
+            EventKind::Event => &["content", "event_type"],
+            EventKind::RoomEvent => &[
+                "content",
+                "event_type",
+                "event_id",
+                "origin_server_ts",
+                "room_id",
+                "sender",
+                "unsigned",
+            ],
+            EventKind::StateEvent => &[
+                "content",
+                "event_type",
+                "event_id",
+                "origin_server_ts",
+                "room_id",
+                "sender",
+                "unsigned",
+                "prev_content",
+                "state_key",
+            ],
+        }
+    }
+}
+
+fn expand(input: TokenStream, kind: EventKind) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    match impls(&input, kind) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn impls(input: &DeriveInput, kind: EventKind) -> Result<TokenStream2, Error> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "events must be structs with named fields",
+                ))
+            }
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "events must be structs")),
+    };
+
+    let field_type = |name: &str| -> Option<&Type> {
+        fields
+            .iter()
+            .find(|field| match field.ident {
+                Some(ref ident) => ident == name,
+                None => false,
+            })
+            .map(|field| &field.ty)
+    };
+
+    for name in kind.required_fields() {
+        if field_type(name).is_none() {
+            return Err(Error::new_spanned(
+                &input.ident,
+                format!("events of this kind must have a `{}` field", name),
+            ));
+        }
+    }
+
+    let name = &input.ident;
+    let content_type = field_type("content").unwrap();
+
+    let mut tokens = event_impl(name, content_type);
+
+    if kind >= EventKind::RoomEvent {
+        tokens.extend(room_event_impl(name));
+    }
+
+    if kind >= EventKind::StateEvent {
+        tokens.extend(state_event_impl(name));
+        tokens.extend(from_impl(name, content_type));
+    }
+
+    tokens.extend(serialize_impl(name, fields)?);
+    tokens.extend(deserialize_impl(name, fields));
+
+    Ok(tokens)
+}
+
+/// The serde attributes of a field that the generated `Serialize` implementation has to honor.
+#[derive(Default)]
+struct SerdeField {
+    rename: Option<LitStr>,
+    skip_serializing_if: Option<Path>,
+}
+
+impl SerdeField {
+    /// Parses the `#[serde(...)]` attributes of `field`, rejecting any that are not supported.
+    fn parse(field: &Field) -> Result<Self, Error> {
+        let mut serde_field = SerdeField::default();
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("serde"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    serde_field.rename = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip_serializing_if") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    serde_field.skip_serializing_if = Some(path.parse()?);
+                } else if meta.path.is_ident("default") {
+                    if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<LitStr>()?;
+                    }
+                } else {
+                    return Err(meta.error("unsupported serde attribute on an event field"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(serde_field)
+    }
+}
+
+fn serialize_impl(name: &Ident, fields: &Punctuated<Field, Comma>) -> Result<TokenStream2, Error> {
+    let mut serialize_fields = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let serde_field = SerdeField::parse(field)?;
+        let key = serde_field
+            .rename
+            .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+
+        serialize_fields.push(match serde_field.skip_serializing_if {
+            Some(skip) => quote! {
+                if #skip(&self.#ident) {
+                    state.skip_field(#key)?;
+                } else {
+                    state.serialize_field(#key, &self.#ident)?;
+                }
+            },
+            None => quote! {
+                state.serialize_field(#key, &self.#ident)?;
+            },
+        });
+    }
+
+    let len = fields.len();
+
+    Ok(quote! {
+        impl ::ruma_events::exports::serde::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::ruma_events::exports::serde::Serializer,
+            {
+                use ::ruma_events::exports::serde::ser::SerializeStruct;
+
+                let mut state = serializer.serialize_struct(stringify!(#name), #len)?;
+                #(#serialize_fields)*
+                state.end()
+            }
+        }
+    })
+}
+
+/// Deserializes the struct through a copy of it, including the serde attributes of its fields,
+/// that derives `Deserialize` with serde.
+fn deserialize_impl(name: &Ident, fields: &Punctuated<Field, Comma>) -> TokenStream2 {
+    let helper_fields = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let attrs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("serde"));
+
+        quote! {
+            #(#attrs)*
+            #ident: #ty
+        }
+    });
+    let idents = fields.iter().map(|field| &field.ident);
+    let name_str = LitStr::new(&name.to_string(), name.span());
+
+    quote! {
+        impl<'de> ::ruma_events::exports::serde::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::ruma_events::exports::serde::Deserializer<'de>,
+            {
+                #[derive(::ruma_events::exports::serde_derive::Deserialize)]
+                #[serde(crate = "::ruma_events::exports::serde", rename = #name_str)]
+                struct Helper {
+                    #(#helper_fields),*
+                }
+
+                use ::ruma_events::exports::serde::Deserialize;
+
+                let helper = Helper::deserialize(deserializer)?;
+
+                Ok(#name {
+                    #(#idents: helper.#idents),*
+                })
+            }
+        }
+    }
+}
+
+fn event_impl(name: &Ident, content_type: &Type) -> TokenStream2 {
+    quote! {
+        impl ::ruma_events::Event for #name {
+            type Content = #content_type;
+
+            fn content(&self) -> &Self::Content {
+                &self.content
+            }
+
+            fn event_type(&self) -> &::ruma_events::EventType {
+                &self.event_type
+            }
+        }
+    }
+}
+
+fn room_event_impl(name: &Ident) -> TokenStream2 {
+    quote! {
+        impl ::ruma_events::RoomEvent for #name {
+            fn event_id(&self) -> &::ruma_events::exports::EventId {
+                &self.event_id
+            }
+
+            fn origin_server_ts(&self) -> u64 {
+                self.origin_server_ts
+            }
+
+            fn room_id(&self) -> Option<&::ruma_events::exports::RoomId> {
+                self.room_id.as_ref()
+            }
+
+            fn unsigned(&self) -> Option<&::ruma_events::exports::Value> {
+                self.unsigned.as_ref()
+            }
+
+            fn sender(&self) -> &::ruma_events::exports::UserId {
+                &self.sender
+            }
+        }
+    }
+}
+
+fn state_event_impl(name: &Ident) -> TokenStream2 {
+    quote! {
+        impl ::ruma_events::StateEvent for #name {
+            fn prev_content(&self) -> Option<&Self::Content> {
+                self.prev_content.as_ref()
+            }
+
+            fn state_key(&self) -> &str {
+                &self.state_key
+            }
+        }
+    }
+}
+
+fn from_impl(name: &Ident, content_type: &Type) -> TokenStream2 {
+    quote! {
+        impl From<#name> for ::ruma_events::stripped::StrippedStateContent<#content_type> {
+            fn from(event: #name) -> Self {
+                ::ruma_events::stripped::StrippedStateContent {
+                    content: event.content,
+                    event_type: event.event_type,
+                    sender: Some(event.sender),
+                    state_key: event.state_key,
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Types for the *m.call.answer* event.
 
-use super::SessionDescription;
+use super::{CallError, SessionDescription, SessionDescriptionType};
 
 room_event! {
     /// This event is sent by the callee when they wish to answer the call.
@@ -8,7 +8,7 @@ room_event! {
 }
 
 /// The payload of an `AnswerEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct AnswerEventContent {
     /// The VoIP session description object. The session description type must be *answer*.
     pub answer: SessionDescription,
@@ -17,3 +17,39 @@ pub struct AnswerEventContent {
     /// The version of the VoIP specification this messages adheres to.
     pub version: u64,
 }
+
+impl AnswerEventContent {
+    /// Checks that the session description is of type *answer*.
+    pub fn validate(&self) -> Result<(), CallError> {
+        self.answer.validate_type(SessionDescriptionType::Answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::AnswerEventContent;
+    use call::{CallError, SessionDescriptionType};
+
+    #[test]
+    fn validate() {
+        let valid = from_str::<AnswerEventContent>(
+            r#"{"call_id":"abc","answer":{"sdp":"v=0","type":"answer"},"version":0}"#,
+        )
+        .unwrap();
+        let invalid = from_str::<AnswerEventContent>(
+            r#"{"call_id":"abc","answer":{"sdp":"v=0","type":"offer"},"version":0}"#,
+        )
+        .unwrap();
+
+        assert!(valid.validate().is_ok());
+        assert_eq!(
+            invalid.validate(),
+            Err(CallError::WrongSessionDescriptionType {
+                expected: SessionDescriptionType::Answer,
+                found: SessionDescriptionType::Offer,
+            })
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Types for the *m.call.invite* event.
 
-use super::SessionDescription;
+use super::{CallError, SessionDescription, SessionDescriptionType};
 
 room_event! {
     /// This event is sent by the caller when they wish to establish a call.
@@ -8,7 +8,7 @@ room_event! {
 }
 
 /// The payload of an `InviteEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct InviteEventContent {
     /// A unique identifer for the call.
     pub call_id: String,
@@ -21,3 +21,39 @@ pub struct InviteEventContent {
     /// The version of the VoIP specification this messages adheres to.
     pub version: u64,
 }
+
+impl InviteEventContent {
+    /// Checks that the session description is of type *offer*.
+    pub fn validate(&self) -> Result<(), CallError> {
+        self.offer.validate_type(SessionDescriptionType::Offer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::InviteEventContent;
+    use call::{CallError, SessionDescriptionType};
+
+    #[test]
+    fn validate() {
+        let valid = from_str::<InviteEventContent>(
+            r#"{"call_id":"abc","lifetime":30000,"offer":{"sdp":"v=0","type":"offer"},"version":0}"#,
+        )
+        .unwrap();
+        let invalid = from_str::<InviteEventContent>(
+            r#"{"call_id":"abc","lifetime":30000,"offer":{"sdp":"v=0","type":"answer"},"version":0}"#,
+        )
+        .unwrap();
+
+        assert!(valid.validate().is_ok());
+        assert_eq!(
+            invalid.validate(),
+            Err(CallError::WrongSessionDescriptionType {
+                expected: SessionDescriptionType::Offer,
+                found: SessionDescriptionType::Answer,
+            })
+        );
+    }
+}
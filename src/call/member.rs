@@ -0,0 +1,118 @@
+//! Types for the *m.call.member* event, as proposed in MSC3401 for group calls.
+
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
+state_event! {
+    /// The devices of a user that are taking part in group calls in a room.
+    ///
+    /// The state key is the ID of the user.
+    pub struct CallMemberEvent(CallMemberEventContent) {}
+}
+
+/// The payload of a `CallMemberEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CallMemberEventContent {
+    /// The calls the user's devices are taking part in. Empty when the user has left all calls.
+    pub memberships: Vec<CallMembership>,
+}
+
+impl CallMemberEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
+
+/// A device taking part in a call.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CallMembership {
+    /// The kind of call, e.g. *m.call*.
+    pub application: String,
+    /// The ID of the call.
+    pub call_id: String,
+    /// The ID of the device.
+    pub device_id: String,
+    /// The time in milliseconds since the unix epoch after which the membership should be
+    /// considered stale unless it has been renewed.
+    pub expires: u64,
+    /// The media streams the device sends.
+    #[serde(default)]
+    pub feeds: Vec<CallFeed>,
+    /// Who the call is shared with.
+    pub scope: CallScope,
+}
+
+/// A media stream sent by a device in a call.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CallFeed {
+    /// What the stream is used for, e.g. *m.usermedia* or *m.screenshare*.
+    pub purpose: String,
+}
+
+/// Who a call is shared with.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CallScope {
+    /// The call is for everyone in the room.
+    #[serde(rename = "m.room")]
+    Room,
+    /// The call is between specific users in the room.
+    #[serde(rename = "m.user")]
+    User,
+}
+
+impl_enum! {
+    CallScope {
+        Room => "m.room",
+        User => "m.user",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{CallFeed, CallMembership, CallScope};
+    use collections::all::StateEvent;
+
+    #[test]
+    fn round_trip() {
+        let json = r#"{
+            "content": {
+                "memberships": [{
+                    "application": "m.call",
+                    "call_id": "",
+                    "device_id": "ABCDEFG",
+                    "expires": 1700000000000,
+                    "feeds": [{"purpose": "m.usermedia"}],
+                    "scope": "m.room"
+                }]
+            },
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1,
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@alice:example.org",
+            "state_key": "@alice:example.org",
+            "type": "m.call.member"
+        }"#;
+        let event = from_str::<StateEvent>(json).unwrap();
+
+        match event {
+            StateEvent::CallMember(ref event) => assert_eq!(
+                event.content.memberships,
+                vec![CallMembership {
+                    application: "m.call".to_string(),
+                    call_id: "".to_string(),
+                    device_id: "ABCDEFG".to_string(),
+                    expires: 1_700_000_000_000,
+                    feeds: vec![CallFeed {
+                        purpose: "m.usermedia".to_string(),
+                    }],
+                    scope: CallScope::Room,
+                }]
+            ),
+            _ => panic!("expected a call member event"),
+        }
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+}
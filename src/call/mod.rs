@@ -6,9 +6,10 @@ pub mod answer;
 pub mod candidates;
 pub mod hangup;
 pub mod invite;
+pub mod member;
 
 /// A VoIP session description.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct SessionDescription {
     /// The type of session description.
     #[serde(rename = "type")]
@@ -17,8 +18,47 @@ pub struct SessionDescription {
     pub sdp: String,
 }
 
+/// An error when the content of a call event is not valid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CallError {
+    /// The session description is of the wrong type, e.g. an *answer* in an invite.
+    WrongSessionDescriptionType {
+        /// The type of session description the event must carry.
+        expected: SessionDescriptionType,
+        /// The type of session description the event carries.
+        found: SessionDescriptionType,
+    },
+}
+
+/// The ID of a VoIP call, the `call_id` of every event in the call.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CallId(pub String);
+
+impl CallId {
+    /// Generates a new random call ID, a version 4 UUID such as
+    /// "936da01f-9abd-4d9d-80c7-02af85c822a8".
+    #[cfg(feature = "random-call-id")]
+    pub fn generate() -> String {
+        ::uuid::Uuid::new_v4().to_string()
+    }
+
+    /// Whether or not `call_id` is a UUID in its hyphenated form, such as the IDs generated by
+    /// `CallId::generate`.
+    ///
+    /// The spec does not require call IDs to be UUIDs, so other clients' calls may fail this
+    /// check.
+    pub fn validate(call_id: &str) -> bool {
+        let groups = call_id.split('-').collect::<Vec<_>>();
+
+        groups.len() == 5
+            && groups.iter().zip(&[8, 4, 4, 4, 12]).all(|(group, &len)| {
+                group.len() == len && group.bytes().all(|byte| byte.is_ascii_hexdigit())
+            })
+    }
+}
+
 /// The type of VoIP session description.
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum SessionDescriptionType {
     /// An answer.
     #[serde(rename = "answer")]
@@ -34,3 +74,44 @@ impl_enum! {
         Offer => "offer",
     }
 }
+
+impl SessionDescription {
+    /// Checks that this session description is of the given type.
+    fn validate_type(&self, expected: SessionDescriptionType) -> Result<(), CallError> {
+        if self.session_type == expected {
+            Ok(())
+        } else {
+            Err(CallError::WrongSessionDescriptionType {
+                expected,
+                found: self.session_type,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CallId;
+
+    #[test]
+    fn valid_call_ids() {
+        assert!(CallId::validate("936da01f-9abd-4d9d-80c7-02af85c822a8"));
+        assert!(CallId::validate("936DA01F-9ABD-4D9D-80C7-02AF85C822A8"));
+        assert!(!CallId::validate("936da01f9abd4d9d80c702af85c822a8"));
+        assert!(!CallId::validate("936da01f-9abd-4d9d-80c7-02af85c822a"));
+        assert!(!CallId::validate("936da01f-9abd-4d9d-80c7-02af85c822a8-0"));
+        assert!(!CallId::validate("936da01g-9abd-4d9d-80c7-02af85c822a8"));
+        assert!(!CallId::validate(""));
+    }
+
+    #[cfg(feature = "random-call-id")]
+    #[test]
+    fn generated_call_ids_are_valid_and_unique() {
+        let first = CallId::generate();
+        let second = CallId::generate();
+
+        assert!(CallId::validate(&first));
+        assert!(CallId::validate(&second));
+        assert_ne!(first, second);
+    }
+}
@@ -1,17 +1,31 @@
 //! Enums for heterogeneous collections of events, inclusive for every event type that implements
 //! the trait of the same name.
 
+// *m.room.aliases* is deprecated, but these events are still received from older servers.
+#![allow(deprecated)]
+
 use call::answer::AnswerEvent;
 use call::candidates::CandidatesEvent;
 use call::hangup::HangupEvent;
 use call::invite::InviteEvent;
+use call::member::CallMemberEvent;
 use direct::DirectEvent;
+use key::verification::accept::AcceptEvent;
+use key::verification::done::DoneRoomEvent;
+use key::verification::key::KeyEvent;
+use key::verification::mac::MacEvent;
+use key::verification::ready::ReadyRoomEvent;
+use poll::end::EndEvent;
+use poll::response::ResponseEvent;
+use poll::start::StartEvent;
 use presence::PresenceEvent;
+use reaction::ReactionEvent;
 use receipt::ReceiptEvent;
 use room::aliases::AliasesEvent;
 use room::avatar::AvatarEvent;
 use room::canonical_alias::CanonicalAliasEvent;
 use room::create::CreateEvent;
+use room::encrypted::EncryptedEvent;
 use room::guest_access::GuestAccessEvent;
 use room::history_visibility::HistoryVisibilityEvent;
 use room::join_rules::JoinRulesEvent;
@@ -21,18 +35,22 @@ use room::name::NameEvent;
 use room::pinned_events::PinnedEventsEvent;
 use room::power_levels::PowerLevelsEvent;
 use room::redaction::RedactionEvent;
+use room::server_acl::ServerAclEvent;
 use room::third_party_invite::ThirdPartyInviteEvent;
 use room::topic::TopicEvent;
+use space::child::SpaceChildEvent;
 use tag::TagEvent;
 use typing::TypingEvent;
+use voice_broadcast_info::VoiceBroadcastInfoEvent;
 use {CustomEvent, CustomRoomEvent, CustomStateEvent, EventType};
 
+use ruma_identifiers::{EventId, RoomId, UserId};
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{from_value, Value};
 
 /// A basic event, room event, or state event.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Event {
     /// m.call.answer
     CallAnswer(AnswerEvent),
@@ -42,10 +60,30 @@ pub enum Event {
     CallHangup(HangupEvent),
     /// m.call.invite
     CallInvite(InviteEvent),
+    /// m.call.member
+    CallMember(CallMemberEvent),
     /// m.direct
     Direct(DirectEvent),
+    /// m.key.verification.accept
+    KeyVerificationAccept(AcceptEvent),
+    /// m.key.verification.done, as sent in a room
+    KeyVerificationDone(DoneRoomEvent),
+    /// m.key.verification.key
+    KeyVerificationKey(KeyEvent),
+    /// m.key.verification.mac
+    KeyVerificationMac(MacEvent),
+    /// m.key.verification.ready, as sent in a room
+    KeyVerificationReady(ReadyRoomEvent),
+    /// m.poll.end
+    PollEnd(EndEvent),
+    /// m.poll.response
+    PollResponse(ResponseEvent),
+    /// m.poll.start
+    PollStart(StartEvent),
     /// m.presence
     Presence(PresenceEvent),
+    /// m.reaction
+    Reaction(ReactionEvent),
     /// m.receipt
     Receipt(ReceiptEvent),
     /// m.room.aliases
@@ -56,6 +94,8 @@ pub enum Event {
     RoomCanonicalAlias(CanonicalAliasEvent),
     /// m.room.create
     RoomCreate(CreateEvent),
+    /// m.room.encrypted
+    RoomEncrypted(EncryptedEvent),
     /// m.room.guest_access
     RoomGuestAccess(GuestAccessEvent),
     /// m.room.history_visibility
@@ -74,14 +114,20 @@ pub enum Event {
     RoomPowerLevels(PowerLevelsEvent),
     /// m.room.redaction
     RoomRedaction(RedactionEvent),
+    /// m.room.server_acl
+    RoomServerAcl(ServerAclEvent),
     /// m.room.third_party_invite
     RoomThirdPartyInvite(ThirdPartyInviteEvent),
     /// m.room.topic
     RoomTopic(TopicEvent),
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
     /// m.tag
     Tag(TagEvent),
     /// m.typing
     Typing(TypingEvent),
+    /// m.voice_broadcast_info
+    VoiceBroadcastInfo(VoiceBroadcastInfoEvent),
     /// Any basic event that is not part of the specification.
     Custom(CustomEvent),
     /// Any room event that is not part of the specification.
@@ -91,7 +137,7 @@ pub enum Event {
 }
 
 /// A room event or state event.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum RoomEvent {
     /// m.call.answer
     CallAnswer(AnswerEvent),
@@ -101,6 +147,20 @@ pub enum RoomEvent {
     CallHangup(HangupEvent),
     /// m.call.invite
     CallInvite(InviteEvent),
+    /// m.call.member
+    CallMember(CallMemberEvent),
+    /// m.key.verification.done
+    KeyVerificationDone(DoneRoomEvent),
+    /// m.key.verification.ready
+    KeyVerificationReady(ReadyRoomEvent),
+    /// m.poll.end
+    PollEnd(EndEvent),
+    /// m.poll.response
+    PollResponse(ResponseEvent),
+    /// m.poll.start
+    PollStart(StartEvent),
+    /// m.reaction
+    Reaction(ReactionEvent),
     /// m.room.aliases
     RoomAliases(AliasesEvent),
     /// m.room.avatar
@@ -109,6 +169,8 @@ pub enum RoomEvent {
     RoomCanonicalAlias(CanonicalAliasEvent),
     /// m.room.create
     RoomCreate(CreateEvent),
+    /// m.room.encrypted
+    RoomEncrypted(EncryptedEvent),
     /// m.room.guest_access
     RoomGuestAccess(GuestAccessEvent),
     /// m.room.history_visibility
@@ -127,19 +189,99 @@ pub enum RoomEvent {
     RoomPowerLevels(PowerLevelsEvent),
     /// m.room.redaction
     RoomRedaction(RedactionEvent),
+    /// m.room.server_acl
+    RoomServerAcl(ServerAclEvent),
     /// m.room.third_party_invite
     RoomThirdPartyInvite(ThirdPartyInviteEvent),
     /// m.room.topic
     RoomTopic(TopicEvent),
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+    /// m.voice_broadcast_info
+    VoiceBroadcastInfo(VoiceBroadcastInfoEvent),
     /// Any room event that is not part of the specification.
     CustomRoom(CustomRoomEvent),
     /// Any state event that is not part of the specification.
     CustomState(CustomStateEvent),
 }
 
+/// A borrowed room event or state event.
+///
+/// This is returned by `Event::as_room_event` to inspect the room-specific fields of an event
+/// without taking ownership of it.
+#[derive(Clone, Copy, Debug)]
+pub enum RoomEventRef<'a> {
+    /// m.call.answer
+    CallAnswer(&'a AnswerEvent),
+    /// m.call.candidates
+    CallCandidates(&'a CandidatesEvent),
+    /// m.call.hangup
+    CallHangup(&'a HangupEvent),
+    /// m.call.invite
+    CallInvite(&'a InviteEvent),
+    /// m.call.member
+    CallMember(&'a CallMemberEvent),
+    /// m.key.verification.done
+    KeyVerificationDone(&'a DoneRoomEvent),
+    /// m.key.verification.ready
+    KeyVerificationReady(&'a ReadyRoomEvent),
+    /// m.poll.end
+    PollEnd(&'a EndEvent),
+    /// m.poll.response
+    PollResponse(&'a ResponseEvent),
+    /// m.poll.start
+    PollStart(&'a StartEvent),
+    /// m.reaction
+    Reaction(&'a ReactionEvent),
+    /// m.room.aliases
+    RoomAliases(&'a AliasesEvent),
+    /// m.room.avatar
+    RoomAvatar(&'a AvatarEvent),
+    /// m.room.canonical_alias
+    RoomCanonicalAlias(&'a CanonicalAliasEvent),
+    /// m.room.create
+    RoomCreate(&'a CreateEvent),
+    /// m.room.encrypted
+    RoomEncrypted(&'a EncryptedEvent),
+    /// m.room.guest_access
+    RoomGuestAccess(&'a GuestAccessEvent),
+    /// m.room.history_visibility
+    RoomHistoryVisibility(&'a HistoryVisibilityEvent),
+    /// m.room.join_rules
+    RoomJoinRules(&'a JoinRulesEvent),
+    /// m.room.member
+    RoomMember(&'a MemberEvent),
+    /// m.room.message
+    RoomMessage(&'a MessageEvent),
+    /// m.room.name
+    RoomName(&'a NameEvent),
+    /// m.room.pinned_events
+    RoomPinnedEvents(&'a PinnedEventsEvent),
+    /// m.room.power_levels
+    RoomPowerLevels(&'a PowerLevelsEvent),
+    /// m.room.redaction
+    RoomRedaction(&'a RedactionEvent),
+    /// m.room.server_acl
+    RoomServerAcl(&'a ServerAclEvent),
+    /// m.room.third_party_invite
+    RoomThirdPartyInvite(&'a ThirdPartyInviteEvent),
+    /// m.room.topic
+    RoomTopic(&'a TopicEvent),
+    /// m.space.child
+    SpaceChild(&'a SpaceChildEvent),
+    /// m.voice_broadcast_info
+    VoiceBroadcastInfo(&'a VoiceBroadcastInfoEvent),
+    /// Any room event that is not part of the specification.
+    CustomRoom(&'a CustomRoomEvent),
+    /// Any state event that is not part of the specification.
+    CustomState(&'a CustomStateEvent),
+}
+
 /// A state event.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum StateEvent {
+    /// m.call.member
+    CallMember(CallMemberEvent),
     /// m.room.aliases
     RoomAliases(AliasesEvent),
     /// m.room.avatar
@@ -162,14 +304,195 @@ pub enum StateEvent {
     RoomPinnedEvents(PinnedEventsEvent),
     /// m.room.power_levels
     RoomPowerLevels(PowerLevelsEvent),
+    /// m.room.server_acl
+    RoomServerAcl(ServerAclEvent),
     /// m.room.third_party_invite
     RoomThirdPartyInvite(ThirdPartyInviteEvent),
     /// m.room.topic
     RoomTopic(TopicEvent),
+    /// m.space.child
+    SpaceChild(SpaceChildEvent),
+    /// m.voice_broadcast_info
+    VoiceBroadcastInfo(VoiceBroadcastInfoEvent),
     /// Any state event that is not part of the specification.
     CustomState(CustomStateEvent),
 }
 
+impl Event {
+    /// Borrows this event as a room event, if it is one.
+    pub fn as_room_event(&self) -> Option<RoomEventRef<'_>> {
+        match *self {
+            Event::CallAnswer(ref event) => Some(RoomEventRef::CallAnswer(event)),
+            Event::CallCandidates(ref event) => Some(RoomEventRef::CallCandidates(event)),
+            Event::CallHangup(ref event) => Some(RoomEventRef::CallHangup(event)),
+            Event::CallInvite(ref event) => Some(RoomEventRef::CallInvite(event)),
+            Event::CallMember(ref event) => Some(RoomEventRef::CallMember(event)),
+            Event::KeyVerificationDone(ref event) => {
+                Some(RoomEventRef::KeyVerificationDone(event))
+            }
+            Event::KeyVerificationReady(ref event) => {
+                Some(RoomEventRef::KeyVerificationReady(event))
+            }
+            Event::PollEnd(ref event) => Some(RoomEventRef::PollEnd(event)),
+            Event::PollResponse(ref event) => Some(RoomEventRef::PollResponse(event)),
+            Event::PollStart(ref event) => Some(RoomEventRef::PollStart(event)),
+            Event::Reaction(ref event) => Some(RoomEventRef::Reaction(event)),
+            Event::RoomAliases(ref event) => Some(RoomEventRef::RoomAliases(event)),
+            Event::RoomAvatar(ref event) => Some(RoomEventRef::RoomAvatar(event)),
+            Event::RoomCanonicalAlias(ref event) => Some(RoomEventRef::RoomCanonicalAlias(event)),
+            Event::RoomCreate(ref event) => Some(RoomEventRef::RoomCreate(event)),
+            Event::RoomEncrypted(ref event) => Some(RoomEventRef::RoomEncrypted(event)),
+            Event::RoomGuestAccess(ref event) => Some(RoomEventRef::RoomGuestAccess(event)),
+            Event::RoomHistoryVisibility(ref event) => {
+                Some(RoomEventRef::RoomHistoryVisibility(event))
+            }
+            Event::RoomJoinRules(ref event) => Some(RoomEventRef::RoomJoinRules(event)),
+            Event::RoomMember(ref event) => Some(RoomEventRef::RoomMember(event)),
+            Event::RoomMessage(ref event) => Some(RoomEventRef::RoomMessage(event)),
+            Event::RoomName(ref event) => Some(RoomEventRef::RoomName(event)),
+            Event::RoomPinnedEvents(ref event) => Some(RoomEventRef::RoomPinnedEvents(event)),
+            Event::RoomPowerLevels(ref event) => Some(RoomEventRef::RoomPowerLevels(event)),
+            Event::RoomRedaction(ref event) => Some(RoomEventRef::RoomRedaction(event)),
+            Event::RoomThirdPartyInvite(ref event) => {
+                Some(RoomEventRef::RoomThirdPartyInvite(event))
+            }
+            Event::RoomServerAcl(ref event) => Some(RoomEventRef::RoomServerAcl(event)),
+            Event::RoomTopic(ref event) => Some(RoomEventRef::RoomTopic(event)),
+            Event::SpaceChild(ref event) => Some(RoomEventRef::SpaceChild(event)),
+            Event::VoiceBroadcastInfo(ref event) => Some(RoomEventRef::VoiceBroadcastInfo(event)),
+            Event::CustomRoom(ref event) => Some(RoomEventRef::CustomRoom(event)),
+            Event::CustomState(ref event) => Some(RoomEventRef::CustomState(event)),
+            Event::Direct(_)
+            | Event::KeyVerificationAccept(_)
+            | Event::KeyVerificationKey(_)
+            | Event::KeyVerificationMac(_)
+            | Event::Presence(_)
+            | Event::Receipt(_)
+            | Event::Tag(_)
+            | Event::Typing(_)
+            | Event::Custom(_) => None,
+        }
+    }
+
+    /// The type of the event.
+    pub fn event_type(&self) -> &EventType {
+        match *self {
+            Event::CallAnswer(ref event) => &event.event_type,
+            Event::CallCandidates(ref event) => &event.event_type,
+            Event::CallHangup(ref event) => &event.event_type,
+            Event::CallInvite(ref event) => &event.event_type,
+            Event::CallMember(ref event) => &event.event_type,
+            Event::Direct(ref event) => &event.event_type,
+            Event::KeyVerificationAccept(ref event) => &event.event_type,
+            Event::KeyVerificationDone(ref event) => &event.event_type,
+            Event::KeyVerificationKey(ref event) => &event.event_type,
+            Event::KeyVerificationMac(ref event) => &event.event_type,
+            Event::KeyVerificationReady(ref event) => &event.event_type,
+            Event::PollEnd(ref event) => &event.event_type,
+            Event::PollResponse(ref event) => &event.event_type,
+            Event::PollStart(ref event) => &event.event_type,
+            Event::Presence(ref event) => &event.event_type,
+            Event::Reaction(ref event) => &event.event_type,
+            Event::Receipt(ref event) => &event.event_type,
+            Event::RoomAliases(ref event) => &event.event_type,
+            Event::RoomAvatar(ref event) => &event.event_type,
+            Event::RoomCanonicalAlias(ref event) => &event.event_type,
+            Event::RoomCreate(ref event) => &event.event_type,
+            Event::RoomEncrypted(ref event) => &event.event_type,
+            Event::RoomGuestAccess(ref event) => &event.event_type,
+            Event::RoomHistoryVisibility(ref event) => &event.event_type,
+            Event::RoomJoinRules(ref event) => &event.event_type,
+            Event::RoomMember(ref event) => &event.event_type,
+            Event::RoomMessage(ref event) => &event.event_type,
+            Event::RoomName(ref event) => &event.event_type,
+            Event::RoomPinnedEvents(ref event) => &event.event_type,
+            Event::RoomPowerLevels(ref event) => &event.event_type,
+            Event::RoomRedaction(ref event) => &event.event_type,
+            Event::RoomServerAcl(ref event) => &event.event_type,
+            Event::RoomThirdPartyInvite(ref event) => &event.event_type,
+            Event::RoomTopic(ref event) => &event.event_type,
+            Event::SpaceChild(ref event) => &event.event_type,
+            Event::Tag(ref event) => &event.event_type,
+            Event::Typing(ref event) => &event.event_type,
+            Event::VoiceBroadcastInfo(ref event) => &event.event_type,
+            Event::Custom(ref event) => &event.event_type,
+            Event::CustomRoom(ref event) => &event.event_type,
+            Event::CustomState(ref event) => &event.event_type,
+        }
+    }
+}
+
+macro_rules! dispatch_room_event_ref {
+    ($self:expr, $event:ident => $body:expr) => {
+        match $self {
+            RoomEventRef::CallAnswer($event) => $body,
+            RoomEventRef::CallCandidates($event) => $body,
+            RoomEventRef::CallHangup($event) => $body,
+            RoomEventRef::CallInvite($event) => $body,
+            RoomEventRef::CallMember($event) => $body,
+            RoomEventRef::KeyVerificationDone($event) => $body,
+            RoomEventRef::KeyVerificationReady($event) => $body,
+            RoomEventRef::PollEnd($event) => $body,
+            RoomEventRef::PollResponse($event) => $body,
+            RoomEventRef::PollStart($event) => $body,
+            RoomEventRef::Reaction($event) => $body,
+            RoomEventRef::RoomAliases($event) => $body,
+            RoomEventRef::RoomAvatar($event) => $body,
+            RoomEventRef::RoomCanonicalAlias($event) => $body,
+            RoomEventRef::RoomCreate($event) => $body,
+            RoomEventRef::RoomEncrypted($event) => $body,
+            RoomEventRef::RoomGuestAccess($event) => $body,
+            RoomEventRef::RoomHistoryVisibility($event) => $body,
+            RoomEventRef::RoomJoinRules($event) => $body,
+            RoomEventRef::RoomMember($event) => $body,
+            RoomEventRef::RoomMessage($event) => $body,
+            RoomEventRef::RoomName($event) => $body,
+            RoomEventRef::RoomPinnedEvents($event) => $body,
+            RoomEventRef::RoomPowerLevels($event) => $body,
+            RoomEventRef::RoomRedaction($event) => $body,
+            RoomEventRef::RoomServerAcl($event) => $body,
+            RoomEventRef::RoomThirdPartyInvite($event) => $body,
+            RoomEventRef::RoomTopic($event) => $body,
+            RoomEventRef::SpaceChild($event) => $body,
+            RoomEventRef::VoiceBroadcastInfo($event) => $body,
+            RoomEventRef::CustomRoom($event) => $body,
+            RoomEventRef::CustomState($event) => $body,
+        }
+    };
+}
+
+impl<'a> RoomEventRef<'a> {
+    /// The unique identifier for the event.
+    pub fn event_id(self) -> &'a EventId {
+        dispatch_room_event_ref!(self, event => &event.event_id)
+    }
+
+    /// The type of the event.
+    pub fn event_type(self) -> &'a EventType {
+        dispatch_room_event_ref!(self, event => &event.event_type)
+    }
+
+    /// Timestamp in milliseconds on originating homeserver when this event was sent.
+    pub fn origin_server_ts(self) -> u64 {
+        dispatch_room_event_ref!(self, event => event.origin_server_ts)
+    }
+
+    /// The unique identifier for the room associated with this event.
+    pub fn room_id(self) -> Option<&'a RoomId> {
+        dispatch_room_event_ref!(self, event => event.room_id.as_ref())
+    }
+
+    /// The unique identifier for the user who sent this event.
+    pub fn sender(self) -> &'a UserId {
+        dispatch_room_event_ref!(self, event => &event.sender)
+    }
+
+    /// Additional key-value pairs not signed by the homeserver.
+    pub fn unsigned(self) -> Option<&'a Value> {
+        dispatch_room_event_ref!(self, event => event.unsigned.as_ref())
+    }
+}
+
 impl Serialize for Event {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -180,13 +503,24 @@ impl Serialize for Event {
             Event::CallCandidates(ref event) => event.serialize(serializer),
             Event::CallHangup(ref event) => event.serialize(serializer),
             Event::CallInvite(ref event) => event.serialize(serializer),
+            Event::CallMember(ref event) => event.serialize(serializer),
             Event::Direct(ref event) => event.serialize(serializer),
+            Event::KeyVerificationAccept(ref event) => event.serialize(serializer),
+            Event::KeyVerificationDone(ref event) => event.serialize(serializer),
+            Event::KeyVerificationKey(ref event) => event.serialize(serializer),
+            Event::KeyVerificationMac(ref event) => event.serialize(serializer),
+            Event::KeyVerificationReady(ref event) => event.serialize(serializer),
+            Event::PollEnd(ref event) => event.serialize(serializer),
+            Event::PollResponse(ref event) => event.serialize(serializer),
+            Event::PollStart(ref event) => event.serialize(serializer),
             Event::Presence(ref event) => event.serialize(serializer),
+            Event::Reaction(ref event) => event.serialize(serializer),
             Event::Receipt(ref event) => event.serialize(serializer),
             Event::RoomAliases(ref event) => event.serialize(serializer),
             Event::RoomAvatar(ref event) => event.serialize(serializer),
             Event::RoomCanonicalAlias(ref event) => event.serialize(serializer),
             Event::RoomCreate(ref event) => event.serialize(serializer),
+            Event::RoomEncrypted(ref event) => event.serialize(serializer),
             Event::RoomGuestAccess(ref event) => event.serialize(serializer),
             Event::RoomHistoryVisibility(ref event) => event.serialize(serializer),
             Event::RoomJoinRules(ref event) => event.serialize(serializer),
@@ -196,10 +530,13 @@ impl Serialize for Event {
             Event::RoomPinnedEvents(ref event) => event.serialize(serializer),
             Event::RoomPowerLevels(ref event) => event.serialize(serializer),
             Event::RoomRedaction(ref event) => event.serialize(serializer),
+            Event::RoomServerAcl(ref event) => event.serialize(serializer),
             Event::RoomThirdPartyInvite(ref event) => event.serialize(serializer),
             Event::RoomTopic(ref event) => event.serialize(serializer),
+            Event::SpaceChild(ref event) => event.serialize(serializer),
             Event::Tag(ref event) => event.serialize(serializer),
             Event::Typing(ref event) => event.serialize(serializer),
+            Event::VoiceBroadcastInfo(ref event) => event.serialize(serializer),
             Event::Custom(ref event) => event.serialize(serializer),
             Event::CustomRoom(ref event) => event.serialize(serializer),
             Event::CustomState(ref event) => event.serialize(serializer),
@@ -257,6 +594,14 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::CallInvite(event))
             }
+            EventType::CallMember => {
+                let event = match from_value::<CallMemberEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::CallMember(event))
+            }
             EventType::Direct => {
                 let event = match from_value::<DirectEvent>(value) {
                     Ok(event) => event,
@@ -265,6 +610,70 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::Direct(event))
             }
+            EventType::KeyVerificationAccept => {
+                let event = match from_value::<AcceptEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationAccept(event))
+            }
+            EventType::KeyVerificationDone => {
+                let event = match from_value::<DoneRoomEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationDone(event))
+            }
+            EventType::KeyVerificationKey => {
+                let event = match from_value::<KeyEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationKey(event))
+            }
+            EventType::KeyVerificationMac => {
+                let event = match from_value::<MacEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationMac(event))
+            }
+            EventType::KeyVerificationReady => {
+                let event = match from_value::<ReadyRoomEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationReady(event))
+            }
+            EventType::PollEnd => {
+                let event = match from_value::<EndEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::PollEnd(event))
+            }
+            EventType::PollResponse => {
+                let event = match from_value::<ResponseEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::PollResponse(event))
+            }
+            EventType::PollStart => {
+                let event = match from_value::<StartEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::PollStart(event))
+            }
             EventType::Presence => {
                 let event = match from_value::<PresenceEvent>(value) {
                     Ok(event) => event,
@@ -273,6 +682,14 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::Presence(event))
             }
+            EventType::Reaction => {
+                let event = match from_value::<ReactionEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::Reaction(event))
+            }
             EventType::Receipt => {
                 let event = match from_value::<ReceiptEvent>(value) {
                     Ok(event) => event,
@@ -313,6 +730,14 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::RoomCreate(event))
             }
+            EventType::RoomEncrypted => {
+                let event = match from_value::<EncryptedEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::RoomEncrypted(event))
+            }
             EventType::RoomGuestAccess => {
                 let event = match from_value::<GuestAccessEvent>(value) {
                     Ok(event) => event,
@@ -385,6 +810,14 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::RoomRedaction(event))
             }
+            EventType::RoomServerAcl => {
+                let event = match from_value::<ServerAclEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::RoomServerAcl(event))
+            }
             EventType::RoomThirdPartyInvite => {
                 let event = match from_value::<ThirdPartyInviteEvent>(value) {
                     Ok(event) => event,
@@ -401,6 +834,14 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::RoomTopic(event))
             }
+            EventType::SpaceChild => {
+                let event = match from_value::<SpaceChildEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::SpaceChild(event))
+            }
             EventType::Tag => {
                 let event = match from_value::<TagEvent>(value) {
                     Ok(event) => event,
@@ -417,6 +858,14 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::Typing(event))
             }
+            EventType::VoiceBroadcastInfo => {
+                let event = match from_value::<VoiceBroadcastInfoEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::VoiceBroadcastInfo(event))
+            }
             EventType::Custom(_) => {
                 if value.get("state_key").is_some() {
                     let event = match from_value::<CustomStateEvent>(value) {
@@ -457,10 +906,18 @@ impl Serialize for RoomEvent {
             RoomEvent::CallCandidates(ref event) => event.serialize(serializer),
             RoomEvent::CallHangup(ref event) => event.serialize(serializer),
             RoomEvent::CallInvite(ref event) => event.serialize(serializer),
+            RoomEvent::CallMember(ref event) => event.serialize(serializer),
+            RoomEvent::KeyVerificationDone(ref event) => event.serialize(serializer),
+            RoomEvent::KeyVerificationReady(ref event) => event.serialize(serializer),
+            RoomEvent::PollEnd(ref event) => event.serialize(serializer),
+            RoomEvent::PollResponse(ref event) => event.serialize(serializer),
+            RoomEvent::PollStart(ref event) => event.serialize(serializer),
+            RoomEvent::Reaction(ref event) => event.serialize(serializer),
             RoomEvent::RoomAliases(ref event) => event.serialize(serializer),
             RoomEvent::RoomAvatar(ref event) => event.serialize(serializer),
             RoomEvent::RoomCanonicalAlias(ref event) => event.serialize(serializer),
             RoomEvent::RoomCreate(ref event) => event.serialize(serializer),
+            RoomEvent::RoomEncrypted(ref event) => event.serialize(serializer),
             RoomEvent::RoomGuestAccess(ref event) => event.serialize(serializer),
             RoomEvent::RoomHistoryVisibility(ref event) => event.serialize(serializer),
             RoomEvent::RoomJoinRules(ref event) => event.serialize(serializer),
@@ -470,8 +927,11 @@ impl Serialize for RoomEvent {
             RoomEvent::RoomPinnedEvents(ref event) => event.serialize(serializer),
             RoomEvent::RoomPowerLevels(ref event) => event.serialize(serializer),
             RoomEvent::RoomRedaction(ref event) => event.serialize(serializer),
+            RoomEvent::RoomServerAcl(ref event) => event.serialize(serializer),
             RoomEvent::RoomThirdPartyInvite(ref event) => event.serialize(serializer),
             RoomEvent::RoomTopic(ref event) => event.serialize(serializer),
+            RoomEvent::SpaceChild(ref event) => event.serialize(serializer),
+            RoomEvent::VoiceBroadcastInfo(ref event) => event.serialize(serializer),
             RoomEvent::CustomRoom(ref event) => event.serialize(serializer),
             RoomEvent::CustomState(ref event) => event.serialize(serializer),
         }
@@ -528,6 +988,62 @@ impl<'de> Deserialize<'de> for RoomEvent {
 
                 Ok(RoomEvent::CallInvite(event))
             }
+            EventType::CallMember => {
+                let event = match from_value::<CallMemberEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::CallMember(event))
+            }
+            EventType::KeyVerificationDone => {
+                let event = match from_value::<DoneRoomEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::KeyVerificationDone(event))
+            }
+            EventType::KeyVerificationReady => {
+                let event = match from_value::<ReadyRoomEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::KeyVerificationReady(event))
+            }
+            EventType::PollEnd => {
+                let event = match from_value::<EndEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::PollEnd(event))
+            }
+            EventType::PollResponse => {
+                let event = match from_value::<ResponseEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::PollResponse(event))
+            }
+            EventType::PollStart => {
+                let event = match from_value::<StartEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::PollStart(event))
+            }
+            EventType::Reaction => {
+                let event = match from_value::<ReactionEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::Reaction(event))
+            }
             EventType::RoomAliases => {
                 let event = match from_value::<AliasesEvent>(value) {
                     Ok(event) => event,
@@ -560,6 +1076,14 @@ impl<'de> Deserialize<'de> for RoomEvent {
 
                 Ok(RoomEvent::RoomCreate(event))
             }
+            EventType::RoomEncrypted => {
+                let event = match from_value::<EncryptedEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::RoomEncrypted(event))
+            }
             EventType::RoomGuestAccess => {
                 let event = match from_value::<GuestAccessEvent>(value) {
                     Ok(event) => event,
@@ -632,6 +1156,14 @@ impl<'de> Deserialize<'de> for RoomEvent {
 
                 Ok(RoomEvent::RoomRedaction(event))
             }
+            EventType::RoomServerAcl => {
+                let event = match from_value::<ServerAclEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::RoomServerAcl(event))
+            }
             EventType::RoomThirdPartyInvite => {
                 let event = match from_value::<ThirdPartyInviteEvent>(value) {
                     Ok(event) => event,
@@ -648,6 +1180,22 @@ impl<'de> Deserialize<'de> for RoomEvent {
 
                 Ok(RoomEvent::RoomTopic(event))
             }
+            EventType::SpaceChild => {
+                let event = match from_value::<SpaceChildEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::SpaceChild(event))
+            }
+            EventType::VoiceBroadcastInfo => {
+                let event = match from_value::<VoiceBroadcastInfoEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::VoiceBroadcastInfo(event))
+            }
             EventType::Custom(_) => {
                 if value.get("state_key").is_some() {
                     let event = match from_value::<CustomStateEvent>(value) {
@@ -666,6 +1214,9 @@ impl<'de> Deserialize<'de> for RoomEvent {
                 }
             }
             EventType::Direct
+            | EventType::KeyVerificationAccept
+            | EventType::KeyVerificationKey
+            | EventType::KeyVerificationMac
             | EventType::Presence
             | EventType::Receipt
             | EventType::Tag
@@ -682,6 +1233,7 @@ impl Serialize for StateEvent {
         S: Serializer,
     {
         match *self {
+            StateEvent::CallMember(ref event) => event.serialize(serializer),
             StateEvent::RoomAliases(ref event) => event.serialize(serializer),
             StateEvent::RoomAvatar(ref event) => event.serialize(serializer),
             StateEvent::RoomCanonicalAlias(ref event) => event.serialize(serializer),
@@ -693,8 +1245,11 @@ impl Serialize for StateEvent {
             StateEvent::RoomName(ref event) => event.serialize(serializer),
             StateEvent::RoomPinnedEvents(ref event) => event.serialize(serializer),
             StateEvent::RoomPowerLevels(ref event) => event.serialize(serializer),
+            StateEvent::RoomServerAcl(ref event) => event.serialize(serializer),
             StateEvent::RoomThirdPartyInvite(ref event) => event.serialize(serializer),
             StateEvent::RoomTopic(ref event) => event.serialize(serializer),
+            StateEvent::SpaceChild(ref event) => event.serialize(serializer),
+            StateEvent::VoiceBroadcastInfo(ref event) => event.serialize(serializer),
             StateEvent::CustomState(ref event) => event.serialize(serializer),
         }
     }
@@ -718,6 +1273,14 @@ impl<'de> Deserialize<'de> for StateEvent {
         };
 
         match event_type {
+            EventType::CallMember => {
+                let event = match from_value::<CallMemberEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(StateEvent::CallMember(event))
+            }
             EventType::RoomAliases => {
                 let event = match from_value::<AliasesEvent>(value) {
                     Ok(event) => event,
@@ -806,6 +1369,14 @@ impl<'de> Deserialize<'de> for StateEvent {
 
                 Ok(StateEvent::RoomPowerLevels(event))
             }
+            EventType::RoomServerAcl => {
+                let event = match from_value::<ServerAclEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(StateEvent::RoomServerAcl(event))
+            }
             EventType::RoomThirdPartyInvite => {
                 let event = match from_value::<ThirdPartyInviteEvent>(value) {
                     Ok(event) => event,
@@ -822,6 +1393,22 @@ impl<'de> Deserialize<'de> for StateEvent {
 
                 Ok(StateEvent::RoomTopic(event))
             }
+            EventType::SpaceChild => {
+                let event = match from_value::<SpaceChildEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(StateEvent::SpaceChild(event))
+            }
+            EventType::VoiceBroadcastInfo => {
+                let event = match from_value::<VoiceBroadcastInfoEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(StateEvent::VoiceBroadcastInfo(event))
+            }
             EventType::Custom(_) => {
                 let event = match from_value::<CustomStateEvent>(value) {
                     Ok(event) => event,
@@ -835,8 +1422,18 @@ impl<'de> Deserialize<'de> for StateEvent {
             | EventType::CallHangup
             | EventType::CallInvite
             | EventType::Direct
+            | EventType::KeyVerificationAccept
+            | EventType::KeyVerificationDone
+            | EventType::KeyVerificationKey
+            | EventType::KeyVerificationMac
+            | EventType::KeyVerificationReady
+            | EventType::PollEnd
+            | EventType::PollResponse
+            | EventType::PollStart
             | EventType::Presence
+            | EventType::Reaction
             | EventType::Receipt
+            | EventType::RoomEncrypted
             | EventType::RoomMessage
             | EventType::RoomRedaction
             | EventType::Tag
@@ -861,13 +1458,24 @@ impl_from_t_for_event!(AnswerEvent, CallAnswer);
 impl_from_t_for_event!(CandidatesEvent, CallCandidates);
 impl_from_t_for_event!(HangupEvent, CallHangup);
 impl_from_t_for_event!(InviteEvent, CallInvite);
+impl_from_t_for_event!(CallMemberEvent, CallMember);
 impl_from_t_for_event!(DirectEvent, Direct);
+impl_from_t_for_event!(AcceptEvent, KeyVerificationAccept);
+impl_from_t_for_event!(DoneRoomEvent, KeyVerificationDone);
+impl_from_t_for_event!(KeyEvent, KeyVerificationKey);
+impl_from_t_for_event!(MacEvent, KeyVerificationMac);
+impl_from_t_for_event!(ReadyRoomEvent, KeyVerificationReady);
+impl_from_t_for_event!(EndEvent, PollEnd);
+impl_from_t_for_event!(ResponseEvent, PollResponse);
+impl_from_t_for_event!(StartEvent, PollStart);
 impl_from_t_for_event!(PresenceEvent, Presence);
+impl_from_t_for_event!(ReactionEvent, Reaction);
 impl_from_t_for_event!(ReceiptEvent, Receipt);
 impl_from_t_for_event!(AliasesEvent, RoomAliases);
 impl_from_t_for_event!(AvatarEvent, RoomAvatar);
 impl_from_t_for_event!(CanonicalAliasEvent, RoomCanonicalAlias);
 impl_from_t_for_event!(CreateEvent, RoomCreate);
+impl_from_t_for_event!(EncryptedEvent, RoomEncrypted);
 impl_from_t_for_event!(GuestAccessEvent, RoomGuestAccess);
 impl_from_t_for_event!(HistoryVisibilityEvent, RoomHistoryVisibility);
 impl_from_t_for_event!(JoinRulesEvent, RoomJoinRules);
@@ -877,10 +1485,13 @@ impl_from_t_for_event!(NameEvent, RoomName);
 impl_from_t_for_event!(PinnedEventsEvent, RoomPinnedEvents);
 impl_from_t_for_event!(PowerLevelsEvent, RoomPowerLevels);
 impl_from_t_for_event!(RedactionEvent, RoomRedaction);
+impl_from_t_for_event!(ServerAclEvent, RoomServerAcl);
 impl_from_t_for_event!(ThirdPartyInviteEvent, RoomThirdPartyInvite);
 impl_from_t_for_event!(TopicEvent, RoomTopic);
+impl_from_t_for_event!(SpaceChildEvent, SpaceChild);
 impl_from_t_for_event!(TagEvent, Tag);
 impl_from_t_for_event!(TypingEvent, Typing);
+impl_from_t_for_event!(VoiceBroadcastInfoEvent, VoiceBroadcastInfo);
 impl_from_t_for_event!(CustomEvent, Custom);
 impl_from_t_for_event!(CustomRoomEvent, CustomRoom);
 impl_from_t_for_event!(CustomStateEvent, CustomState);
@@ -899,10 +1510,18 @@ impl_from_t_for_room_event!(AnswerEvent, CallAnswer);
 impl_from_t_for_room_event!(CandidatesEvent, CallCandidates);
 impl_from_t_for_room_event!(HangupEvent, CallHangup);
 impl_from_t_for_room_event!(InviteEvent, CallInvite);
+impl_from_t_for_room_event!(CallMemberEvent, CallMember);
+impl_from_t_for_room_event!(DoneRoomEvent, KeyVerificationDone);
+impl_from_t_for_room_event!(ReadyRoomEvent, KeyVerificationReady);
+impl_from_t_for_room_event!(EndEvent, PollEnd);
+impl_from_t_for_room_event!(ResponseEvent, PollResponse);
+impl_from_t_for_room_event!(StartEvent, PollStart);
+impl_from_t_for_room_event!(ReactionEvent, Reaction);
 impl_from_t_for_room_event!(AliasesEvent, RoomAliases);
 impl_from_t_for_room_event!(AvatarEvent, RoomAvatar);
 impl_from_t_for_room_event!(CanonicalAliasEvent, RoomCanonicalAlias);
 impl_from_t_for_room_event!(CreateEvent, RoomCreate);
+impl_from_t_for_room_event!(EncryptedEvent, RoomEncrypted);
 impl_from_t_for_room_event!(GuestAccessEvent, RoomGuestAccess);
 impl_from_t_for_room_event!(HistoryVisibilityEvent, RoomHistoryVisibility);
 impl_from_t_for_room_event!(JoinRulesEvent, RoomJoinRules);
@@ -912,8 +1531,11 @@ impl_from_t_for_room_event!(NameEvent, RoomName);
 impl_from_t_for_room_event!(PinnedEventsEvent, RoomPinnedEvents);
 impl_from_t_for_room_event!(PowerLevelsEvent, RoomPowerLevels);
 impl_from_t_for_room_event!(RedactionEvent, RoomRedaction);
+impl_from_t_for_room_event!(ServerAclEvent, RoomServerAcl);
 impl_from_t_for_room_event!(ThirdPartyInviteEvent, RoomThirdPartyInvite);
 impl_from_t_for_room_event!(TopicEvent, RoomTopic);
+impl_from_t_for_room_event!(SpaceChildEvent, SpaceChild);
+impl_from_t_for_room_event!(VoiceBroadcastInfoEvent, VoiceBroadcastInfo);
 impl_from_t_for_room_event!(CustomRoomEvent, CustomRoom);
 impl_from_t_for_room_event!(CustomStateEvent, CustomState);
 
@@ -927,6 +1549,7 @@ macro_rules! impl_from_t_for_state_event {
     };
 }
 
+impl_from_t_for_state_event!(CallMemberEvent, CallMember);
 impl_from_t_for_state_event!(AliasesEvent, RoomAliases);
 impl_from_t_for_state_event!(AvatarEvent, RoomAvatar);
 impl_from_t_for_state_event!(CanonicalAliasEvent, RoomCanonicalAlias);
@@ -938,6 +1561,246 @@ impl_from_t_for_state_event!(MemberEvent, RoomMember);
 impl_from_t_for_state_event!(NameEvent, RoomName);
 impl_from_t_for_state_event!(PinnedEventsEvent, RoomPinnedEvents);
 impl_from_t_for_state_event!(PowerLevelsEvent, RoomPowerLevels);
+impl_from_t_for_state_event!(ServerAclEvent, RoomServerAcl);
 impl_from_t_for_state_event!(ThirdPartyInviteEvent, RoomThirdPartyInvite);
 impl_from_t_for_state_event!(TopicEvent, RoomTopic);
+impl_from_t_for_state_event!(SpaceChildEvent, SpaceChild);
+impl_from_t_for_state_event!(VoiceBroadcastInfoEvent, VoiceBroadcastInfo);
 impl_from_t_for_state_event!(CustomStateEvent, CustomState);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, from_value, to_value, Value};
+
+    use super::{Event, RoomEvent, RoomEventRef, StateEvent};
+    use EventType;
+
+    assert_impl_all!(Event: Clone);
+    assert_impl_all!(RoomEvent: Clone);
+    assert_impl_all!(StateEvent: Clone);
+
+    #[test]
+    fn as_room_event_borrows_room_events() {
+        let event = from_str::<Event>(
+            r#"{
+                "content": {"name": "Ruma"},
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.name"
+            }"#,
+        )
+        .unwrap();
+
+        match event.as_room_event() {
+            Some(room_event @ RoomEventRef::RoomName(_)) => {
+                assert_eq!(room_event.event_id().to_string(), "$h29iv0s8:example.com");
+                assert_eq!(room_event.event_type(), &EventType::RoomName);
+                assert_eq!(room_event.origin_server_ts(), 1);
+                assert_eq!(room_event.room_id(), None);
+                assert_eq!(room_event.sender().to_string(), "@carl:example.com");
+            }
+            _ => panic!("expected a borrowed m.room.name event"),
+        }
+    }
+
+    #[test]
+    fn as_room_event_skips_basic_events() {
+        let event =
+            from_str::<Event>(r#"{"content": {"user_ids": []}, "type": "m.typing"}"#).unwrap();
+
+        assert!(event.as_room_event().is_none());
+    }
+
+    #[test]
+    fn in_room_verification_events_round_trip() {
+        let relates_to = r#""m.relates_to":{"event_id":"$request:example.com","rel_type":"m.reference"}"#;
+        let ready = room_event(
+            "m.key.verification.ready",
+            &format!(
+                r#"{{"from_device":"ABCDEFG",{},"methods":["m.sas.v1"]}}"#,
+                relates_to
+            ),
+            None,
+        );
+        let done = room_event(
+            "m.key.verification.done",
+            &format!("{{{}}}", relates_to),
+            None,
+        );
+
+        for json in &[ready, done] {
+            let value = from_str::<Value>(json).unwrap();
+            let event = from_value::<RoomEvent>(value.clone()).unwrap();
+
+            match event {
+                RoomEvent::KeyVerificationDone(_) | RoomEvent::KeyVerificationReady(_) => {}
+                _ => panic!("expected an in-room verification event: {}", json),
+            }
+            assert_eq!(to_value(&event).unwrap(), value, "{}", json);
+
+            let event = from_value::<Event>(value.clone()).unwrap();
+
+            assert_eq!(
+                event.as_room_event().unwrap().event_id().to_string(),
+                "$h29iv0s8:example.com"
+            );
+            assert_eq!(to_value(&event).unwrap(), value, "{}", json);
+        }
+    }
+
+    fn room_event(event_type: &str, content: &str, state_key: Option<&str>) -> String {
+        let state_key = match state_key {
+            Some(state_key) => format!(r#""state_key":"{}","#, state_key),
+            None => String::new(),
+        };
+
+        format!(
+            r#"{{"content":{},"event_id":"$h29iv0s8:example.com","origin_server_ts":1,"room_id":"!n8f893n9:example.com","sender":"@carl:example.com",{}"type":"{}"}}"#,
+            content, state_key, event_type
+        )
+    }
+
+    /// Events as sent by Synapse, with every field the types know about.
+    ///
+    /// A field whose name does not match the JSON would be ignored on deserialization and missing
+    /// after serializing the event again.
+    #[test]
+    fn fully_populated_events_keep_every_field() {
+        let events = vec![
+            r#"{
+                "content": {
+                    "avatar_url": "mxc://example.com/SEsfnsuifSDFSSEF",
+                    "displayname": "Alice Margatroid",
+                    "is_direct": true,
+                    "membership": "invite",
+                    "reason": "Looking forward to it",
+                    "third_party_invite": {
+                        "display_name": "alice",
+                        "signed": {
+                            "mxid": "@alice:example.com",
+                            "signatures": {"magic.forest": {"ed25519:3": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"}},
+                            "token": "abc123"
+                        }
+                    }
+                },
+                "event_id": "$143273582443PhrSn:example.com",
+                "invite_room_state": [{"content": {"name": "Ruma"}, "sender": "@carl:example.com", "state_key": "", "type": "m.room.name"}],
+                "origin_server_ts": 1432735824653,
+                "prev_content": {"membership": "leave"},
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.com",
+                "sender": "@carl:example.com",
+                "state_key": "@alice:example.com",
+                "type": "m.room.member",
+                "unsigned": {"age": 1234}
+            }"#,
+            r#"{
+                "content": {
+                    "avatar_url": "mxc://example.com/SEsfnsuifSDFSSEF",
+                    "currently_active": false,
+                    "displayname": "Alice Margatroid",
+                    "last_active_ago": 2478593,
+                    "presence": "online",
+                    "user_id": "@alice:example.com"
+                },
+                "sender": "@alice:example.com",
+                "type": "m.presence"
+            }"#,
+            r#"{
+                "content": {
+                    "creator": "@carl:example.com",
+                    "m.federate": false,
+                    "predecessor": {"event_id": "$something:example.com", "room_id": "!oldroom:example.com"},
+                    "room_version": "11",
+                    "type": "m.space"
+                },
+                "event_id": "$143273582443PhrSn:example.com",
+                "origin_server_ts": 1432735824653,
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.com",
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.create"
+            }"#,
+            r#"{
+                "content": {
+                    "ban": 100,
+                    "events": {"m.room.name": 100},
+                    "events_default": 1,
+                    "invite": 0,
+                    "kick": 75,
+                    "notifications": {"room": 20},
+                    "redact": 25,
+                    "state_default": 60,
+                    "users": {"@carl:example.com": 100},
+                    "users_default": -1
+                },
+                "event_id": "$143273582443PhrSn:example.com",
+                "origin_server_ts": 1432735824653,
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.com",
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.power_levels"
+            }"#,
+            r#"{
+                "content": {
+                    "info": {
+                        "h": 398,
+                        "mimetype": "image/jpeg",
+                        "size": 31037,
+                        "thumbnail_info": {"h": 100, "mimetype": "image/jpeg", "size": 3000, "w": 100},
+                        "thumbnail_url": "mxc://example.com/thumbnail",
+                        "w": 394
+                    },
+                    "thumbnail_info": {"h": 100, "mimetype": "image/jpeg", "size": 3000, "w": 100},
+                    "thumbnail_url": "mxc://example.com/thumbnail",
+                    "url": "mxc://example.com/JWEIFJgwEIhweiWJE"
+                },
+                "event_id": "$143273582443PhrSn:example.com",
+                "origin_server_ts": 1432735824653,
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.com",
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.avatar"
+            }"#,
+            r#"{
+                "content": {
+                    "chunk_length": 120,
+                    "device_id": "ABCDEFG",
+                    "state": "started"
+                },
+                "event_id": "$143273582443PhrSn:example.com",
+                "origin_server_ts": 1432735824653,
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.com",
+                "sender": "@carl:example.com",
+                "state_key": "@carl:example.com",
+                "type": "m.voice_broadcast_info"
+            }"#,
+            r#"{
+                "content": {
+                    "memberships": [{
+                        "application": "m.call",
+                        "call_id": "",
+                        "device_id": "ABCDEFG",
+                        "expires": 3600000,
+                        "feeds": [{"purpose": "m.usermedia"}],
+                        "scope": "m.room"
+                    }]
+                },
+                "event_id": "$143273582443PhrSn:example.com",
+                "origin_server_ts": 1432735824653,
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.com",
+                "sender": "@carl:example.com",
+                "state_key": "@carl:example.com",
+                "type": "m.call.member"
+            }"#,
+        ];
+
+        for json in events {
+            let value = from_str::<Value>(json).unwrap();
+            let event = from_value::<Event>(value.clone()).unwrap();
+
+            assert_eq!(to_value(&event).unwrap(), value, "{}", json);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Enums for heterogeneous collections of events.
+
+use serde::de::DeserializeOwned;
+use serde_json::{from_str, from_value, Error, Value};
+
+pub mod all;
+pub mod only;
+pub mod to_device;
+
+/// Deserializes each element of a JSON array of events independently.
+///
+/// An event that fails to deserialize does not affect the others. If `value` is not an array, the
+/// result is a single error.
+pub fn events_from_json_array<T: DeserializeOwned>(value: Value) -> Vec<Result<T, Error>> {
+    match from_value::<Vec<Value>>(value) {
+        Ok(events) => events.into_iter().map(from_value).collect(),
+        Err(error) => vec![Err(error)],
+    }
+}
+
+/// Deserializes each element of a JSON array of events in string form independently.
+///
+/// If `s` is not a JSON array, the result is a single error.
+pub fn events_from_json_str_array(s: &str) -> Vec<Result<all::Event, Error>> {
+    match from_str::<Value>(s) {
+        Ok(value) => events_from_json_array(value),
+        Err(error) => vec![Err(error)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::{all, events_from_json_array, events_from_json_str_array};
+
+    const EVENTS: &str = r#"[
+        {"content": {"user_ids": []}, "type": "m.typing"},
+        {"content": {"body": "Hello"}, "type": "m.room.message"},
+        {
+            "content": {"body": "Hello", "msgtype": "m.text"},
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message"
+        }
+    ]"#;
+
+    #[test]
+    fn invalid_events_do_not_affect_others() {
+        let events = events_from_json_str_array(EVENTS);
+
+        assert_eq!(events.len(), 3);
+        match events[0] {
+            Ok(all::Event::Typing(_)) => {}
+            _ => panic!("expected an m.typing event"),
+        }
+        assert!(events[1].is_err());
+        match events[2] {
+            Ok(all::Event::RoomMessage(_)) => {}
+            _ => panic!("expected an m.room.message event"),
+        }
+    }
+
+    #[test]
+    fn room_events_from_array() {
+        let events = events_from_json_array::<all::RoomEvent>(from_str(EVENTS).unwrap());
+
+        assert!(events[0].is_err());
+        assert!(events[1].is_err());
+        assert!(events[2].is_ok());
+    }
+
+    #[test]
+    fn non_array_is_a_single_error() {
+        assert_eq!(events_from_json_str_array("{}").len(), 1);
+        assert!(events_from_json_str_array("{}")[0].is_err());
+        assert!(events_from_json_str_array("not json")[0].is_err());
+    }
+}
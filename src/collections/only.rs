@@ -6,8 +6,18 @@ use call::candidates::CandidatesEvent;
 use call::hangup::HangupEvent;
 use call::invite::InviteEvent;
 use direct::DirectEvent;
+use key::verification::accept::AcceptEvent;
+use key::verification::done::{DoneEvent, DoneRoomEvent};
+use key::verification::key::KeyEvent;
+use key::verification::mac::MacEvent;
+use key::verification::ready::{ReadyEvent, ReadyRoomEvent};
+use poll::end::EndEvent;
+use poll::response::ResponseEvent;
+use poll::start::StartEvent;
 use presence::PresenceEvent;
+use reaction::ReactionEvent;
 use receipt::ReceiptEvent;
+use room::encrypted::EncryptedEvent;
 use room::message::MessageEvent;
 use room::redaction::RedactionEvent;
 use tag::TagEvent;
@@ -21,10 +31,20 @@ use serde_json::{from_value, Value};
 pub use super::all::StateEvent;
 
 /// A basic event.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Event {
     /// m.direct
     Direct(DirectEvent),
+    /// m.key.verification.accept
+    KeyVerificationAccept(AcceptEvent),
+    /// m.key.verification.done
+    KeyVerificationDone(DoneEvent),
+    /// m.key.verification.key
+    KeyVerificationKey(KeyEvent),
+    /// m.key.verification.mac
+    KeyVerificationMac(MacEvent),
+    /// m.key.verification.ready
+    KeyVerificationReady(ReadyEvent),
     /// m.presence
     Presence(PresenceEvent),
     /// m.receipt
@@ -38,7 +58,7 @@ pub enum Event {
 }
 
 /// A room event.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum RoomEvent {
     /// m.call.answer
     CallAnswer(AnswerEvent),
@@ -48,8 +68,24 @@ pub enum RoomEvent {
     CallHangup(HangupEvent),
     /// m.call.invite
     CallInvite(InviteEvent),
+    /// m.key.verification.done
+    KeyVerificationDone(DoneRoomEvent),
+    /// m.key.verification.ready
+    KeyVerificationReady(ReadyRoomEvent),
+    /// m.poll.end
+    PollEnd(EndEvent),
+    /// m.poll.response
+    PollResponse(ResponseEvent),
+    /// m.poll.start
+    PollStart(StartEvent),
+    /// m.reaction
+    Reaction(ReactionEvent),
+    /// m.room.encrypted
+    RoomEncrypted(EncryptedEvent),
     /// m.room.message
-    RoomMessage(MessageEvent),
+    ///
+    /// This is boxed because message events are much larger than the other room events.
+    RoomMessage(Box<MessageEvent>),
     /// m.room.redaction
     RoomRedaction(RedactionEvent),
     /// Any room event that is not part of the specification.
@@ -63,6 +99,11 @@ impl Serialize for Event {
     {
         match *self {
             Event::Direct(ref event) => event.serialize(serializer),
+            Event::KeyVerificationAccept(ref event) => event.serialize(serializer),
+            Event::KeyVerificationDone(ref event) => event.serialize(serializer),
+            Event::KeyVerificationKey(ref event) => event.serialize(serializer),
+            Event::KeyVerificationMac(ref event) => event.serialize(serializer),
+            Event::KeyVerificationReady(ref event) => event.serialize(serializer),
             Event::Presence(ref event) => event.serialize(serializer),
             Event::Receipt(ref event) => event.serialize(serializer),
             Event::Tag(ref event) => event.serialize(serializer),
@@ -98,6 +139,46 @@ impl<'de> Deserialize<'de> for Event {
 
                 Ok(Event::Direct(event))
             }
+            EventType::KeyVerificationAccept => {
+                let event = match from_value::<AcceptEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationAccept(event))
+            }
+            EventType::KeyVerificationDone => {
+                let event = match from_value::<DoneEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationDone(event))
+            }
+            EventType::KeyVerificationKey => {
+                let event = match from_value::<KeyEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationKey(event))
+            }
+            EventType::KeyVerificationMac => {
+                let event = match from_value::<MacEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationMac(event))
+            }
+            EventType::KeyVerificationReady => {
+                let event = match from_value::<ReadyEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Event::KeyVerificationReady(event))
+            }
             EventType::Presence => {
                 let event = match from_value::<PresenceEvent>(value) {
                     Ok(event) => event,
@@ -142,10 +223,16 @@ impl<'de> Deserialize<'de> for Event {
             | EventType::CallCandidates
             | EventType::CallHangup
             | EventType::CallInvite
+            | EventType::CallMember
+            | EventType::PollEnd
+            | EventType::PollResponse
+            | EventType::PollStart
+            | EventType::Reaction
             | EventType::RoomAliases
             | EventType::RoomAvatar
             | EventType::RoomCanonicalAlias
             | EventType::RoomCreate
+            | EventType::RoomEncrypted
             | EventType::RoomGuestAccess
             | EventType::RoomHistoryVisibility
             | EventType::RoomJoinRules
@@ -155,8 +242,11 @@ impl<'de> Deserialize<'de> for Event {
             | EventType::RoomPinnedEvents
             | EventType::RoomPowerLevels
             | EventType::RoomRedaction
+            | EventType::RoomServerAcl
             | EventType::RoomThirdPartyInvite
-            | EventType::RoomTopic => {
+            | EventType::RoomTopic
+            | EventType::SpaceChild
+            | EventType::VoiceBroadcastInfo => {
                 return Err(D::Error::custom(
                     "not exclusively a basic event".to_string(),
                 ));
@@ -175,6 +265,13 @@ impl Serialize for RoomEvent {
             RoomEvent::CallCandidates(ref event) => event.serialize(serializer),
             RoomEvent::CallHangup(ref event) => event.serialize(serializer),
             RoomEvent::CallInvite(ref event) => event.serialize(serializer),
+            RoomEvent::KeyVerificationDone(ref event) => event.serialize(serializer),
+            RoomEvent::KeyVerificationReady(ref event) => event.serialize(serializer),
+            RoomEvent::PollEnd(ref event) => event.serialize(serializer),
+            RoomEvent::PollResponse(ref event) => event.serialize(serializer),
+            RoomEvent::PollStart(ref event) => event.serialize(serializer),
+            RoomEvent::Reaction(ref event) => event.serialize(serializer),
+            RoomEvent::RoomEncrypted(ref event) => event.serialize(serializer),
             RoomEvent::RoomMessage(ref event) => event.serialize(serializer),
             RoomEvent::RoomRedaction(ref event) => event.serialize(serializer),
             RoomEvent::CustomRoom(ref event) => event.serialize(serializer),
@@ -232,13 +329,69 @@ impl<'de> Deserialize<'de> for RoomEvent {
 
                 Ok(RoomEvent::CallInvite(event))
             }
+            EventType::KeyVerificationDone => {
+                let event = match from_value::<DoneRoomEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::KeyVerificationDone(event))
+            }
+            EventType::KeyVerificationReady => {
+                let event = match from_value::<ReadyRoomEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::KeyVerificationReady(event))
+            }
+            EventType::PollEnd => {
+                let event = match from_value::<EndEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::PollEnd(event))
+            }
+            EventType::PollResponse => {
+                let event = match from_value::<ResponseEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::PollResponse(event))
+            }
+            EventType::PollStart => {
+                let event = match from_value::<StartEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::PollStart(event))
+            }
+            EventType::Reaction => {
+                let event = match from_value::<ReactionEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::Reaction(event))
+            }
+            EventType::RoomEncrypted => {
+                let event = match from_value::<EncryptedEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(RoomEvent::RoomEncrypted(event))
+            }
             EventType::RoomMessage => {
                 let event = match from_value::<MessageEvent>(value) {
                     Ok(event) => event,
                     Err(error) => return Err(D::Error::custom(error.to_string())),
                 };
 
-                Ok(RoomEvent::RoomMessage(event))
+                Ok(RoomEvent::RoomMessage(Box::new(event)))
             }
             EventType::RoomRedaction => {
                 let event = match from_value::<RedactionEvent>(value) {
@@ -256,7 +409,11 @@ impl<'de> Deserialize<'de> for RoomEvent {
 
                 Ok(RoomEvent::CustomRoom(event))
             }
-            EventType::Direct
+            EventType::CallMember
+            | EventType::Direct
+            | EventType::KeyVerificationAccept
+            | EventType::KeyVerificationKey
+            | EventType::KeyVerificationMac
             | EventType::Presence
             | EventType::Receipt
             | EventType::RoomAliases
@@ -270,10 +427,13 @@ impl<'de> Deserialize<'de> for RoomEvent {
             | EventType::RoomName
             | EventType::RoomPinnedEvents
             | EventType::RoomPowerLevels
+            | EventType::RoomServerAcl
             | EventType::RoomThirdPartyInvite
             | EventType::RoomTopic
+            | EventType::SpaceChild
             | EventType::Tag
-            | EventType::Typing => {
+            | EventType::Typing
+            | EventType::VoiceBroadcastInfo => {
                 return Err(D::Error::custom("not exclusively a room event".to_string()));
             }
         }
@@ -290,6 +450,11 @@ macro_rules! impl_from_t_for_event {
 }
 
 impl_from_t_for_event!(DirectEvent, Direct);
+impl_from_t_for_event!(AcceptEvent, KeyVerificationAccept);
+impl_from_t_for_event!(DoneEvent, KeyVerificationDone);
+impl_from_t_for_event!(KeyEvent, KeyVerificationKey);
+impl_from_t_for_event!(MacEvent, KeyVerificationMac);
+impl_from_t_for_event!(ReadyEvent, KeyVerificationReady);
 impl_from_t_for_event!(PresenceEvent, Presence);
 impl_from_t_for_event!(ReceiptEvent, Receipt);
 impl_from_t_for_event!(TagEvent, Tag);
@@ -310,6 +475,26 @@ impl_from_t_for_room_event!(AnswerEvent, CallAnswer);
 impl_from_t_for_room_event!(CandidatesEvent, CallCandidates);
 impl_from_t_for_room_event!(HangupEvent, CallHangup);
 impl_from_t_for_room_event!(InviteEvent, CallInvite);
-impl_from_t_for_room_event!(MessageEvent, RoomMessage);
+impl_from_t_for_room_event!(DoneRoomEvent, KeyVerificationDone);
+impl_from_t_for_room_event!(ReadyRoomEvent, KeyVerificationReady);
+impl_from_t_for_room_event!(EndEvent, PollEnd);
+impl_from_t_for_room_event!(ResponseEvent, PollResponse);
+impl_from_t_for_room_event!(StartEvent, PollStart);
+impl_from_t_for_room_event!(ReactionEvent, Reaction);
+impl_from_t_for_room_event!(EncryptedEvent, RoomEncrypted);
 impl_from_t_for_room_event!(RedactionEvent, RoomRedaction);
 impl_from_t_for_room_event!(CustomRoomEvent, CustomRoom);
+
+impl From<MessageEvent> for RoomEvent {
+    fn from(event: MessageEvent) -> Self {
+        RoomEvent::RoomMessage(Box::new(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, RoomEvent};
+
+    assert_impl_all!(Event: Clone);
+    assert_impl_all!(RoomEvent: Clone);
+}
@@ -0,0 +1,148 @@
+//! Enums for heterogeneous collections of events sent directly to a device rather than to a room.
+
+use key::verification::accept::AcceptEvent;
+use key::verification::done::DoneEvent;
+use key::verification::key::KeyEvent;
+use key::verification::mac::MacEvent;
+use key::verification::ready::ReadyEvent;
+use EventType;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{from_value, Value};
+
+/// An event sent directly to a device.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToDeviceEvent {
+    /// m.key.verification.accept
+    KeyVerificationAccept(AcceptEvent),
+    /// m.key.verification.done
+    KeyVerificationDone(DoneEvent),
+    /// m.key.verification.key
+    KeyVerificationKey(KeyEvent),
+    /// m.key.verification.mac
+    KeyVerificationMac(MacEvent),
+    /// m.key.verification.ready
+    KeyVerificationReady(ReadyEvent),
+}
+
+impl Serialize for ToDeviceEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            ToDeviceEvent::KeyVerificationAccept(ref event) => event.serialize(serializer),
+            ToDeviceEvent::KeyVerificationDone(ref event) => event.serialize(serializer),
+            ToDeviceEvent::KeyVerificationKey(ref event) => event.serialize(serializer),
+            ToDeviceEvent::KeyVerificationMac(ref event) => event.serialize(serializer),
+            ToDeviceEvent::KeyVerificationReady(ref event) => event.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToDeviceEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+
+        let event_type_value = match value.get("type") {
+            Some(value) => value.clone(),
+            None => return Err(D::Error::missing_field("type")),
+        };
+
+        let event_type = match from_value::<EventType>(event_type_value.clone()) {
+            Ok(event_type) => event_type,
+            Err(error) => return Err(D::Error::custom(error.to_string())),
+        };
+
+        match event_type {
+            EventType::KeyVerificationAccept => {
+                let event = match from_value::<AcceptEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(ToDeviceEvent::KeyVerificationAccept(event))
+            }
+            EventType::KeyVerificationDone => {
+                let event = match from_value::<DoneEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(ToDeviceEvent::KeyVerificationDone(event))
+            }
+            EventType::KeyVerificationKey => {
+                let event = match from_value::<KeyEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(ToDeviceEvent::KeyVerificationKey(event))
+            }
+            EventType::KeyVerificationMac => {
+                let event = match from_value::<MacEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(ToDeviceEvent::KeyVerificationMac(event))
+            }
+            EventType::KeyVerificationReady => {
+                let event = match from_value::<ReadyEvent>(value) {
+                    Ok(event) => event,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(ToDeviceEvent::KeyVerificationReady(event))
+            }
+            _ => Err(D::Error::custom("not a to-device event".to_string())),
+        }
+    }
+}
+
+macro_rules! impl_from_t_for_to_device_event {
+    ($ty:ty, $variant:ident) => {
+        impl From<$ty> for ToDeviceEvent {
+            fn from(event: $ty) -> Self {
+                ToDeviceEvent::$variant(event)
+            }
+        }
+    };
+}
+
+impl_from_t_for_to_device_event!(AcceptEvent, KeyVerificationAccept);
+impl_from_t_for_to_device_event!(DoneEvent, KeyVerificationDone);
+impl_from_t_for_to_device_event!(KeyEvent, KeyVerificationKey);
+impl_from_t_for_to_device_event!(MacEvent, KeyVerificationMac);
+impl_from_t_for_to_device_event!(ReadyEvent, KeyVerificationReady);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_string};
+
+    use super::ToDeviceEvent;
+
+    #[test]
+    fn deserialize_to_device_events() {
+        let json = r#"{"content":{"transaction_id":"S0meUniqueAndOpaqueString"},"type":"m.key.verification.done","sender":"@alice:example.com"}"#;
+
+        match from_str::<ToDeviceEvent>(json).unwrap() {
+            ref event @ ToDeviceEvent::KeyVerificationDone(_) => {
+                assert_eq!(to_string(event).unwrap(), json);
+            }
+            _ => panic!("expected an m.key.verification.done event"),
+        }
+    }
+
+    #[test]
+    fn reject_room_events() {
+        assert!(from_str::<ToDeviceEvent>(
+            r#"{"content":{"topic":"Testing room"},"type":"m.room.topic"}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! Client-side filtering of events that have already been received.
+//!
+//! `EventFilter` mirrors the event filter of the client-server API, but is applied locally to
+//! `collections::all::Event` values rather than being sent to a homeserver.
+
+use ruma_identifiers::UserId;
+
+use collections::all::Event;
+use EventType;
+
+/// A filter that selects events by type and sender.
+///
+/// A field that is `None` places no restriction on events. The default filter matches every event.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventFilter {
+    /// The event types to include. If `None`, all event types are included.
+    pub event_types: Option<Vec<EventType>>,
+
+    /// The event types to exclude. Takes precedence over `event_types`.
+    pub not_types: Option<Vec<EventType>>,
+
+    /// The senders to include. If `None`, all senders are included.
+    ///
+    /// Events without a sender never match a filter with this field set.
+    pub senders: Option<Vec<UserId>>,
+
+    /// The senders to exclude. Takes precedence over `senders`.
+    pub not_senders: Option<Vec<UserId>>,
+}
+
+/// A builder for an `EventFilter`.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilterBuilder {
+    filter: EventFilter,
+}
+
+impl EventFilter {
+    /// Creates a builder for an `EventFilter` that initially matches every event.
+    pub fn builder() -> EventFilterBuilder {
+        EventFilterBuilder::default()
+    }
+
+    /// Whether or not the given event passes this filter.
+    pub fn matches(&self, event: &Event) -> bool {
+        let event_type = event.event_type();
+
+        if let Some(ref not_types) = self.not_types {
+            if not_types.contains(event_type) {
+                return false;
+            }
+        }
+
+        if let Some(ref event_types) = self.event_types {
+            if !event_types.contains(event_type) {
+                return false;
+            }
+        }
+
+        let sender = sender(event);
+
+        if let (Some(sender), Some(not_senders)) = (sender, self.not_senders.as_ref()) {
+            if not_senders.contains(sender) {
+                return false;
+            }
+        }
+
+        if let Some(ref senders) = self.senders {
+            match sender {
+                Some(sender) if senders.contains(sender) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+impl EventFilterBuilder {
+    /// Only include events of the given types.
+    pub fn event_types(mut self, event_types: Vec<EventType>) -> Self {
+        self.filter.event_types = Some(event_types);
+        self
+    }
+
+    /// Exclude events of the given types.
+    pub fn not_types(mut self, not_types: Vec<EventType>) -> Self {
+        self.filter.not_types = Some(not_types);
+        self
+    }
+
+    /// Only include events sent by the given users.
+    pub fn senders(mut self, senders: Vec<UserId>) -> Self {
+        self.filter.senders = Some(senders);
+        self
+    }
+
+    /// Exclude events sent by the given users.
+    pub fn not_senders(mut self, not_senders: Vec<UserId>) -> Self {
+        self.filter.not_senders = Some(not_senders);
+        self
+    }
+
+    /// Finishes building the filter.
+    pub fn build(self) -> EventFilter {
+        self.filter
+    }
+}
+
+/// The sender of an event, if the event type has one.
+fn sender(event: &Event) -> Option<&UserId> {
+    match *event {
+        Event::KeyVerificationAccept(ref event) => Some(&event.sender),
+        Event::KeyVerificationDone(ref event) => Some(&event.sender),
+        Event::KeyVerificationKey(ref event) => Some(&event.sender),
+        Event::KeyVerificationMac(ref event) => Some(&event.sender),
+        Event::KeyVerificationReady(ref event) => Some(&event.sender),
+        Event::Presence(ref event) => Some(&event.sender),
+        _ => event.as_room_event().map(|event| event.sender()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::UserId;
+    use serde_json::from_str;
+
+    use super::EventFilter;
+    use collections::all::Event;
+    use EventType;
+
+    fn message_event() -> Event {
+        from_str::<Event>(
+            r#"{
+                "content": {"body": "Hello", "msgtype": "m.text"},
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "type": "m.room.message"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn typing_event() -> Event {
+        from_str::<Event>(r#"{"content": {"user_ids": []}, "type": "m.typing"}"#).unwrap()
+    }
+
+    #[test]
+    fn default_filter_matches_everything() {
+        let filter = EventFilter::default();
+
+        assert!(filter.matches(&message_event()));
+        assert!(filter.matches(&typing_event()));
+    }
+
+    #[test]
+    fn filter_by_event_type() {
+        let filter = EventFilter::builder()
+            .event_types(vec![EventType::RoomMessage])
+            .build();
+
+        assert!(filter.matches(&message_event()));
+        assert!(!filter.matches(&typing_event()));
+    }
+
+    #[test]
+    fn excluded_types_take_precedence() {
+        let filter = EventFilter::builder()
+            .event_types(vec![EventType::RoomMessage])
+            .not_types(vec![EventType::RoomMessage])
+            .build();
+
+        assert!(!filter.matches(&message_event()));
+    }
+
+    #[test]
+    fn filter_by_sender() {
+        let carl = UserId::try_from("@carl:example.com").unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+
+        let filter = EventFilter::builder().senders(vec![carl.clone()]).build();
+        assert!(filter.matches(&message_event()));
+        assert!(!filter.matches(&typing_event()));
+
+        let filter = EventFilter::builder().senders(vec![alice]).build();
+        assert!(!filter.matches(&message_event()));
+
+        let filter = EventFilter::builder().not_senders(vec![carl]).build();
+        assert!(!filter.matches(&message_event()));
+        assert!(filter.matches(&typing_event()));
+    }
+}
@@ -0,0 +1,3 @@
+//! Modules for events in the *m.key* namespace.
+
+pub mod verification;
@@ -0,0 +1,114 @@
+//! Types for the *m.key.verification.accept* event.
+
+use ruma_identifiers::UserId;
+
+use super::{
+    HashAlgorithm, KeyAgreementProtocol, MessageAuthenticationCode, ShortAuthenticationString,
+};
+
+event! {
+    /// Accepts a previously sent *m.key.verification.start* message.
+    pub struct AcceptEvent(AcceptEventContent) {
+        /// The unique identifier for the user who sent this event.
+        pub sender: UserId
+    }
+}
+
+/// The payload of an `AcceptEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AcceptEventContent {
+    /// The verification method being accepted, along with its method-specific parameters.
+    #[serde(flatten)]
+    pub method: AcceptMethod,
+
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be the same as the one used for the *m.key.verification.start* message.
+    pub transaction_id: String,
+}
+
+/// An accepted verification method and its parameters.
+///
+/// This is serialized as a `method` field alongside the method's parameters.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "method")]
+pub enum AcceptMethod {
+    /// The *m.sas.v1* verification method.
+    #[serde(rename = "m.sas.v1")]
+    SasV1(SasV1AcceptContent),
+}
+
+/// The parameters of an accepted *m.sas.v1* verification.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SasV1AcceptContent {
+    /// The hash (encoded as unpadded base64) of the concatenation of the device's ephemeral public
+    /// key and the canonical JSON representation of the *m.key.verification.start* message.
+    pub commitment: String,
+
+    /// The hash method the device is choosing to use.
+    pub hash: HashAlgorithm,
+
+    /// The key agreement protocol the device is choosing to use.
+    pub key_agreement_protocol: KeyAgreementProtocol,
+
+    /// The message authentication code the device is choosing to use.
+    pub message_authentication_code: MessageAuthenticationCode,
+
+    /// The SAS methods both devices involved in the verification process understand.
+    pub short_authentication_string: Vec<ShortAuthenticationString>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::super::{
+        HashAlgorithm, KeyAgreementProtocol, MessageAuthenticationCode, ShortAuthenticationString,
+    };
+    use super::{AcceptEvent, AcceptEventContent, AcceptMethod, SasV1AcceptContent};
+
+    #[test]
+    fn serialization_deserialization() {
+        let json = r#"{
+            "content": {
+                "commitment": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg",
+                "hash": "sha256",
+                "key_agreement_protocol": "curve25519",
+                "message_authentication_code": "hkdf-hmac-sha256",
+                "method": "m.sas.v1",
+                "short_authentication_string": ["decimal", "emoji"],
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.com",
+            "type": "m.key.verification.accept"
+        }"#;
+
+        let event = from_str::<AcceptEvent>(json).unwrap();
+
+        assert_eq!(
+            event.content,
+            AcceptEventContent {
+                method: AcceptMethod::SasV1(SasV1AcceptContent {
+                    commitment: "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg".to_string(),
+                    hash: HashAlgorithm::Sha256,
+                    key_agreement_protocol: KeyAgreementProtocol::Curve25519,
+                    message_authentication_code: MessageAuthenticationCode::HkdfHmacSha256,
+                    short_authentication_string: vec![
+                        ShortAuthenticationString::Decimal,
+                        ShortAuthenticationString::Emoji,
+                    ],
+                }),
+                transaction_id: "S0meUniqueAndOpaqueString".to_string(),
+            }
+        );
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+
+    #[test]
+    fn unknown_method_is_rejected() {
+        assert!(from_str::<AcceptEventContent>(
+            r#"{"method":"m.qr_code.show.v1","transaction_id":"S0meUniqueAndOpaqueString"}"#
+        )
+        .is_err());
+    }
+}
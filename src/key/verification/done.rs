@@ -0,0 +1,26 @@
+//! Types for the *m.key.verification.done* event.
+
+use ruma_identifiers::UserId;
+
+use super::VerificationFlow;
+
+event! {
+    /// Sent by both devices to indicate that they have finished a verification.
+    pub struct DoneEvent(DoneEventContent) {
+        /// The unique identifier for the user who sent this event.
+        pub sender: UserId
+    }
+}
+
+room_event! {
+    /// A `DoneEvent` sent in a room rather than to a device, as proposed in MSC3381.
+    pub struct DoneRoomEvent(DoneEventContent) {}
+}
+
+/// The payload of a `DoneEvent` or a `DoneRoomEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DoneEventContent {
+    /// The verification process this event belongs to.
+    #[serde(flatten)]
+    pub flow: VerificationFlow,
+}
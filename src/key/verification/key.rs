@@ -0,0 +1,23 @@
+//! Types for the *m.key.verification.key* event.
+
+use ruma_identifiers::UserId;
+
+event! {
+    /// Sends the ephemeral public key for a device to the partner device.
+    pub struct KeyEvent(KeyEventContent) {
+        /// The unique identifier for the user who sent this event.
+        pub sender: UserId
+    }
+}
+
+/// The payload of a `KeyEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyEventContent {
+    /// The device's ephemeral public key, encoded as unpadded base64.
+    pub key: String,
+
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be the same as the one used for the *m.key.verification.start* message.
+    pub transaction_id: String,
+}
@@ -0,0 +1,64 @@
+//! Types for the *m.key.verification.mac* event.
+
+use std::collections::HashMap;
+
+use ruma_identifiers::UserId;
+
+event! {
+    /// Sends the MAC of a device's key to the partner device.
+    pub struct MacEvent(MacEventContent) {
+        /// The unique identifier for the user who sent this event.
+        pub sender: UserId
+    }
+}
+
+/// The payload of a `MacEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MacEventContent {
+    /// The MAC of the comma-separated, sorted list of key IDs given in the `mac` property, encoded
+    /// as unpadded base64.
+    pub keys: String,
+
+    /// A map of the key ID to the MAC of the key, using the algorithm in the verification process.
+    ///
+    /// Key IDs have the form *ed25519:DEVICE_ID*. The MAC is encoded as unpadded base64.
+    pub mac: HashMap<String, String>,
+
+    /// An opaque identifier for the verification process.
+    ///
+    /// Must be the same as the one used for the *m.key.verification.start* message.
+    pub transaction_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::MacEvent;
+    use EventType;
+
+    #[test]
+    fn serialization_deserialization() {
+        let json = r#"{
+            "content": {
+                "keys": "2Wptgo4CwmLo/Y8B8qinxApKaCkBG2fjTWB7AbP5Uy+aIbygsSdLOFzvdDjww8zUVKCmI02eP9xtyJxc/cLiBA",
+                "mac": {
+                    "ed25519:ABCDEF": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+                },
+                "transaction_id": "S0meUniqueAndOpaqueString"
+            },
+            "sender": "@alice:example.com",
+            "type": "m.key.verification.mac"
+        }"#;
+
+        let event = from_str::<MacEvent>(json).unwrap();
+
+        assert_eq!(event.event_type, EventType::KeyVerificationMac);
+        assert_eq!(event.content.transaction_id, "S0meUniqueAndOpaqueString");
+        assert_eq!(
+            event.content.mac.get("ed25519:ABCDEF").map(String::as_str),
+            Some("fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg")
+        );
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+}
@@ -0,0 +1,171 @@
+//! Modules for events in the *m.key.verification* namespace.
+//!
+//! This module also contains types shared by events in its child namespaces.
+
+pub mod accept;
+pub mod done;
+pub mod key;
+pub mod mac;
+pub mod ready;
+
+use ruma_identifiers::EventId;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A method for verifying the identity of another device.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum VerificationMethod {
+    /// The *m.sas.v1* verification method, using short authentication strings.
+    #[serde(rename = "m.sas.v1")]
+    MSasV1,
+}
+
+impl_enum! {
+    VerificationMethod {
+        MSasV1 => "m.sas.v1",
+    }
+}
+
+/// A hash algorithm used in SAS verification.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum HashAlgorithm {
+    /// The SHA256 hash algorithm.
+    #[serde(rename = "sha256")]
+    Sha256,
+}
+
+impl_enum! {
+    HashAlgorithm {
+        Sha256 => "sha256",
+    }
+}
+
+/// A key agreement protocol used in SAS verification.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum KeyAgreementProtocol {
+    /// The Curve25519 key agreement protocol.
+    #[serde(rename = "curve25519")]
+    Curve25519,
+
+    /// The Curve25519 key agreement protocol with HKDF-SHA256 key derivation.
+    #[serde(rename = "curve25519-hkdf-sha256")]
+    Curve25519HkdfSha256,
+}
+
+impl_enum! {
+    KeyAgreementProtocol {
+        Curve25519 => "curve25519",
+        Curve25519HkdfSha256 => "curve25519-hkdf-sha256",
+    }
+}
+
+/// A message authentication code algorithm used in SAS verification.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MessageAuthenticationCode {
+    /// The HKDF-HMAC-SHA256 MAC.
+    #[serde(rename = "hkdf-hmac-sha256")]
+    HkdfHmacSha256,
+
+    /// The HMAC-SHA256 MAC.
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+}
+
+impl_enum! {
+    MessageAuthenticationCode {
+        HkdfHmacSha256 => "hkdf-hmac-sha256",
+        HmacSha256 => "hmac-sha256",
+    }
+}
+
+/// A method of displaying a short authentication string to the user.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ShortAuthenticationString {
+    /// Displaying the SAS as three numbers between 1000 and 9191.
+    #[serde(rename = "decimal")]
+    Decimal,
+
+    /// Displaying the SAS as seven emoji.
+    #[serde(rename = "emoji")]
+    Emoji,
+}
+
+impl_enum! {
+    ShortAuthenticationString {
+        Decimal => "decimal",
+        Emoji => "emoji",
+    }
+}
+
+/// How a verification event identifies the verification process it belongs to.
+///
+/// Events sent to a device carry a `transaction_id`. Events sent in a room, as proposed in
+/// MSC3381, reference the *m.key.verification.request* message through *m.relates_to* instead.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum VerificationFlow {
+    /// A verification between devices, sent through to-device messages.
+    ToDevice {
+        /// An opaque identifier for the verification process.
+        ///
+        /// Must be the same as the one used for the *m.key.verification.request* message.
+        transaction_id: String,
+    },
+
+    /// A verification in a room.
+    InRoom {
+        /// The *m.key.verification.request* message that started the verification.
+        #[serde(rename = "m.relates_to")]
+        relates_to: VerificationRelation,
+    },
+}
+
+/// A reference to the *m.key.verification.request* message that started a verification in a
+/// room.
+///
+/// This is serialized with a `rel_type` of *m.reference*.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationRelation {
+    /// The event ID of the *m.key.verification.request* message.
+    pub event_id: EventId,
+}
+
+/// The JSON form of a `VerificationRelation`.
+#[derive(Deserialize, Serialize)]
+struct VerificationRelationJson<T> {
+    event_id: T,
+    rel_type: String,
+}
+
+impl Serialize for VerificationRelation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        VerificationRelationJson {
+            event_id: &self.event_id,
+            rel_type: "m.reference".to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VerificationRelation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let relation = VerificationRelationJson::<EventId>::deserialize(deserializer)?;
+
+        if relation.rel_type != "m.reference" {
+            return Err(D::Error::custom(format!(
+                "expected a rel_type of m.reference, found {}",
+                relation.rel_type
+            )));
+        }
+
+        Ok(VerificationRelation {
+            event_id: relation.event_id,
+        })
+    }
+}
@@ -0,0 +1,94 @@
+//! Types for the *m.key.verification.ready* event.
+
+use ruma_identifiers::UserId;
+
+use super::{VerificationFlow, VerificationMethod};
+
+event! {
+    /// Sent by a device in response to a verification request to indicate that it is ready to
+    /// begin verification.
+    pub struct ReadyEvent(ReadyEventContent) {
+        /// The unique identifier for the user who sent this event.
+        pub sender: UserId
+    }
+}
+
+room_event! {
+    /// A `ReadyEvent` sent in a room rather than to a device, as proposed in MSC3381.
+    pub struct ReadyRoomEvent(ReadyEventContent) {}
+}
+
+/// The payload of a `ReadyEvent` or a `ReadyRoomEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReadyEventContent {
+    /// The verification process this event belongs to.
+    #[serde(flatten)]
+    pub flow: VerificationFlow,
+
+    /// The device ID which is accepting the request.
+    pub from_device: String,
+
+    /// The verification methods supported by the sender.
+    pub methods: Vec<VerificationMethod>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::EventId;
+    use serde_json::{from_str, to_string};
+
+    use super::super::{VerificationFlow, VerificationMethod, VerificationRelation};
+    use super::{ReadyEvent, ReadyEventContent, ReadyRoomEvent};
+    use EventType;
+
+    #[test]
+    fn serialization_deserialization() {
+        let json = r#"{"content":{"transaction_id":"S0meUniqueAndOpaqueString","from_device":"ABCDEFG","methods":["m.sas.v1"]},"type":"m.key.verification.ready","sender":"@alice:example.com"}"#;
+
+        let event = from_str::<ReadyEvent>(json).unwrap();
+
+        assert_eq!(event.event_type, EventType::KeyVerificationReady);
+        assert_eq!(
+            event.content,
+            ReadyEventContent {
+                flow: VerificationFlow::ToDevice {
+                    transaction_id: "S0meUniqueAndOpaqueString".to_string(),
+                },
+                from_device: "ABCDEFG".to_string(),
+                methods: vec![VerificationMethod::MSasV1],
+            }
+        );
+        assert_eq!(to_string(&event).unwrap(), json);
+    }
+
+    #[test]
+    fn in_room() {
+        let json = r#"{"content":{"m.relates_to":{"event_id":"$request:example.com","rel_type":"m.reference"},"from_device":"ABCDEFG","methods":["m.sas.v1"]},"event_id":"$ready:example.com","type":"m.key.verification.ready","origin_server_ts":1,"room_id":"!n8f893n9:example.com","sender":"@alice:example.com"}"#;
+
+        let event = from_str::<ReadyRoomEvent>(json).unwrap();
+
+        assert_eq!(
+            event.content.flow,
+            VerificationFlow::InRoom {
+                relates_to: VerificationRelation {
+                    event_id: EventId::try_from("$request:example.com").unwrap(),
+                },
+            }
+        );
+        assert_eq!(to_string(&event).unwrap(), json);
+    }
+
+    #[test]
+    fn missing_flow_is_rejected() {
+        assert!(from_str::<ReadyEventContent>(
+            r#"{"from_device":"ABCDEFG","methods":["m.sas.v1"]}"#
+        )
+        .is_err());
+        assert!(from_str::<ReadyEventContent>(
+            r#"{"m.relates_to":{"event_id":"$request:example.com","rel_type":"m.replace"},"from_device":"ABCDEFG","methods":["m.sas.v1"]}"#
+        )
+        .is_err());
+    }
+}
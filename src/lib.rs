@@ -100,41 +100,79 @@
 #![deny(missing_docs)]
 #![deny(warnings)]
 
+extern crate self as ruma_events;
+#[macro_use]
+extern crate ruma_events_macros;
 extern crate ruma_identifiers;
 extern crate ruma_signatures;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
+#[cfg(feature = "random-call-id")]
+extern crate uuid;
+#[cfg(test)]
+#[macro_use]
+extern crate static_assertions;
 
+use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Error as FmtError, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use ruma_identifiers::{EventId, RoomId, UserId};
 use serde::de::{Error as SerdeError, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::Value;
+use serde_json::{to_value, to_writer, Error as JsonError, Value};
 
 #[macro_use]
 mod macros;
 
 pub mod call;
-/// Enums for heterogeneous collections of events.
-pub mod collections {
-    pub mod all;
-    pub mod only;
-}
+pub mod collections;
 pub mod direct;
+pub mod filter;
+pub mod key;
+pub mod poll;
 pub mod presence;
+pub mod reaction;
 pub mod receipt;
 pub mod room;
+pub mod space;
 pub mod stripped;
 pub mod tag;
 pub mod typing;
+pub mod voice_broadcast_info;
+
+/// The items of other crates that the code generated by the `ruma-events-macros` derives refers
+/// to.
+#[doc(hidden)]
+pub mod exports {
+    pub extern crate serde;
+    pub extern crate serde_derive;
+
+    pub use ruma_identifiers::{EventId, RoomId, UserId};
+    pub use serde_json::Value;
+}
 
 /// An error when attempting to convert a string to an enum that only accepts certain values.
 #[derive(Clone, Copy, Debug)]
 pub struct ParseError;
 
+/// An *mxc://* URI of content in the content repository, e.g. "mxc://example.org/SEsfnsuifSDFSSEF".
+///
+/// The server name and media ID are validated on construction.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MxcUri {
+    uri: String,
+    media_id_start: usize,
+}
+
+/// An error when attempting to parse a string that is not a valid *mxc://* URI.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MxcUriError;
+
 /// The type of an event.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum EventType {
@@ -146,10 +184,30 @@ pub enum EventType {
     CallHangup,
     /// m.call.invite
     CallInvite,
+    /// m.call.member
+    CallMember,
     /// m.direct
     Direct,
+    /// m.key.verification.accept
+    KeyVerificationAccept,
+    /// m.key.verification.done
+    KeyVerificationDone,
+    /// m.key.verification.key
+    KeyVerificationKey,
+    /// m.key.verification.mac
+    KeyVerificationMac,
+    /// m.key.verification.ready
+    KeyVerificationReady,
+    /// m.poll.end
+    PollEnd,
+    /// m.poll.response
+    PollResponse,
+    /// m.poll.start
+    PollStart,
     /// m.presence
     Presence,
+    /// m.reaction
+    Reaction,
     /// m.receipt
     Receipt,
     /// m.room.aliases
@@ -160,6 +218,8 @@ pub enum EventType {
     RoomCanonicalAlias,
     /// m.room.create
     RoomCreate,
+    /// m.room.encrypted
+    RoomEncrypted,
     /// m.room.guest_access
     RoomGuestAccess,
     /// m.room.history_visibility
@@ -178,14 +238,20 @@ pub enum EventType {
     RoomPowerLevels,
     /// m.room.redaction
     RoomRedaction,
+    /// m.room.server_acl
+    RoomServerAcl,
     /// m.room.third_party_invite
     RoomThirdPartyInvite,
     /// m.room.topic
     RoomTopic,
+    /// m.space.child
+    SpaceChild,
     /// m.tag
     Tag,
     /// m.typing
     Typing,
+    /// m.voice_broadcast_info
+    VoiceBroadcastInfo,
     /// Any event that is not part of the specification.
     Custom(String),
 }
@@ -235,6 +301,30 @@ pub trait StateEvent: RoomEvent {
     fn state_key(&self) -> &str;
 }
 
+/// Serialization to canonical JSON, the form of JSON hashed and signed to compute event IDs and
+/// event signatures.
+///
+/// Canonical JSON has no insignificant whitespace and the keys of every object are sorted by
+/// Unicode code point. This trait is implemented for every serializable type, including every
+/// event and event content.
+pub trait CanonicalJson: Serialize {
+    /// Serializes this value to canonical JSON.
+    fn to_canonical_json(&self) -> Result<Vec<u8>, JsonError> {
+        let mut json = Vec::new();
+        write_canonical_json(&to_value(self)?, &mut json)?;
+
+        Ok(json)
+    }
+}
+
+impl<T: Serialize + ?Sized> CanonicalJson for T {}
+
+/// The content of a state event after a redaction that keeps none of its fields.
+///
+/// This serializes as an empty object.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RedactedStateEventContent {}
+
 event! {
     /// A custom basic event not covered by the Matrix specification.
     pub struct CustomEvent(Value) {}
@@ -250,6 +340,25 @@ state_event! {
     pub struct CustomStateEvent(Value) {}
 }
 
+impl EventType {
+    /// Parses a URI-style event type string such as `m.room.message#m.text`.
+    ///
+    /// Returns the event type before the first `#` and the fragment after it, if there is one.
+    pub fn from_uri(s: &str) -> (EventType, Option<String>) {
+        let mut parts = s.splitn(2, '#');
+        let event_type = EventType::from(parts.next().unwrap_or(""));
+        let fragment = parts.next().map(|fragment| fragment.to_string());
+
+        (event_type, fragment)
+    }
+
+    /// The URI-style form of this event type with the given fragment, e.g.
+    /// `m.room.message#m.text`.
+    pub fn with_fragment(&self, fragment: &str) -> String {
+        format!("{}#{}", self, fragment)
+    }
+}
+
 impl Display for EventType {
     fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
         let event_type_str = match *self {
@@ -257,13 +366,24 @@ impl Display for EventType {
             EventType::CallCandidates => "m.call.candidates",
             EventType::CallHangup => "m.call.hangup",
             EventType::CallInvite => "m.call.invite",
+            EventType::CallMember => "m.call.member",
             EventType::Direct => "m.direct",
+            EventType::KeyVerificationAccept => "m.key.verification.accept",
+            EventType::KeyVerificationDone => "m.key.verification.done",
+            EventType::KeyVerificationKey => "m.key.verification.key",
+            EventType::KeyVerificationMac => "m.key.verification.mac",
+            EventType::KeyVerificationReady => "m.key.verification.ready",
+            EventType::PollEnd => "m.poll.end",
+            EventType::PollResponse => "m.poll.response",
+            EventType::PollStart => "m.poll.start",
             EventType::Presence => "m.presence",
+            EventType::Reaction => "m.reaction",
             EventType::Receipt => "m.receipt",
             EventType::RoomAliases => "m.room.aliases",
             EventType::RoomAvatar => "m.room.avatar",
             EventType::RoomCanonicalAlias => "m.room.canonical_alias",
             EventType::RoomCreate => "m.room.create",
+            EventType::RoomEncrypted => "m.room.encrypted",
             EventType::RoomGuestAccess => "m.room.guest_access",
             EventType::RoomHistoryVisibility => "m.room.history_visibility",
             EventType::RoomJoinRules => "m.room.join_rules",
@@ -273,10 +393,13 @@ impl Display for EventType {
             EventType::RoomPinnedEvents => "m.room.pinned_events",
             EventType::RoomPowerLevels => "m.room.power_levels",
             EventType::RoomRedaction => "m.room.redaction",
+            EventType::RoomServerAcl => "m.room.server_acl",
             EventType::RoomThirdPartyInvite => "m.room.third_party_invite",
             EventType::RoomTopic => "m.room.topic",
+            EventType::SpaceChild => "m.space.child",
             EventType::Tag => "m.tag",
             EventType::Typing => "m.typing",
+            EventType::VoiceBroadcastInfo => "m.voice_broadcast_info",
             EventType::Custom(ref event_type) => event_type,
         };
 
@@ -291,13 +414,24 @@ impl<'a> From<&'a str> for EventType {
             "m.call.candidates" => EventType::CallCandidates,
             "m.call.hangup" => EventType::CallHangup,
             "m.call.invite" => EventType::CallInvite,
+            "m.call.member" => EventType::CallMember,
             "m.direct" => EventType::Direct,
+            "m.key.verification.accept" => EventType::KeyVerificationAccept,
+            "m.key.verification.done" => EventType::KeyVerificationDone,
+            "m.key.verification.key" => EventType::KeyVerificationKey,
+            "m.key.verification.mac" => EventType::KeyVerificationMac,
+            "m.key.verification.ready" => EventType::KeyVerificationReady,
+            "m.poll.end" => EventType::PollEnd,
+            "m.poll.response" => EventType::PollResponse,
+            "m.poll.start" => EventType::PollStart,
             "m.presence" => EventType::Presence,
+            "m.reaction" => EventType::Reaction,
             "m.receipt" => EventType::Receipt,
             "m.room.aliases" => EventType::RoomAliases,
             "m.room.avatar" => EventType::RoomAvatar,
             "m.room.canonical_alias" => EventType::RoomCanonicalAlias,
             "m.room.create" => EventType::RoomCreate,
+            "m.room.encrypted" => EventType::RoomEncrypted,
             "m.room.guest_access" => EventType::RoomGuestAccess,
             "m.room.history_visibility" => EventType::RoomHistoryVisibility,
             "m.room.join_rules" => EventType::RoomJoinRules,
@@ -307,10 +441,13 @@ impl<'a> From<&'a str> for EventType {
             "m.room.pinned_events" => EventType::RoomPinnedEvents,
             "m.room.power_levels" => EventType::RoomPowerLevels,
             "m.room.redaction" => EventType::RoomRedaction,
+            "m.room.server_acl" => EventType::RoomServerAcl,
             "m.room.third_party_invite" => EventType::RoomThirdPartyInvite,
             "m.room.topic" => EventType::RoomTopic,
+            "m.space.child" => EventType::SpaceChild,
             "m.tag" => EventType::Tag,
             "m.typing" => EventType::Typing,
+            "m.voice_broadcast_info" => EventType::VoiceBroadcastInfo,
             event_type => EventType::Custom(event_type.to_string()),
         }
     }
@@ -351,11 +488,153 @@ impl<'de> Deserialize<'de> for EventType {
     }
 }
 
+impl MxcUri {
+    /// The name of the server the content was uploaded to.
+    pub fn server_name(&self) -> &str {
+        &self.uri["mxc://".len()..self.media_id_start - 1]
+    }
+
+    /// The ID of the content on its server.
+    pub fn media_id(&self) -> &str {
+        &self.uri[self.media_id_start..]
+    }
+}
+
+impl Display for MxcUri {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl AsRef<str> for MxcUri {
+    fn as_ref(&self) -> &str {
+        &self.uri
+    }
+}
+
+impl FromStr for MxcUri {
+    type Err = MxcUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("mxc://") {
+            return Err(MxcUriError);
+        }
+
+        let rest = &s["mxc://".len()..];
+        let slash = rest.find('/').ok_or(MxcUriError)?;
+        let (server_name, media_id) = (&rest[..slash], &rest[slash + 1..]);
+        let is_media_id_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+
+        if server_name.is_empty() || media_id.is_empty() || !media_id.chars().all(is_media_id_char)
+        {
+            return Err(MxcUriError);
+        }
+
+        Ok(MxcUri {
+            uri: s.to_string(),
+            media_id_start: "mxc://".len() + slash + 1,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a str> for MxcUri {
+    type Error = MxcUriError;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Serialize for MxcUri {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.uri)
+    }
+}
+
+impl<'de> Deserialize<'de> for MxcUri {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MxcUriVisitor;
+
+        impl<'de> Visitor<'de> for MxcUriVisitor {
+            type Value = MxcUri;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                write!(formatter, "an mxc:// URI as a string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                v.parse()
+                    .map_err(|_| E::custom(format!("invalid mxc:// URI: {}", v)))
+            }
+        }
+
+        deserializer.deserialize_str(MxcUriVisitor)
+    }
+}
+
+/// Deserializes an optional MXC URI, treating one that is not a valid *mxc://* URI as absent.
+pub(crate) fn deserialize_lenient_mxc_uri<'de, D>(
+    deserializer: D,
+) -> Result<Option<MxcUri>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let uri = Option::<String>::deserialize(deserializer)?;
+
+    Ok(uri.and_then(|uri| uri.parse().ok()))
+}
+
+/// Writes `value` as canonical JSON.
+///
+/// The keys are sorted here rather than relying on the order of `serde_json::Map`, which keeps
+/// insertion order if serde_json's `preserve_order` feature is enabled.
+fn write_canonical_json(value: &Value, json: &mut Vec<u8>) -> Result<(), JsonError> {
+    match *value {
+        Value::Array(ref values) => {
+            json.push(b'[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    json.push(b',');
+                }
+                write_canonical_json(value, json)?;
+            }
+            json.push(b']');
+        }
+        Value::Object(ref object) => {
+            let mut entries = object.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            json.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    json.push(b',');
+                }
+                to_writer(&mut *json, key)?;
+                json.push(b':');
+                write_canonical_json(value, json)?;
+            }
+            json.push(b'}');
+        }
+        _ => to_writer(&mut *json, value)?,
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::{from_str, to_string};
 
-    use super::EventType;
+    use super::{CanonicalJson, EventType, MxcUri};
 
     #[test]
     fn event_types_serialize_to_display_form() {
@@ -388,4 +667,160 @@ mod tests {
             EventType::Custom("io.ruma.test".to_string())
         )
     }
+
+    #[test]
+    fn event_type_from_uri_with_fragment() {
+        assert_eq!(
+            EventType::from_uri("m.room.message#m.text"),
+            (EventType::RoomMessage, Some("m.text".to_string()))
+        );
+    }
+
+    #[test]
+    fn event_type_from_uri_without_fragment() {
+        assert_eq!(
+            EventType::from_uri("m.room.message"),
+            (EventType::RoomMessage, None)
+        );
+        assert_eq!(
+            EventType::from_uri("io.ruma.test"),
+            (EventType::Custom("io.ruma.test".to_string()), None)
+        );
+    }
+
+    #[test]
+    fn event_type_with_fragment() {
+        let uri = EventType::RoomMessage.with_fragment("m.text");
+
+        assert_eq!(uri, "m.room.message#m.text");
+        assert_eq!(
+            EventType::from_uri(&uri),
+            (EventType::RoomMessage, Some("m.text".to_string()))
+        );
+    }
+
+    #[test]
+    fn mxc_uris() {
+        let uri = "mxc://example.org/SEsfnsuifSDFSSEF"
+            .parse::<MxcUri>()
+            .unwrap();
+
+        assert_eq!(uri.server_name(), "example.org");
+        assert_eq!(uri.media_id(), "SEsfnsuifSDFSSEF");
+        assert_eq!(uri.to_string(), "mxc://example.org/SEsfnsuifSDFSSEF");
+
+        assert!("https://example.org/SEsfnsuifSDFSSEF"
+            .parse::<MxcUri>()
+            .is_err());
+        assert!("mxc://example.org".parse::<MxcUri>().is_err());
+        assert!("mxc://example.org/".parse::<MxcUri>().is_err());
+        assert!("mxc:///SEsfnsuifSDFSSEF".parse::<MxcUri>().is_err());
+        assert!("mxc://example.org/a/b".parse::<MxcUri>().is_err());
+    }
+
+    #[test]
+    fn canonical_json() {
+        use room::power_levels::{PowerLevelsEvent, PowerLevelsEventContent};
+
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{
+                "users": {"@bob:example.com": 50, "@alice:example.com": 100},
+                "events": {"m.room.name": 50, "m.room.avatar": 50},
+                "ban": 100
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(content.to_canonical_json().unwrap()).unwrap(),
+            r#"{"ban":100,"events":{"m.room.avatar":50,"m.room.name":50},"users":{"@alice:example.com":100,"@bob:example.com":50}}"#
+        );
+
+        let event = from_str::<PowerLevelsEvent>(
+            r#"{
+                "type": "m.room.power_levels",
+                "state_key": "",
+                "sender": "@alice:example.com",
+                "origin_server_ts": 1,
+                "event_id": "$h29iv0s8:example.com",
+                "content": {"users": {"@alice:example.com": 100}},
+                "unsigned": {"b": ["\u00e9", {"d": 1, "c": null}], "a": 1}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(event.to_canonical_json().unwrap()).unwrap(),
+            "{\"content\":{\"users\":{\"@alice:example.com\":100}},\"event_id\":\"$h29iv0s8:example.com\",\"origin_server_ts\":1,\"sender\":\"@alice:example.com\",\"state_key\":\"\",\"type\":\"m.room.power_levels\",\"unsigned\":{\"a\":1,\"b\":[\"\u{e9}\",{\"c\":null,\"d\":1}]}}"
+        );
+    }
+
+    #[test]
+    fn derived_state_event() {
+        use ruma_identifiers::{EventId, RoomId, UserId};
+        use serde_json::{to_value, Value};
+
+        use super::{Event, RoomEvent, StateEvent};
+        use stripped::StrippedStateContent;
+
+        #[derive(Clone, Debug, StateEvent)]
+        struct CustomStateEvent {
+            content: Value,
+            event_id: EventId,
+            #[serde(rename = "type")]
+            event_type: EventType,
+            origin_server_ts: u64,
+            prev_content: Option<Value>,
+            room_id: Option<RoomId>,
+            sender: UserId,
+            state_key: String,
+            unsigned: Option<Value>,
+        }
+
+        let event = from_str::<CustomStateEvent>(
+            r#"{
+                "content": {"foo": "bar"},
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "prev_content": {"foo": "baz"},
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "org.example.custom"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.content()["foo"], "bar");
+        assert_eq!(
+            event.event_type(),
+            &EventType::Custom("org.example.custom".to_string())
+        );
+        assert_eq!(event.sender().to_string(), "@carl:example.com");
+        assert_eq!(event.room_id(), None);
+        assert_eq!(event.prev_content().unwrap()["foo"], "baz");
+        assert_eq!(event.state_key(), "");
+        assert_eq!(
+            to_value(&event).unwrap(),
+            from_str::<Value>(
+                r#"{
+                    "content": {"foo": "bar"},
+                    "event_id": "$h29iv0s8:example.com",
+                    "origin_server_ts": 1,
+                    "prev_content": {"foo": "baz"},
+                    "room_id": null,
+                    "sender": "@carl:example.com",
+                    "state_key": "",
+                    "type": "org.example.custom",
+                    "unsigned": null
+                }"#
+            )
+            .unwrap()
+        );
+
+        let stripped = StrippedStateContent::from(event);
+
+        assert_eq!(stripped.content["foo"], "bar");
+        assert_eq!(stripped.sender.unwrap().to_string(), "@carl:example.com");
+        assert_eq!(stripped.state_key, "");
+    }
 }
@@ -1,12 +1,17 @@
 macro_rules! impl_enum {
     ($name:ident { $($variant:ident => $s:expr,)+ }) => {
-        impl ::std::fmt::Display for $name {
-            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-                let variant = match *self {
+        impl $name {
+            /// The string this variant is displayed and parsed as.
+            pub(crate) fn as_str(&self) -> &'static str {
+                match *self {
                     $($name::$variant => $s,)*
-                };
+                }
+            }
+        }
 
-                write!(f, "{}", variant)
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+                write!(f, "{}", self.as_str())
             }
         }
 
@@ -33,7 +38,7 @@ macro_rules! event {
         }
     ) => {
         $(#[$attr])*
-        #[derive(Clone, Debug, Deserialize, Serialize)]
+        #[derive(Clone, Debug, Event, PartialEq)]
         pub struct $name {
             /// The event's content.
             pub content: $content_type,
@@ -47,27 +52,9 @@ macro_rules! event {
                 pub $field_name: $field_type
             ),*
         }
-
-        impl_event!($name, $content_type);
     }
 }
 
-macro_rules! impl_event {
-    ($name:ident, $content_type:ty) => {
-        impl $crate::Event for $name {
-            type Content = $content_type;
-
-            fn content(&self) -> &<$name as $crate::Event>::Content {
-                &self.content
-            }
-
-            fn event_type(&self) -> &$crate::EventType {
-                &self.event_type
-            }
-        }
-    };
-}
-
 macro_rules! room_event {
     (   $(#[$attr:meta])*
         pub struct $name:ident($content_type:ty) {
@@ -78,13 +65,13 @@ macro_rules! room_event {
         }
     ) => {
         $(#[$attr])*
-        #[derive(Clone, Debug, Deserialize, Serialize)]
+        #[derive(Clone, Debug, PartialEq, RoomEvent)]
         pub struct $name {
             /// The event's content.
             pub content: $content_type,
 
             /// The unique identifier for the event.
-            pub event_id: ::ruma_identifiers::EventId,
+            pub event_id: $crate::exports::EventId,
 
             /// The type of the event.
             #[serde(rename="type")]
@@ -95,53 +82,23 @@ macro_rules! room_event {
 
             /// The unique identifier for the room associated with this event.
             #[serde(skip_serializing_if="Option::is_none")]
-            pub room_id: Option<::ruma_identifiers::RoomId>,
+            pub room_id: Option<$crate::exports::RoomId>,
 
             /// Additional key-value pairs not signed by the homeserver.
             #[serde(skip_serializing_if="Option::is_none")]
-            pub unsigned: Option<::serde_json::Value>,
+            pub unsigned: Option<$crate::exports::Value>,
 
             /// The unique identifier for the user who sent this event.
-            pub sender: ::ruma_identifiers::UserId,
+            pub sender: $crate::exports::UserId,
 
             $(
                 $(#[$field_attr])*
                 pub $field_name: $field_type
             ),*
         }
-
-        impl_room_event!($name, $content_type);
     }
 }
 
-macro_rules! impl_room_event {
-    ($name:ident, $content_type:ty) => {
-        impl_event!($name, $content_type);
-
-        impl $crate::RoomEvent for $name {
-            fn event_id(&self) -> &::ruma_identifiers::EventId {
-                &self.event_id
-            }
-
-            fn origin_server_ts(&self) -> u64 {
-                self.origin_server_ts
-            }
-
-            fn room_id(&self) -> Option<&::ruma_identifiers::RoomId> {
-                self.room_id.as_ref()
-            }
-
-            fn unsigned(&self) -> Option<&::serde_json::Value> {
-                self.unsigned.as_ref()
-            }
-
-            fn sender(&self) -> &::ruma_identifiers::UserId {
-                &self.sender
-            }
-        }
-    };
-}
-
 macro_rules! state_event {
     (   $(#[$attr:meta])*
         pub struct $name:ident($content_type:ty) {
@@ -153,13 +110,13 @@ macro_rules! state_event {
     ) => {
         $(#[$attr])*
         #[allow(missing_docs)]
-        #[derive(Clone, Debug, Deserialize, Serialize)]
+        #[derive(Clone, Debug, PartialEq, StateEvent)]
         pub struct $name {
             /// The event's content.
             pub content: $content_type,
 
             /// The unique identifier for the event.
-            pub event_id: ::ruma_identifiers::EventId,
+            pub event_id: $crate::exports::EventId,
 
             /// The type of the event.
             #[serde(rename="type")]
@@ -174,40 +131,22 @@ macro_rules! state_event {
 
             /// The unique identifier for the room associated with this event.
             #[serde(skip_serializing_if="Option::is_none")]
-            pub room_id: Option<::ruma_identifiers::RoomId>,
+            pub room_id: Option<$crate::exports::RoomId>,
 
             /// A key that determines which piece of room state the event represents.
             pub state_key: String,
 
             /// Additional key-value pairs not signed by the homeserver.
             #[serde(skip_serializing_if="Option::is_none")]
-            pub unsigned: Option<::serde_json::Value>,
+            pub unsigned: Option<$crate::exports::Value>,
 
             /// The unique identifier for the user associated with this event.
-            pub sender: ::ruma_identifiers::UserId,
+            pub sender: $crate::exports::UserId,
 
             $(
                 $(#[$field_attr])*
                 pub $field_name: $field_type
             ),*
         }
-
-        impl_state_event!($name, $content_type);
     }
 }
-
-macro_rules! impl_state_event {
-    ($name:ident, $content_type:ty) => {
-        impl_room_event!($name, $content_type);
-
-        impl $crate::StateEvent for $name {
-            fn prev_content(&self) -> Option<&Self::Content> {
-                self.prev_content.as_ref()
-            }
-
-            fn state_key(&self) -> &str {
-                &self.state_key
-            }
-        }
-    };
-}
@@ -0,0 +1,23 @@
+//! Types for the *m.poll.end* event.
+
+use room::message::Relation;
+
+room_event! {
+    /// Closes a poll, after which no more votes are counted.
+    pub struct EndEvent(EndEventContent) {}
+}
+
+/// The payload of an `EndEvent`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EndEventContent {
+    /// The poll being closed, as a `Relation::Reference` to its *m.poll.start* event.
+    #[serde(rename = "m.relates_to")]
+    pub relates_to: Relation,
+    /// Marks the poll as closed.
+    #[serde(rename = "org.matrix.msc3381.poll.end")]
+    pub poll_end: PollEndContent,
+}
+
+/// The marker of a closed poll. It has no fields.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollEndContent {}
@@ -0,0 +1,143 @@
+//! Modules for events in the *m.poll* namespace, as proposed in MSC3381.
+//!
+//! This module also contains types shared by events in its child namespaces.
+
+pub mod end;
+pub mod response;
+pub mod start;
+
+/// How the results of a poll are shown while it is open.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PollKind {
+    /// Votes are visible to everyone while the poll is open.
+    #[serde(rename = "org.matrix.msc3381.poll.disclosed")]
+    Disclosed,
+    /// Votes are only shown once the poll has ended.
+    #[serde(rename = "org.matrix.msc3381.poll.undisclosed")]
+    Undisclosed,
+}
+
+impl_enum! {
+    PollKind {
+        Disclosed => "org.matrix.msc3381.poll.disclosed",
+        Undisclosed => "org.matrix.msc3381.poll.undisclosed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::EventId;
+    use serde_json::{from_str, to_value, Value};
+
+    use super::end::EndEventContent;
+    use super::response::PollResponseContent;
+    use super::start::{PollAnswer, PollAnswerContent, PollQuestion};
+    use super::PollKind;
+    use collections::all::RoomEvent;
+    use room::message::{Reference, Relation};
+
+    fn room_event(json: &str) -> RoomEvent {
+        let event = from_str::<RoomEvent>(json).unwrap();
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+        event
+    }
+
+    #[test]
+    fn poll_lifecycle() {
+        let start = room_event(
+            r#"{
+                "content": {
+                    "org.matrix.msc3381.poll.start": {
+                        "answers": [
+                            {"id": "pizza", "org.matrix.msc3381.poll.answer": {"body": "Pizza"}},
+                            {"id": "tacos", "org.matrix.msc3381.poll.answer": {"body": "Tacos"}}
+                        ],
+                        "kind": "org.matrix.msc3381.poll.disclosed",
+                        "max_selections": 1,
+                        "question": {"body": "What should we order?"}
+                    }
+                },
+                "event_id": "$poll:example.org",
+                "origin_server_ts": 1,
+                "sender": "@alice:example.org",
+                "type": "m.poll.start"
+            }"#,
+        );
+        let response = room_event(
+            r#"{
+                "content": {
+                    "m.relates_to": {"event_id": "$poll:example.org", "rel_type": "m.reference"},
+                    "org.matrix.msc3381.poll.response": {"answers": ["tacos"]}
+                },
+                "event_id": "$vote:example.org",
+                "origin_server_ts": 2,
+                "sender": "@bob:example.org",
+                "type": "m.poll.response"
+            }"#,
+        );
+        let end = room_event(
+            r#"{
+                "content": {
+                    "m.relates_to": {"event_id": "$poll:example.org", "rel_type": "m.reference"},
+                    "org.matrix.msc3381.poll.end": {}
+                },
+                "event_id": "$end:example.org",
+                "origin_server_ts": 3,
+                "sender": "@alice:example.org",
+                "type": "m.poll.end"
+            }"#,
+        );
+
+        let start = match start {
+            RoomEvent::PollStart(event) => event.content.poll_start,
+            _ => panic!("expected a poll start event"),
+        };
+        assert_eq!(start.kind, PollKind::Disclosed);
+        assert_eq!(start.max_selections, 1);
+        assert_eq!(
+            start.question,
+            PollQuestion {
+                body: "What should we order?".to_string()
+            }
+        );
+        assert_eq!(
+            start.answers[1],
+            PollAnswer {
+                id: "tacos".to_string(),
+                answer: PollAnswerContent {
+                    body: "Tacos".to_string()
+                },
+            }
+        );
+
+        let poll = Relation::Reference(Reference {
+            event_id: EventId::try_from("$poll:example.org").unwrap(),
+        });
+
+        match response {
+            RoomEvent::PollResponse(event) => {
+                assert_eq!(event.content.relates_to, poll);
+                assert_eq!(
+                    event.content.poll_response,
+                    PollResponseContent {
+                        answers: vec!["tacos".to_string()]
+                    }
+                );
+            }
+            _ => panic!("expected a poll response event"),
+        }
+
+        match end {
+            RoomEvent::PollEnd(event) => assert_eq!(
+                event.content,
+                EndEventContent {
+                    relates_to: poll,
+                    poll_end: Default::default(),
+                }
+            ),
+            _ => panic!("expected a poll end event"),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Types for the *m.poll.response* event.
+
+use room::message::Relation;
+
+room_event! {
+    /// A vote in a poll.
+    pub struct ResponseEvent(ResponseEventContent) {}
+}
+
+/// The payload of a `ResponseEvent`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ResponseEventContent {
+    /// The poll being voted in, as a `Relation::Reference` to its *m.poll.start* event.
+    #[serde(rename = "m.relates_to")]
+    pub relates_to: Relation,
+    /// The vote.
+    #[serde(rename = "org.matrix.msc3381.poll.response")]
+    pub poll_response: PollResponseContent,
+}
+
+/// The answers selected in a vote.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollResponseContent {
+    /// The IDs of the selected answers. An empty list withdraws an earlier vote.
+    pub answers: Vec<String>,
+}
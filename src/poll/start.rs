@@ -0,0 +1,59 @@
+//! Types for the *m.poll.start* event.
+
+use super::PollKind;
+
+room_event! {
+    /// Starts a poll in a room.
+    pub struct StartEvent(StartEventContent) {}
+}
+
+/// The payload of a `StartEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StartEventContent {
+    /// The poll.
+    #[serde(rename = "org.matrix.msc3381.poll.start")]
+    pub poll_start: PollStartContent,
+}
+
+/// The question and possible answers of a poll.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollStartContent {
+    /// The possible answers.
+    pub answers: Vec<PollAnswer>,
+    /// How the results are shown while the poll is open.
+    pub kind: PollKind,
+    /// The maximum number of answers a user may select. Defaults to 1.
+    #[serde(default = "default_max_selections")]
+    pub max_selections: u64,
+    /// The question of the poll.
+    pub question: PollQuestion,
+}
+
+/// The question of a poll.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollQuestion {
+    /// The text of the question.
+    pub body: String,
+}
+
+/// A possible answer to a poll.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollAnswer {
+    /// The ID of the answer, used to select it in an *m.poll.response*.
+    pub id: String,
+    /// The answer.
+    #[serde(rename = "org.matrix.msc3381.poll.answer")]
+    pub answer: PollAnswerContent,
+}
+
+/// The content of a possible answer to a poll.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PollAnswerContent {
+    /// The text of the answer.
+    pub body: String,
+}
+
+/// The default value of `max_selections`.
+fn default_max_selections() -> u64 {
+    1
+}
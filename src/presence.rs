@@ -31,6 +31,13 @@ pub struct PresenceEventContent {
 
     /// The presence state for this user.
     pub presence: PresenceState,
+
+    /// The user whose presence this is.
+    ///
+    /// This duplicates the `sender` of the containing event, and is only present in events sent
+    /// by older homeservers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<UserId>,
 }
 
 /// A description of a user's connectivity and availability for chat.
@@ -77,6 +84,7 @@ mod tests {
                 displayname: None,
                 last_active_ago: Some(2478593),
                 presence: PresenceState::Online,
+                user_id: None,
             },
             event_type: EventType::Presence,
             sender: UserId::try_from("@example:localhost").unwrap(),
@@ -98,4 +106,26 @@ mod tests {
             event.sender
         );
     }
+
+    #[test]
+    fn deserialization_with_user_id_in_content() {
+        let event = from_str::<PresenceEvent>(
+            r#"{"content":{"presence":"unavailable","user_id":"@example:localhost"},"type":"m.presence","sender":"@example:localhost"}"#
+        ).unwrap();
+
+        assert_eq!(
+            event.sender,
+            UserId::try_from("@example:localhost").unwrap()
+        );
+        assert_eq!(event.content.user_id, Some(event.sender.clone()));
+        assert_eq!(event.content.presence, PresenceState::Unavailable);
+    }
+
+    #[test]
+    fn deserialization_requires_sender() {
+        assert!(from_str::<PresenceEvent>(
+            r#"{"content":{"presence":"online"},"type":"m.presence"}"#
+        )
+        .is_err());
+    }
 }
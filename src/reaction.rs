@@ -0,0 +1,223 @@
+//! Types for the *m.reaction* event.
+
+use std::collections::HashMap;
+
+use ruma_identifiers::{EventId, UserId};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+room_event! {
+    /// A reaction to another event, e.g. an emoji.
+    pub struct ReactionEvent(ReactionEventContent) {}
+}
+
+/// The payload of a `ReactionEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReactionEventContent {
+    /// The event being reacted to and the reaction.
+    #[serde(rename = "m.relates_to")]
+    pub relates_to: Annotation,
+}
+
+/// An annotation of another event, sent as an *m.relates_to* with a `rel_type` of *m.annotation*.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Annotation {
+    /// The event being annotated.
+    pub event_id: EventId,
+
+    /// The annotation, e.g. an emoji.
+    pub key: String,
+}
+
+/// The JSON form of an `Annotation`.
+#[derive(Deserialize, Serialize)]
+struct AnnotationJson {
+    event_id: EventId,
+    key: String,
+    rel_type: String,
+}
+
+impl Serialize for Annotation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        AnnotationJson {
+            event_id: self.event_id.clone(),
+            key: self.key.clone(),
+            rel_type: "m.annotation".to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Annotation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let annotation = AnnotationJson::deserialize(deserializer)?;
+
+        if annotation.rel_type != "m.annotation" {
+            return Err(D::Error::custom(format!(
+                "expected a rel_type of m.annotation, found {}",
+                annotation.rel_type
+            )));
+        }
+
+        Ok(Annotation {
+            event_id: annotation.event_id,
+            key: annotation.key,
+        })
+    }
+}
+
+/// Groups reactions by their key, collecting the users who reacted with each key.
+///
+/// A user who reacted with the same key more than once is only included once, for their latest
+/// reaction. Users are listed in the order of their reactions.
+pub fn aggregate_reactions<'a, I>(reactions: I) -> HashMap<String, Vec<&'a UserId>>
+where
+    I: IntoIterator<Item = &'a ReactionEvent>,
+{
+    let mut latest: HashMap<(&'a str, &'a UserId), &'a ReactionEvent> = HashMap::new();
+
+    for reaction in reactions {
+        let key = (reaction.content.relates_to.key.as_str(), &reaction.sender);
+
+        match latest.get(&key) {
+            Some(previous) if previous.origin_server_ts > reaction.origin_server_ts => {}
+            _ => {
+                latest.insert(key, reaction);
+            }
+        }
+    }
+
+    let mut by_key: HashMap<String, Vec<&'a ReactionEvent>> = HashMap::new();
+
+    for ((key, _), reaction) in latest {
+        by_key.entry(key.to_string()).or_default().push(reaction);
+    }
+
+    by_key
+        .into_iter()
+        .map(|(key, mut reactions)| {
+            reactions.sort_by_key(|reaction| reaction.origin_server_ts);
+
+            let senders = reactions
+                .into_iter()
+                .map(|reaction| &reaction.sender)
+                .collect();
+
+            (key, senders)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{aggregate_reactions, ReactionEvent};
+
+    fn reaction(sender: &str, key: &str, origin_server_ts: u64) -> ReactionEvent {
+        from_str::<ReactionEvent>(&format!(
+            r#"{{
+                "content": {{
+                    "m.relates_to": {{
+                        "event_id": "$original:example.org",
+                        "key": "{}",
+                        "rel_type": "m.annotation"
+                    }}
+                }},
+                "event_id": "${}:example.org",
+                "origin_server_ts": {},
+                "sender": "{}",
+                "type": "m.reaction"
+            }}"#,
+            key, origin_server_ts, origin_server_ts, sender
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn reaction_round_trips() {
+        let json = r#"{
+            "content": {
+                "m.relates_to": {
+                    "event_id": "$original:example.org",
+                    "key": "👍",
+                    "rel_type": "m.annotation"
+                }
+            },
+            "event_id": "$reaction:example.org",
+            "origin_server_ts": 1,
+            "sender": "@alice:example.org",
+            "type": "m.reaction"
+        }"#;
+        let event = from_str::<ReactionEvent>(json).unwrap();
+
+        assert_eq!(event.content.relates_to.key, "👍");
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+
+    #[test]
+    fn other_relation_types_are_rejected() {
+        assert!(from_str::<ReactionEvent>(
+            r#"{
+                "content": {
+                    "m.relates_to": {
+                        "event_id": "$original:example.org",
+                        "key": "👍",
+                        "rel_type": "m.reference"
+                    }
+                },
+                "event_id": "$reaction:example.org",
+                "origin_server_ts": 1,
+                "sender": "@alice:example.org",
+                "type": "m.reaction"
+            }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn aggregate_by_key() {
+        let reactions = vec![
+            reaction("@alice:example.org", "👍", 1),
+            reaction("@bob:example.org", "👍", 2),
+            reaction("@alice:example.org", "🎉", 3),
+        ];
+
+        let aggregated = aggregate_reactions(&reactions);
+
+        assert_eq!(aggregated.len(), 2);
+        assert_eq!(
+            aggregated["👍"]
+                .iter()
+                .map(|user_id| user_id.to_string())
+                .collect::<Vec<_>>(),
+            vec!["@alice:example.org", "@bob:example.org"]
+        );
+        assert_eq!(aggregated["🎉"].len(), 1);
+    }
+
+    #[test]
+    fn repeated_reactions_keep_the_latest() {
+        let reactions = vec![
+            reaction("@alice:example.org", "👍", 5),
+            reaction("@bob:example.org", "👍", 2),
+            reaction("@alice:example.org", "👍", 1),
+        ];
+
+        let aggregated = aggregate_reactions(&reactions);
+
+        assert_eq!(
+            aggregated["👍"]
+                .iter()
+                .map(|user_id| user_id.to_string())
+                .collect::<Vec<_>>(),
+            vec!["@bob:example.org", "@alice:example.org"]
+        );
+    }
+}
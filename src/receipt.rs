@@ -1,8 +1,11 @@
 //! Types for the *m.receipt* event.
 
 use std::collections::HashMap;
+use std::fmt::{Formatter, Result as FmtResult};
 
 use ruma_identifiers::{EventId, RoomId, UserId};
+use serde::de::{Error as SerdeError, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 event! {
     /// Informs the client of new receipts.
@@ -23,7 +26,7 @@ event! {
 pub type ReceiptEventContent = HashMap<EventId, Receipts>;
 
 /// A collection of receipts.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Receipts {
     /// A collection of users who have sent *m.read* receipts for this event.
     #[serde(rename = "m.read")]
@@ -37,8 +40,106 @@ pub struct Receipts {
 pub type UserReceipts = HashMap<UserId, Receipt>;
 
 /// An acknowledgement of an event.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Receipt {
+    /// The thread this receipt applies to, if it is a threaded receipt.
+    ///
+    /// An unthreaded receipt (`None`) applies to the whole room.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<ReceiptThread>,
+
     /// The timestamp the receipt was sent at.
     pub ts: u64,
 }
+
+/// The thread a threaded receipt applies to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ReceiptThread {
+    /// The main timeline of the room, i.e. events that are not part of any thread.
+    Main,
+
+    /// The thread with the given thread root event ID.
+    Thread(String),
+}
+
+impl Serialize for ReceiptThread {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            ReceiptThread::Main => serializer.serialize_str("main"),
+            ReceiptThread::Thread(ref thread_id) => serializer.serialize_str(thread_id),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ReceiptThread {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ReceiptThreadVisitor;
+
+        impl<'de> Visitor<'de> for ReceiptThreadVisitor {
+            type Value = ReceiptThread;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                write!(formatter, "\"main\" or a thread root event ID as a string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                match v {
+                    "main" => Ok(ReceiptThread::Main),
+                    thread_id => Ok(ReceiptThread::Thread(thread_id.to_string())),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(ReceiptThreadVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_string};
+
+    use super::{Receipt, ReceiptThread};
+
+    #[test]
+    fn main_thread_receipt_round_trips() {
+        let json = r#"{"thread_id":"main","ts":1436451550453}"#;
+        let receipt = from_str::<Receipt>(json).unwrap();
+
+        assert_eq!(receipt.thread_id, Some(ReceiptThread::Main));
+        assert_eq!(receipt.ts, 1436451550453);
+        assert_eq!(to_string(&receipt).unwrap(), json);
+    }
+
+    #[test]
+    fn threaded_receipt_round_trips() {
+        let json = r#"{"thread_id":"$thread_root:example.com","ts":1436451550453}"#;
+        let receipt = from_str::<Receipt>(json).unwrap();
+
+        assert_eq!(
+            receipt.thread_id,
+            Some(ReceiptThread::Thread(
+                "$thread_root:example.com".to_string()
+            ))
+        );
+        assert_eq!(to_string(&receipt).unwrap(), json);
+    }
+
+    #[test]
+    fn unthreaded_receipt_round_trips() {
+        let json = r#"{"ts":1436451550453}"#;
+        let receipt = from_str::<Receipt>(json).unwrap();
+
+        assert_eq!(receipt.thread_id, None);
+        assert_eq!(to_string(&receipt).unwrap(), json);
+    }
+}
@@ -1,15 +1,109 @@
 //! Types for the *m.room.aliases* event.
+//!
+//! *m.room.aliases* is deprecated in favor of the `alt_aliases` of *m.room.canonical_alias*. The
+//! types in this module are marked as deprecated unless the `compat` feature is enabled, for
+//! applications that still need to handle these events from older servers.
+
+#![allow(deprecated)]
 
 use ruma_identifiers::RoomAliasId;
 
+use room::create::RoomVersion;
+use room::{parse_alias, AliasFormatError};
+
 state_event! {
     /// Informs the room about what room aliases it has been given.
+    #[cfg_attr(
+        not(feature = "compat"),
+        deprecated(
+            since = "0.11.0",
+            note = "Use m.room.canonical_alias with alt_aliases instead"
+        )
+    )]
     pub struct AliasesEvent(AliasesEventContent) {}
 }
 
 /// The payload of an `AliasesEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(
+    not(feature = "compat"),
+    deprecated(
+        since = "0.11.0",
+        note = "Use m.room.canonical_alias with alt_aliases instead"
+    )
+)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct AliasesEventContent {
     /// A list of room aliases.
     pub aliases: Vec<RoomAliasId>,
 }
+
+impl AliasesEventContent {
+    /// Creates the content from aliases given as strings, checking that each has the form
+    /// `#localpart:server_name`.
+    pub fn new(aliases: &[&str]) -> Result<Self, AliasFormatError> {
+        Ok(AliasesEventContent {
+            aliases: aliases
+                .iter()
+                .map(|alias| parse_alias(alias))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The content of this event after a redaction in a room of the given version.
+    ///
+    /// Room versions 1 to 5 keep `aliases`, later versions remove every alias.
+    pub fn redact(&self, room_version: &RoomVersion) -> AliasesEventContent {
+        if room_version.is_older_than(6) {
+            self.clone()
+        } else {
+            AliasesEventContent {
+                aliases: Vec::new(),
+            }
+        }
+    }
+
+    /// The aliases of this event, for use as the `alt_aliases` of an *m.room.canonical_alias*
+    /// event.
+    pub fn to_canonical_alias_alt_aliases(&self) -> Vec<RoomAliasId> {
+        self.aliases.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::RoomAliasId;
+    use serde_json::from_str;
+
+    use super::AliasesEventContent;
+    use room::create::RoomVersion;
+
+    #[test]
+    fn migrate_to_alt_aliases() {
+        let content = from_str::<AliasesEventContent>(
+            r##"{"aliases":["#somewhere:localhost","#elsewhere:localhost"]}"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            content.to_canonical_alias_alt_aliases(),
+            vec![
+                RoomAliasId::try_from("#somewhere:localhost").unwrap(),
+                RoomAliasId::try_from("#elsewhere:localhost").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn redact() {
+        let content = AliasesEventContent::new(&["#somewhere:localhost"]).unwrap();
+
+        assert_eq!(content.redact(&RoomVersion::V5), content);
+        assert!(content.redact(&RoomVersion::V6).aliases.is_empty());
+        assert!(content
+            .redact(&RoomVersion::Custom("org.example.12".to_string()))
+            .aliases
+            .is_empty());
+    }
+}
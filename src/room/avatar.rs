@@ -2,6 +2,9 @@
 
 use super::ImageInfo;
 
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
 state_event! {
     /// A picture that is associated with the room.
     ///
@@ -10,7 +13,7 @@ state_event! {
 }
 
 /// The payload of an `AvatarEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct AvatarEventContent {
     /// Information about the avatar image.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -24,3 +27,10 @@ pub struct AvatarEventContent {
     /// URL of the avatar image.
     pub url: String,
 }
+
+impl AvatarEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
@@ -2,14 +2,197 @@
 
 use ruma_identifiers::RoomAliasId;
 
+use room::create::RoomVersion;
+use room::{parse_alias, AliasFormatError};
+use RedactedStateEventContent;
+
 state_event! {
     /// Informs the room as to which alias is the canonical one.
     pub struct CanonicalAliasEvent(CanonicalAliasEventContent) {}
 }
 
 /// The payload of a `CanonicalAliasEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CanonicalAliasEventContent {
     /// The canonical alias.
-    pub alias: RoomAliasId,
+    ///
+    /// If this is `None` (either `null` or absent in JSON), the room has no canonical alias.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<RoomAliasId>,
+
+    /// Alternative aliases the room advertises.
+    ///
+    /// This replaces the deprecated *m.room.aliases* event.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alt_aliases: Vec<RoomAliasId>,
+}
+
+impl CanonicalAliasEventContent {
+    /// Creates the content from aliases given as strings, checking that each has the form
+    /// `#localpart:server_name`.
+    pub fn new(alias: Option<&str>, alt_aliases: &[&str]) -> Result<Self, AliasFormatError> {
+        Ok(CanonicalAliasEventContent {
+            alias: match alias {
+                Some(alias) => Some(parse_alias(alias)?),
+                None => None,
+            },
+            alt_aliases: alt_aliases
+                .iter()
+                .map(|alias| parse_alias(alias))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::RoomAliasId;
+    use serde_json::{from_str, to_string};
+
+    use super::{CanonicalAliasEvent, CanonicalAliasEventContent};
+    use room::create::RoomVersion;
+    use room::AliasFormatError;
+
+    #[test]
+    fn serialization_with_alias() {
+        let content = CanonicalAliasEventContent {
+            alias: Some(RoomAliasId::try_from("#somewhere:localhost").unwrap()),
+            alt_aliases: Vec::new(),
+        };
+
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r##"{"alias":"#somewhere:localhost"}"##
+        );
+    }
+
+    #[test]
+    fn serialization_without_alias() {
+        let content = CanonicalAliasEventContent {
+            alias: None,
+            alt_aliases: Vec::new(),
+        };
+
+        assert_eq!(to_string(&content).unwrap(), r#"{}"#);
+    }
+
+    #[test]
+    fn absent_alias_deserializes_as_none() {
+        assert_eq!(
+            from_str::<CanonicalAliasEventContent>(r#"{}"#)
+                .unwrap()
+                .alias,
+            None
+        );
+    }
+
+    #[test]
+    fn null_alias_deserializes_as_none() {
+        assert_eq!(
+            from_str::<CanonicalAliasEventContent>(r#"{"alias":null}"#)
+                .unwrap()
+                .alias,
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_alias_is_rejected() {
+        assert!(from_str::<CanonicalAliasEventContent>(r#"{"alias":"somewhere"}"#).is_err());
+        assert!(
+            from_str::<CanonicalAliasEventContent>(r#"{"alias":"!somewhere:localhost"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn event_with_null_alias() {
+        let event = from_str::<CanonicalAliasEvent>(
+            r#"{
+                "content": {"alias": null},
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.canonical_alias"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.content.alias, None);
+    }
+
+    #[test]
+    fn event_without_alias() {
+        let event = from_str::<CanonicalAliasEvent>(
+            r#"{
+                "content": {},
+                "event_id": "$h29iv0s8:example.com",
+                "origin_server_ts": 1,
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.canonical_alias"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.content.alias, None);
+    }
+
+    #[test]
+    fn alt_aliases() {
+        let json = r##"{"alias":"#somewhere:localhost","alt_aliases":["#elsewhere:localhost"]}"##;
+        let content = from_str::<CanonicalAliasEventContent>(json).unwrap();
+
+        assert_eq!(
+            content.alt_aliases,
+            vec![RoomAliasId::try_from("#elsewhere:localhost").unwrap()]
+        );
+        assert_eq!(to_string(&content).unwrap(), json);
+    }
+
+    #[test]
+    fn new_validates_aliases() {
+        let content = CanonicalAliasEventContent::new(
+            Some("#somewhere:localhost"),
+            &["#elsewhere:localhost"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            content.alias,
+            Some(RoomAliasId::try_from("#somewhere:localhost").unwrap())
+        );
+        assert_eq!(
+            content.alt_aliases,
+            vec![RoomAliasId::try_from("#elsewhere:localhost").unwrap()]
+        );
+        assert!(CanonicalAliasEventContent::new(None, &[]).is_ok());
+        assert_eq!(
+            CanonicalAliasEventContent::new(Some("#:localhost"), &[]),
+            Err(AliasFormatError::EmptyLocalpart)
+        );
+        assert_eq!(
+            CanonicalAliasEventContent::new(None, &["#somewhere:localhost", "elsewhere:localhost"]),
+            Err(AliasFormatError::MissingSigil)
+        );
+    }
+
+    #[test]
+    fn redact() {
+        let content = CanonicalAliasEventContent {
+            alias: Some(RoomAliasId::try_from("#somewhere:localhost").unwrap()),
+            alt_aliases: vec![RoomAliasId::try_from("#elsewhere:localhost").unwrap()],
+        };
+
+        assert_eq!(to_string(&content.redact(&RoomVersion::V11)).unwrap(), "{}");
+    }
 }
@@ -1,6 +1,13 @@
 //! Types for the *m.room.create* event.
 
-use ruma_identifiers::UserId;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use ruma_identifiers::{EventId, RoomId, UserId};
+use serde::de::{Error as SerdeError, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use ParseError;
 
 state_event! {
     /// This is the first event in a room and cannot be changed. It acts as the root of all other
@@ -9,11 +16,402 @@ state_event! {
 }
 
 /// The payload of a `CreateEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CreateEventContent {
     /// The `user_id` of the room creator. This is set by the homeserver.
     pub creator: UserId,
     /// Whether or not this room's data should be transferred to other homeservers.
     #[serde(rename = "m.federate")]
-    pub federate: Option<bool>,
+    #[serde(default = "default_federate")]
+    pub federate: bool,
+    /// The room this room replaces, if it was upgraded from an earlier room.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predecessor: Option<PreviousRoom>,
+    /// The version of the room.
+    #[serde(default = "default_room_version")]
+    pub room_version: RoomVersion,
+    /// The type of the room, if it is not a regular chat room.
+    #[serde(rename = "type")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_type: Option<RoomType>,
+}
+
+/// A reference to the room an upgraded room replaces.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PreviousRoom {
+    /// The ID of the old room.
+    pub room_id: RoomId,
+    /// The ID of the *m.room.tombstone* event in the old room.
+    pub event_id: EventId,
+}
+
+/// The type of a room.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum RoomType {
+    /// A space, a room used to group other rooms.
+    #[serde(rename = "m.space")]
+    Space,
+}
+
+impl_enum! {
+    RoomType {
+        Space => "m.space",
+    }
+}
+
+/// The version of a room, which determines the algorithms it uses.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RoomVersion {
+    /// Room version 1.
+    V1,
+    /// Room version 2.
+    V2,
+    /// Room version 3.
+    V3,
+    /// Room version 4.
+    V4,
+    /// Room version 5.
+    V5,
+    /// Room version 6.
+    V6,
+    /// Room version 7.
+    V7,
+    /// Room version 8.
+    V8,
+    /// Room version 9.
+    V9,
+    /// Room version 10.
+    V10,
+    /// Room version 11.
+    V11,
+    /// A room version not known to this library.
+    Custom(String),
+}
+
+impl CreateEventContent {
+    /// The content of this event after a redaction.
+    ///
+    /// Room version 11 keeps every field, earlier versions keep only `creator`.
+    pub fn redact(&self, room_version: &RoomVersion) -> CreateEventContent {
+        if !room_version.is_older_than(11) {
+            return self.clone();
+        }
+
+        CreateEventContent {
+            creator: self.creator.clone(),
+            federate: default_federate(),
+            predecessor: None,
+            room_version: default_room_version(),
+            room_type: None,
+        }
+    }
+
+    /// Creates the content for a space created by the given user.
+    pub fn for_space(creator: UserId) -> Self {
+        CreateEventContent {
+            creator,
+            federate: default_federate(),
+            predecessor: None,
+            room_version: default_room_version(),
+            room_type: Some(RoomType::Space),
+        }
+    }
+
+    /// Creates the content for a direct message room created by the given user.
+    ///
+    /// Direct message rooms are regular rooms, so this leaves `room_type` unset.
+    pub fn for_dm(creator: UserId) -> Self {
+        CreateEventContent {
+            creator,
+            federate: default_federate(),
+            predecessor: None,
+            room_version: default_room_version(),
+            room_type: None,
+        }
+    }
+}
+
+impl RoomVersion {
+    /// Whether or not this is a known room version older than room version `version`.
+    ///
+    /// Room versions not known to this library are assumed to be newer than every known one.
+    pub(crate) fn is_older_than(&self, version: u8) -> bool {
+        let number = match *self {
+            RoomVersion::V1 => 1,
+            RoomVersion::V2 => 2,
+            RoomVersion::V3 => 3,
+            RoomVersion::V4 => 4,
+            RoomVersion::V5 => 5,
+            RoomVersion::V6 => 6,
+            RoomVersion::V7 => 7,
+            RoomVersion::V8 => 8,
+            RoomVersion::V9 => 9,
+            RoomVersion::V10 => 10,
+            RoomVersion::V11 => 11,
+            RoomVersion::Custom(_) => return false,
+        };
+
+        number < version
+    }
+}
+
+impl Display for RoomVersion {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let room_version = match *self {
+            RoomVersion::V1 => "1",
+            RoomVersion::V2 => "2",
+            RoomVersion::V3 => "3",
+            RoomVersion::V4 => "4",
+            RoomVersion::V5 => "5",
+            RoomVersion::V6 => "6",
+            RoomVersion::V7 => "7",
+            RoomVersion::V8 => "8",
+            RoomVersion::V9 => "9",
+            RoomVersion::V10 => "10",
+            RoomVersion::V11 => "11",
+            RoomVersion::Custom(ref room_version) => room_version,
+        };
+
+        write!(f, "{}", room_version)
+    }
+}
+
+impl FromStr for RoomVersion {
+    type Err = ParseError;
+
+    /// Parses a room version. Fails only for the empty string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let room_version = match s {
+            "" => return Err(ParseError),
+            "1" => RoomVersion::V1,
+            "2" => RoomVersion::V2,
+            "3" => RoomVersion::V3,
+            "4" => RoomVersion::V4,
+            "5" => RoomVersion::V5,
+            "6" => RoomVersion::V6,
+            "7" => RoomVersion::V7,
+            "8" => RoomVersion::V8,
+            "9" => RoomVersion::V9,
+            "10" => RoomVersion::V10,
+            "11" => RoomVersion::V11,
+            room_version => RoomVersion::Custom(room_version.to_string()),
+        };
+
+        Ok(room_version)
+    }
+}
+
+impl Serialize for RoomVersion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for RoomVersion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RoomVersionVisitor;
+
+        impl<'de> Visitor<'de> for RoomVersionVisitor {
+            type Value = RoomVersion;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                write!(formatter, "a non-empty room version string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                v.parse()
+                    .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_str(RoomVersionVisitor)
+    }
+}
+
+fn default_federate() -> bool {
+    true
+}
+
+fn default_room_version() -> RoomVersion {
+    RoomVersion::V1
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::{EventId, RoomId, UserId};
+    use serde_json::{from_str, to_string, to_value, Value};
+
+    use super::{CreateEvent, CreateEventContent, PreviousRoom, RoomType, RoomVersion};
+
+    #[test]
+    fn serialization() {
+        let content = CreateEventContent {
+            creator: UserId::try_from("@carl:example.com").unwrap(),
+            federate: true,
+            predecessor: None,
+            room_version: RoomVersion::V4,
+            room_type: None,
+        };
+
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r#"{"creator":"@carl:example.com","m.federate":true,"room_version":"4"}"#
+        );
+    }
+
+    #[test]
+    fn missing_federate_defaults_to_true() {
+        let content = from_str::<CreateEventContent>(r#"{"creator":"@carl:example.com"}"#).unwrap();
+
+        assert!(content.federate);
+    }
+
+    #[test]
+    fn event_without_federate_is_federated() {
+        let event = from_str::<CreateEvent>(
+            r#"{
+                "content": {"creator": "@carl:example.com", "room_version": "6"},
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1,
+                "room_id": "!room:example.org",
+                "sender": "@carl:example.com",
+                "state_key": "",
+                "type": "m.room.create"
+            }"#,
+        )
+        .unwrap();
+
+        assert!(event.content.federate);
+        assert!(to_string(&event).unwrap().contains(r#""m.federate":true"#));
+    }
+
+    #[test]
+    fn explicit_federate_is_respected() {
+        let content =
+            from_str::<CreateEventContent>(r#"{"creator":"@carl:example.com","m.federate":false}"#)
+                .unwrap();
+
+        assert!(!content.federate);
+    }
+
+    #[test]
+    fn space_constructor() {
+        let content = CreateEventContent::for_space(UserId::try_from("@carl:example.com").unwrap());
+
+        assert_eq!(content.room_type, Some(RoomType::Space));
+        assert!(content.federate);
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r#"{"creator":"@carl:example.com","m.federate":true,"room_version":"1","type":"m.space"}"#
+        );
+    }
+
+    #[test]
+    fn dm_constructor() {
+        let content = CreateEventContent::for_dm(UserId::try_from("@carl:example.com").unwrap());
+
+        assert_eq!(content.room_type, None);
+        assert!(content.federate);
+    }
+
+    #[test]
+    fn deserialize_space_type() {
+        let content =
+            from_str::<CreateEventContent>(r#"{"creator":"@carl:example.com","type":"m.space"}"#)
+                .unwrap();
+
+        assert_eq!(content.room_type, Some(RoomType::Space));
+    }
+
+    #[test]
+    fn missing_room_version_defaults_to_1() {
+        let content = from_str::<CreateEventContent>(r#"{"creator":"@carl:example.com"}"#).unwrap();
+
+        assert_eq!(content.room_version, RoomVersion::V1);
+    }
+
+    #[test]
+    fn known_and_custom_room_versions() {
+        let content = from_str::<CreateEventContent>(
+            r#"{"creator":"@carl:example.com","room_version":"11"}"#,
+        )
+        .unwrap();
+        assert_eq!(content.room_version, RoomVersion::V11);
+
+        let content = from_str::<CreateEventContent>(
+            r#"{"creator":"@carl:example.com","room_version":"org.example.custom"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            content.room_version,
+            RoomVersion::Custom("org.example.custom".to_string())
+        );
+        assert_eq!(content.room_version.to_string(), "org.example.custom");
+    }
+
+    #[test]
+    fn empty_room_version_is_rejected() {
+        assert!(from_str::<CreateEventContent>(
+            r#"{"creator":"@carl:example.com","room_version":""}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn predecessor_round_trips() {
+        let json = r#"{"creator":"@carl:example.com","m.federate":true,"predecessor":{"event_id":"$something:example.org","room_id":"!oldroom:example.org"},"room_version":"6"}"#;
+        let content = from_str::<CreateEventContent>(json).unwrap();
+
+        assert_eq!(
+            content.predecessor,
+            Some(PreviousRoom {
+                room_id: RoomId::try_from("!oldroom:example.org").unwrap(),
+                event_id: EventId::try_from("$something:example.org").unwrap(),
+            })
+        );
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_predecessor_ids_are_rejected() {
+        assert!(from_str::<CreateEventContent>(
+            r#"{"creator":"@carl:example.com","predecessor":{"event_id":"!oldroom:example.org","room_id":"$something:example.org"}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn redact() {
+        let content = |room_version: &str| {
+            from_str::<CreateEventContent>(&format!(
+                r#"{{"creator":"@carl:example.com","m.federate":false,"room_version":"{}","type":"m.space"}}"#,
+                room_version
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(content("11").redact(&RoomVersion::V11), content("11"));
+        assert_eq!(
+            to_value(content("10").redact(&RoomVersion::V10)).unwrap(),
+            from_str::<Value>(
+                r#"{"creator":"@carl:example.com","m.federate":true,"room_version":"1"}"#
+            )
+            .unwrap()
+        );
+    }
 }
@@ -0,0 +1,232 @@
+//! Types for the *m.room.encrypted* event.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{from_value, Value};
+
+room_event! {
+    /// An event encrypted end-to-end, usually with a Megolm session.
+    pub struct EncryptedEvent(EncryptedEventContent) {}
+}
+
+/// The payload of an `EncryptedEvent`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EncryptedEventContent {
+    /// An event encrypted with *m.megolm.v1.aes-sha2*.
+    MegolmV1AesSha2(MegolmV1AesSha2Content),
+
+    /// An event encrypted with an algorithm not known to this library, e.g.
+    /// *m.olm.v1.curve25519-aes-sha2*, or the empty content of a redacted event.
+    Custom(Value),
+}
+
+/// The payload of an event encrypted with *m.megolm.v1.aes-sha2*.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MegolmV1AesSha2Content {
+    /// The encryption algorithm used, always *m.megolm.v1.aes-sha2*.
+    pub algorithm: String,
+
+    /// The encrypted event.
+    pub ciphertext: String,
+
+    /// The ID of the device of the sender. Deprecated in favor of the sender's device keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+
+    /// The Curve25519 key of the sender. Deprecated in favor of the sender's device keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_key: Option<String>,
+
+    /// The ID of the session used to encrypt the event.
+    pub session_id: String,
+}
+
+impl Serialize for EncryptedEventContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            EncryptedEventContent::MegolmV1AesSha2(ref content) => content.serialize(serializer),
+            EncryptedEventContent::Custom(ref content) => content.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EncryptedEventContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+
+        if value.get("algorithm").and_then(Value::as_str) != Some("m.megolm.v1.aes-sha2") {
+            return Ok(EncryptedEventContent::Custom(value));
+        }
+
+        match from_value::<MegolmV1AesSha2Content>(value) {
+            Ok(content) => Ok(EncryptedEventContent::MegolmV1AesSha2(content)),
+            Err(error) => Err(D::Error::custom(error.to_string())),
+        }
+    }
+}
+
+/// Whether or not `next` was encrypted with a different session than `prev`, i.e. the sender
+/// rotated their session key.
+///
+/// The sessions differ if either event is not encrypted with *m.megolm.v1.aes-sha2*, which
+/// includes redacted events, if their `session_id`s differ, or if both events have a
+/// `sender_key` and those differ. A missing `sender_key` is not a change, since it is deprecated.
+pub fn session_changed_from(prev: &EncryptedEventContent, next: &EncryptedEventContent) -> bool {
+    let (prev, next) = match (prev, next) {
+        (
+            EncryptedEventContent::MegolmV1AesSha2(prev),
+            EncryptedEventContent::MegolmV1AesSha2(next),
+        ) => (prev, next),
+        _ => return true,
+    };
+
+    if prev.session_id != next.session_id {
+        return true;
+    }
+
+    match (&prev.sender_key, &next.sender_key) {
+        (Some(prev_key), Some(next_key)) => prev_key != next_key,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{
+        session_changed_from, EncryptedEvent, EncryptedEventContent, MegolmV1AesSha2Content,
+    };
+
+    fn content(sender_key: Option<&str>, session_id: &str) -> EncryptedEventContent {
+        EncryptedEventContent::MegolmV1AesSha2(MegolmV1AesSha2Content {
+            algorithm: "m.megolm.v1.aes-sha2".to_string(),
+            ciphertext: "AwgAEnACgAkLmt6qF84IK++J7UDH2Za1YVchHyprqTqsg".to_string(),
+            device_id: Some("RJYKSTBOIE".to_string()),
+            sender_key: sender_key.map(|sender_key| sender_key.to_string()),
+            session_id: session_id.to_string(),
+        })
+    }
+
+    #[test]
+    fn serialization() {
+        let json = r#"{
+            "algorithm": "m.megolm.v1.aes-sha2",
+            "ciphertext": "AwgAEnACgAkLmt6qF84IK++J7UDH2Za1YVchHyprqTqsg",
+            "device_id": "RJYKSTBOIE",
+            "sender_key": "IlRMeOPX2e0MurIyfWEucYBRVOEEUMrOHqn/8mLqMjA",
+            "session_id": "X3lUlvLELLYxeTx4yOVu6UDpasGEVO0Jbu+QFnm0cKQ"
+        }"#;
+        let content = from_str::<EncryptedEventContent>(json).unwrap();
+
+        match content {
+            EncryptedEventContent::MegolmV1AesSha2(ref content) => assert_eq!(
+                content.sender_key.as_deref(),
+                Some("IlRMeOPX2e0MurIyfWEucYBRVOEEUMrOHqn/8mLqMjA")
+            ),
+            _ => panic!("expected Megolm content, got {:?}", content),
+        }
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn olm_content() {
+        let json = r#"{
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "ciphertext": {
+                "7qZcfnBmbEGzxxaWfBjElJuvn7BZx+lSz/SvFrDF/z8": {
+                    "body": "AwogGJJzMhf/S3GQFXAOrCZ3iKyGU5ZScVtjI0KypTYrW",
+                    "type": 0
+                }
+            },
+            "sender_key": "Szl29ksW/L8yZGWAX+8dY1XyFi+i5wm+DRhTGkbMiwU"
+        }"#;
+        let content = from_str::<EncryptedEventContent>(json).unwrap();
+
+        assert_eq!(
+            content,
+            EncryptedEventContent::Custom(from_str::<Value>(json).unwrap())
+        );
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn redacted_content() {
+        let event = from_str::<EncryptedEvent>(
+            r#"{
+                "content": {},
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1432735824653,
+                "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+                "sender": "@example:example.org",
+                "type": "m.room.encrypted",
+                "unsigned": {"redacted_because": {}}
+            }"#,
+        )
+        .unwrap();
+        let content = event.content;
+
+        assert_eq!(
+            content,
+            EncryptedEventContent::Custom(from_str("{}").unwrap())
+        );
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>("{}").unwrap()
+        );
+        assert!(from_str::<EncryptedEventContent>(
+            r#"{"algorithm":"m.megolm.v1.aes-sha2","ciphertext":"AwgAEnAC"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn session_changes() {
+        let alice_key = Some("IlRMeOPX2e0MurIyfWEucYBRVOEEUMrOHqn/8mLqMjA");
+        let bob_key = Some("F2Ux0KXsExqGdq6ZUbMoPAmEmq9gzNP6+UX1WS6wA1E");
+
+        assert!(!session_changed_from(
+            &content(alice_key, "session1"),
+            &content(alice_key, "session1")
+        ));
+        assert!(session_changed_from(
+            &content(alice_key, "session1"),
+            &content(alice_key, "session2")
+        ));
+        assert!(session_changed_from(
+            &content(alice_key, "session1"),
+            &content(bob_key, "session1")
+        ));
+        assert!(!session_changed_from(
+            &content(alice_key, "session1"),
+            &content(None, "session1")
+        ));
+    }
+
+    #[test]
+    fn other_algorithms_and_redactions_change_the_session() {
+        let megolm = content(None, "session1");
+        let olm = from_str::<EncryptedEventContent>(
+            r#"{"algorithm":"m.olm.v1.curve25519-aes-sha2","ciphertext":{},"sender_key":"key"}"#,
+        )
+        .unwrap();
+        let redacted = from_str::<EncryptedEventContent>("{}").unwrap();
+
+        assert!(session_changed_from(&megolm, &olm));
+        assert!(session_changed_from(&olm, &megolm));
+        assert!(session_changed_from(&megolm, &redacted));
+        assert!(session_changed_from(&redacted, &megolm));
+    }
+}
@@ -1,5 +1,8 @@
 //! Types for the *m.room.guest_access* event.
 
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
 state_event! {
     /// Controls whether guest users are allowed to join rooms.
     ///
@@ -9,14 +12,24 @@ state_event! {
 }
 
 /// The payload of a `GuestAccessEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct GuestAccessEventContent {
     /// A policy for guest user access to a room.
     pub guest_access: GuestAccess,
 }
 
+impl GuestAccessEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
+
 /// A policy for guest user access to a room.
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+///
+/// Note that *world_readable* is not a guest access policy. It is a value of
+/// `HistoryVisibility`, which controls who can read a room's history rather than who can join it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum GuestAccess {
     /// Guests are allowed to join the room.
     #[serde(rename = "can_join")]
@@ -33,3 +46,29 @@ impl_enum! {
         Forbidden => "forbidden",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_string};
+
+    use super::GuestAccess;
+
+    #[test]
+    fn guest_access_round_trips() {
+        assert_eq!(to_string(&GuestAccess::CanJoin).unwrap(), r#""can_join""#);
+        assert_eq!(
+            from_str::<GuestAccess>(r#""forbidden""#).unwrap(),
+            GuestAccess::Forbidden
+        );
+        assert_eq!(
+            "can_join".parse::<GuestAccess>().unwrap(),
+            GuestAccess::CanJoin
+        );
+    }
+
+    #[test]
+    fn world_readable_is_not_a_guest_access_policy() {
+        assert!("world_readable".parse::<GuestAccess>().is_err());
+        assert!(from_str::<GuestAccess>(r#""world_readable""#).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! Types for the *m.room.history_visibility* event.
 
+use room::create::RoomVersion;
+
 state_event! {
     /// This event controls whether a member of a room can see the events that happened in a room
     /// from before they joined.
@@ -7,14 +9,22 @@ state_event! {
 }
 
 /// The payload of a `HistoryVisibilityEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct HistoryVisibilityEventContent {
     /// Who can see the room history.
     pub history_visibility: HistoryVisibility,
 }
 
+impl HistoryVisibilityEventContent {
+    /// The content of this event after a redaction, which keeps `history_visibility` in every room
+    /// version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> HistoryVisibilityEventContent {
+        self.clone()
+    }
+}
+
 /// Who can see a room's history.
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum HistoryVisibility {
     /// Previous events are accessible to newly joined members from the point they were invited
     /// onwards. Events stop being accessible when the member's state changes to something other
@@ -47,3 +57,19 @@ impl_enum! {
         WorldReadable => "world_readable",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryVisibility, HistoryVisibilityEventContent};
+    use room::create::RoomVersion;
+
+    #[test]
+    fn redact() {
+        let content = HistoryVisibilityEventContent {
+            history_visibility: HistoryVisibility::Joined,
+        };
+
+        assert_eq!(content.redact(&RoomVersion::V1), content);
+        assert_eq!(content.redact(&RoomVersion::V11), content);
+    }
+}
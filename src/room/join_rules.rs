@@ -1,19 +1,28 @@
 //! Types for the *m.room.join_rules* event.
 
+use room::create::RoomVersion;
+
 state_event! {
     /// Describes how users are allowed to join the room.
     pub struct JoinRulesEvent(JoinRulesEventContent) {}
 }
 
 /// The payload of a `JoinRulesEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct JoinRulesEventContent {
     /// The type of rules used for users wishing to join this room.
     pub join_rule: JoinRule,
 }
 
+impl JoinRulesEventContent {
+    /// The content of this event after a redaction, which keeps `join_rule` in every room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> JoinRulesEventContent {
+        self.clone()
+    }
+}
+
 /// The rule used for users wishing to join this room.
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum JoinRule {
     /// A user who wishes to join the room must first receive an invite to the room from someone
     /// already inside of the room.
@@ -41,3 +50,19 @@ impl_enum! {
         Public => "public",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{JoinRule, JoinRulesEventContent};
+    use room::create::RoomVersion;
+
+    #[test]
+    fn redact() {
+        let content = JoinRulesEventContent {
+            join_rule: JoinRule::Invite,
+        };
+
+        assert_eq!(content.redact(&RoomVersion::V1), content);
+        assert_eq!(content.redact(&RoomVersion::V11), content);
+    }
+}
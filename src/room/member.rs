@@ -1,9 +1,20 @@
 //! Types for the *m.room.member* event.
 
+use std::convert::TryFrom;
+
 use ruma_identifiers::UserId;
 use ruma_signatures::Signatures;
+use serde::Deserialize;
+use serde_json::{from_value, to_value, Map, Value};
 
-use stripped::StrippedState;
+use room::create::RoomVersion;
+use room::redaction::RedactionEvent;
+use room::third_party_invite::ThirdPartyInviteEvent;
+use stripped::{
+    StrippedRoomAvatar, StrippedRoomCanonicalAlias, StrippedRoomJoinRules, StrippedRoomName,
+    StrippedState,
+};
+use {deserialize_lenient_mxc_uri, EventType, MxcUri};
 
 state_event! {
     /// The current membership state of a user in the room.
@@ -28,11 +39,14 @@ state_event! {
 }
 
 /// The payload of a `MemberEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct MemberEventContent {
     /// The avatar URL for this user.
+    ///
+    /// An avatar URL that is not a valid *mxc://* URI is treated as absent.
+    #[serde(default, deserialize_with = "deserialize_lenient_mxc_uri")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub avatar_url: Option<String>,
+    pub avatar_url: Option<MxcUri>,
 
     /// The display name for this user.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -43,9 +57,18 @@ pub struct MemberEventContent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_direct: Option<bool>,
 
+    /// A user on the resident server that authorised this join to a restricted room.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join_authorised_via_users_server: Option<UserId>,
+
     /// The membership state of this user.
     pub membership: MembershipState,
 
+    /// The user-supplied reason for the membership change, e.g. why a user is knocking on the
+    /// room or why they were kicked or banned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
     /// If this member event is the successor to a third party invitation, this field will contain
     /// information about that invitation.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -86,12 +109,453 @@ impl_enum! {
     }
 }
 
+/// The change to a user's membership described by a `MemberEvent`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MembershipChange {
+    /// The user joined the room.
+    Joined,
+
+    /// The user left the room.
+    Left,
+
+    /// The user was banned.
+    Banned,
+
+    /// The user was kicked.
+    Kicked,
+
+    /// The user was invited.
+    Invited {
+        /// Whether or not the invite is for a direct chat.
+        is_direct: bool,
+    },
+
+    /// The user's invitation was revoked.
+    InvitationRevoked,
+
+    /// The user rejected their invitation.
+    InvitationRejected,
+
+    /// The user requested to join the room.
+    Knocked,
+
+    /// The user's display name and/or avatar changed while they were in the room.
+    ProfileChanged {
+        /// Whether or not the display name changed.
+        displayname_changed: bool,
+
+        /// Whether or not the avatar URL changed.
+        avatar_url_changed: bool,
+    },
+
+    /// The user was kicked and banned.
+    KickedAndBanned,
+
+    /// The user was unbanned.
+    Unbanned,
+
+    /// The user's membership did not change.
+    NotChanged,
+
+    /// The transition is not permitted by the membership rules, e.g. a banned user joining.
+    Error,
+}
+
+/// An error when validating a `MemberEvent`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemberEventError {
+    /// The `state_key` is not a valid user ID.
+    InvalidStateKey,
+
+    /// The `state_key` is not the user the content is about, i.e. the `mxid` of the third party
+    /// invite.
+    StateKeyMismatch,
+}
+
+/// A change of membership that the membership rules do not permit, found by
+/// `validate_transition`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidTransition {
+    /// The membership before the change, if the user had one.
+    pub from: Option<MembershipState>,
+
+    /// The membership after the change.
+    pub to: MembershipState,
+}
+
+/// Checks that a user's membership may change from `from` to `to`.
+///
+/// `sender_is_target` is whether the user sending the event is the user whose membership changes.
+/// Users may only join, knock and leave by themselves, and may not invite or ban themselves.
+/// Banned users must be unbanned before their membership can change in any other way, and joined
+/// users must leave before they can be invited or knock. A user without a membership is treated as
+/// having left, except that leaving again is not a change.
+///
+/// Power levels and join rules are not checked, so a knocking user may join, as they can in a room
+/// whose join rule is `public`.
+pub fn validate_transition(
+    from: Option<MembershipState>,
+    to: MembershipState,
+    sender_is_target: bool,
+) -> Result<(), InvalidTransition> {
+    if is_valid_transition(from, to, sender_is_target) {
+        Ok(())
+    } else {
+        Err(InvalidTransition { from, to })
+    }
+}
+
+fn is_valid_transition(
+    from: Option<MembershipState>,
+    to: MembershipState,
+    sender_is_target: bool,
+) -> bool {
+    match (from, to) {
+        (_, MembershipState::Ban) => !sender_is_target,
+        (Some(MembershipState::Ban), MembershipState::Leave) => !sender_is_target,
+        (Some(MembershipState::Ban), _) => false,
+        (None, MembershipState::Leave) | (Some(MembershipState::Leave), MembershipState::Leave) => {
+            false
+        }
+        (_, MembershipState::Leave) => true,
+        (_, MembershipState::Join) => sender_is_target,
+        (Some(MembershipState::Join), MembershipState::Invite) => false,
+        (_, MembershipState::Invite) => !sender_is_target,
+        (None, MembershipState::Knock) | (Some(MembershipState::Leave), MembershipState::Knock) => {
+            sender_is_target
+        }
+        (_, MembershipState::Knock) => false,
+    }
+}
+
+impl MemberEvent {
+    /// The stripped state event of the given type in `invite_room_state`, if any.
+    pub fn invite_room_state_for(&self, event_type: &EventType) -> Option<&StrippedState> {
+        self.invite_room_state
+            .as_ref()
+            .and_then(|state| state.iter().find(|event| event.event_type() == event_type))
+    }
+
+    /// The stripped *m.room.name* event in `invite_room_state`, if any.
+    pub fn room_name(&self) -> Option<&StrippedRoomName> {
+        match self.invite_room_state_for(&EventType::RoomName) {
+            Some(StrippedState::RoomName(event)) => Some(event),
+            _ => None,
+        }
+    }
+
+    /// The stripped *m.room.join_rules* event in `invite_room_state`, if any.
+    pub fn join_rules(&self) -> Option<&StrippedRoomJoinRules> {
+        match self.invite_room_state_for(&EventType::RoomJoinRules) {
+            Some(StrippedState::RoomJoinRules(event)) => Some(event),
+            _ => None,
+        }
+    }
+
+    /// The stripped *m.room.avatar* event in `invite_room_state`, if any.
+    pub fn room_avatar(&self) -> Option<&StrippedRoomAvatar> {
+        match self.invite_room_state_for(&EventType::RoomAvatar) {
+            Some(StrippedState::RoomAvatar(event)) => Some(event),
+            _ => None,
+        }
+    }
+
+    /// The stripped *m.room.canonical_alias* event in `invite_room_state`, if any.
+    pub fn canonical_alias(&self) -> Option<&StrippedRoomCanonicalAlias> {
+        match self.invite_room_state_for(&EventType::RoomCanonicalAlias) {
+            Some(StrippedState::RoomCanonicalAlias(event)) => Some(event),
+            _ => None,
+        }
+    }
+
+    /// The membership of the user before this event, if the previous content is known.
+    ///
+    /// This is taken from `prev_content`, or from `unsigned.prev_content` where some homeservers
+    /// put it instead.
+    pub fn previous_membership(&self) -> Option<MembershipState> {
+        self.previous_content().map(|prev| prev.membership)
+    }
+
+    /// The change to the user's membership this event describes, compared to the previous content.
+    ///
+    /// An event without previous content is treated as a change from `leave`.
+    pub fn membership_change(&self) -> MembershipChange {
+        let prev_content = self.previous_content();
+        let prev_membership = prev_content
+            .as_ref()
+            .map_or(MembershipState::Leave, |prev| prev.membership);
+        let by_self = self.sender.to_string() == self.state_key;
+
+        match (prev_membership, self.content.membership) {
+            (MembershipState::Join, MembershipState::Join) => {
+                let (displayname_changed, avatar_url_changed) = match prev_content.as_ref() {
+                    Some(prev) => (
+                        prev.displayname != self.content.displayname,
+                        prev.avatar_url != self.content.avatar_url,
+                    ),
+                    None => (false, false),
+                };
+
+                if displayname_changed || avatar_url_changed {
+                    MembershipChange::ProfileChanged {
+                        displayname_changed,
+                        avatar_url_changed,
+                    }
+                } else {
+                    MembershipChange::NotChanged
+                }
+            }
+            (MembershipState::Ban, MembershipState::Ban)
+            | (MembershipState::Invite, MembershipState::Invite)
+            | (MembershipState::Knock, MembershipState::Knock)
+            | (MembershipState::Leave, MembershipState::Leave) => MembershipChange::NotChanged,
+            (MembershipState::Ban, MembershipState::Leave) => MembershipChange::Unbanned,
+            (MembershipState::Ban, _) => MembershipChange::Error,
+            (MembershipState::Join, MembershipState::Ban) => MembershipChange::KickedAndBanned,
+            (_, MembershipState::Ban) => MembershipChange::Banned,
+            (MembershipState::Invite, MembershipState::Leave) if by_self => {
+                MembershipChange::InvitationRejected
+            }
+            (MembershipState::Invite, MembershipState::Leave) => {
+                MembershipChange::InvitationRevoked
+            }
+            (_, MembershipState::Leave) if by_self => MembershipChange::Left,
+            (_, MembershipState::Leave) => MembershipChange::Kicked,
+            (MembershipState::Join, _) => MembershipChange::Error,
+            (_, MembershipState::Join) => MembershipChange::Joined,
+            (MembershipState::Leave, MembershipState::Invite)
+            | (MembershipState::Knock, MembershipState::Invite) => MembershipChange::Invited {
+                is_direct: self.content.is_direct_message_invite(),
+            },
+            (MembershipState::Leave, MembershipState::Knock) => MembershipChange::Knocked,
+            (MembershipState::Invite, MembershipState::Knock) => MembershipChange::Error,
+        }
+    }
+
+    /// Whether or not the user changed their display name while in the room with this event.
+    pub fn display_name_changed(&self) -> bool {
+        match self.membership_change() {
+            MembershipChange::ProfileChanged {
+                displayname_changed,
+                ..
+            } => displayname_changed,
+            _ => false,
+        }
+    }
+
+    /// Whether or not the user changed their avatar while in the room with this event.
+    pub fn avatar_url_changed(&self) -> bool {
+        match self.membership_change() {
+            MembershipChange::ProfileChanged {
+                avatar_url_changed, ..
+            } => avatar_url_changed,
+            _ => false,
+        }
+    }
+
+    /// The display name of the user before this event, if the previous content is known and has
+    /// one.
+    pub fn previous_display_name(&self) -> Option<&str> {
+        if let Some(ref prev_content) = self.prev_content {
+            return prev_content.displayname.as_deref();
+        }
+
+        self.unsigned
+            .as_ref()
+            .and_then(|unsigned| unsigned.get("prev_content"))
+            .and_then(|prev_content| prev_content.get("displayname"))
+            .and_then(Value::as_str)
+    }
+
+    /// The avatar URL of the user before this event, if the previous content is known and has a
+    /// valid one.
+    ///
+    /// This is returned by value because the previous content may have to be parsed from
+    /// `unsigned`.
+    pub fn previous_avatar_url(&self) -> Option<MxcUri> {
+        self.previous_content()
+            .and_then(|prev_content| prev_content.avatar_url)
+    }
+
+    /// Checks that the `state_key` is the ID of the user whose membership this event describes.
+    ///
+    /// The `state_key` must be a valid user ID and, if the content holds a third party invite, it
+    /// must be the invited `mxid`.
+    pub fn validate_state_key(&self) -> Result<(), MemberEventError> {
+        let user_id = UserId::try_from(self.state_key.as_str())
+            .map_err(|_| MemberEventError::InvalidStateKey)?;
+
+        match self.content.third_party_invite {
+            Some(ref third_party_invite) if third_party_invite.signed.mxid != user_id => {
+                Err(MemberEventError::StateKeyMismatch)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// A copy of this event as it would be after being redacted by `redaction`.
+    ///
+    /// The content is redacted following the rules of `room_version`, and `redaction` is stored in
+    /// `unsigned.redacted_because`.
+    pub fn redact(&self, redaction: &RedactionEvent, room_version: &RoomVersion) -> MemberEvent {
+        let mut unsigned = match self.unsigned {
+            Some(Value::Object(ref unsigned)) => unsigned.clone(),
+            _ => Map::new(),
+        };
+        unsigned.insert(
+            "redacted_because".to_string(),
+            to_value(redaction).expect("a redaction event always serializes"),
+        );
+
+        MemberEvent {
+            content: self.content.redact(room_version),
+            unsigned: Some(Value::Object(unsigned)),
+            ..self.clone()
+        }
+    }
+
+    fn previous_content(&self) -> Option<MemberEventContent> {
+        if let Some(ref prev_content) = self.prev_content {
+            return Some(prev_content.clone());
+        }
+
+        self.unsigned
+            .as_ref()
+            .and_then(|unsigned| unsigned.get("prev_content"))
+            .and_then(|prev_content| from_value(prev_content.clone()).ok())
+    }
+}
+
+impl MemberEventContent {
+    /// The content of this event after a redaction.
+    ///
+    /// Every room version keeps `membership`. Room version 9 and later also keep
+    /// `join_authorised_via_users_server`, and room version 11 and later keep the `signed` part of
+    /// `third_party_invite`.
+    pub fn redact(&self, room_version: &RoomVersion) -> MemberEventContent {
+        let join_authorised_via_users_server = if room_version.is_older_than(9) {
+            None
+        } else {
+            self.join_authorised_via_users_server.clone()
+        };
+
+        let third_party_invite = if room_version.is_older_than(11) {
+            None
+        } else {
+            self.third_party_invite
+                .as_ref()
+                .map(|third_party_invite| ThirdPartyInvite {
+                    display_name: None,
+                    signed: third_party_invite.signed.clone(),
+                })
+        };
+
+        MemberEventContent {
+            avatar_url: None,
+            displayname: None,
+            is_direct: None,
+            join_authorised_via_users_server,
+            membership: self.membership,
+            reason: None,
+            third_party_invite,
+        }
+    }
+
+    /// Creates the content of an invite for the user who was the target of the given third party
+    /// invitation.
+    ///
+    /// `signed` is the signed block provided by the identity server. Its `mxid` is the invited user,
+    /// which must also be used as the `state_key` of the resulting `MemberEvent`.
+    pub fn from_third_party_invite(invite: &ThirdPartyInviteEvent, signed: SignedContent) -> Self {
+        MemberEventContent {
+            avatar_url: None,
+            displayname: None,
+            is_direct: None,
+            join_authorised_via_users_server: None,
+            membership: MembershipState::Invite,
+            reason: None,
+            third_party_invite: Some(ThirdPartyInvite {
+                display_name: Some(invite.content.display_name.clone()),
+                signed,
+            }),
+        }
+    }
+
+    /// Whether or not this is an invite to a direct chat, i.e. the invite has `is_direct` set.
+    pub fn is_direct_message_invite(&self) -> bool {
+        self.membership == MembershipState::Invite && self.is_direct == Some(true)
+    }
+
+    /// Whether or not this is an invite for the given third party invitation.
+    ///
+    /// The token of the third party invite, if any, is matched against the `state_key` of
+    /// `third_party_invite_event`, which holds the token that the identity server returned when the
+    /// invitation was made. The signature of the `signed` block is not checked.
+    pub fn verify_token(&self, third_party_invite_event: &ThirdPartyInviteEvent) -> bool {
+        match self.third_party_invite {
+            Some(ref third_party_invite) => {
+                self.membership == MembershipState::Invite
+                    && third_party_invite.signed.token == third_party_invite_event.state_key
+            }
+            None => false,
+        }
+    }
+}
+
+/// A ban of a user, which always gives a reason.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BanMemberEventContent {
+    /// The banned user, which is the `state_key` of the resulting `MemberEvent`.
+    pub banned_user: UserId,
+
+    /// The reason for the ban.
+    pub reason: String,
+}
+
+impl BanMemberEventContent {
+    /// Creates a ban of `banned_user` for the given reason.
+    pub fn new(banned_user: UserId, reason: String) -> Self {
+        BanMemberEventContent {
+            banned_user,
+            reason,
+        }
+    }
+
+    /// The `state_key` of the `MemberEvent` for this ban.
+    pub fn state_key(&self) -> String {
+        self.banned_user.to_string()
+    }
+
+    /// The content of the `MemberEvent` for this ban.
+    pub fn content(&self) -> MemberEventContent {
+        MemberEventContent {
+            avatar_url: None,
+            displayname: None,
+            is_direct: None,
+            join_authorised_via_users_server: None,
+            membership: MembershipState::Ban,
+            reason: Some(self.reason.clone()),
+            third_party_invite: None,
+        }
+    }
+}
+
+impl MembershipState {
+    /// Whether or not the user has requested to join the room.
+    pub fn is_knock(&self) -> bool {
+        *self == MembershipState::Knock
+    }
+}
+
 /// Information about a third party invitation.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct ThirdPartyInvite {
     /// A name which can be displayed to represent the user instead of their third party
     /// identifier.
-    pub display_name: String,
+    ///
+    /// This is absent from the redacted content of an invite.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
     /// A block of content which has been signed, which servers can use to verify the event.
     /// Clients should ignore this.
     pub signed: SignedContent,
@@ -111,3 +575,638 @@ pub struct SignedContent {
     /// The token property of the containing third_party_invite object.
     pub token: String,
 }
+
+impl PartialEq for SignedContent {
+    fn eq(&self, other: &SignedContent) -> bool {
+        // `Signatures` does not implement `PartialEq`, so the signatures are compared as JSON.
+        self.mxid == other.mxid
+            && self.token == other.token
+            && to_value(&self.signatures).ok() == to_value(&other.signatures).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::UserId;
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{
+        validate_transition, BanMemberEventContent, InvalidTransition, MemberEvent,
+        MemberEventContent, MemberEventError, MembershipChange, MembershipState, SignedContent,
+    };
+    use room::create::RoomVersion;
+    use room::join_rules::JoinRule;
+    use room::redaction::RedactionEvent;
+    use room::third_party_invite::ThirdPartyInviteEvent;
+    use EventType;
+
+    #[test]
+    fn knock_with_reason_round_trips() {
+        let json = r#"{
+            "content": {
+                "displayname": "Alice Margatroid",
+                "membership": "knock",
+                "reason": "Looking for support"
+            },
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1432735824653,
+            "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+            "sender": "@alice:example.org",
+            "state_key": "@alice:example.org",
+            "type": "m.room.member"
+        }"#;
+
+        let event = from_str::<MemberEvent>(json).unwrap();
+
+        assert!(event.content.membership.is_knock());
+        assert_eq!(
+            event.content.reason,
+            Some("Looking for support".to_string())
+        );
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+
+    #[test]
+    fn avatar_urls() {
+        let avatar_url = |avatar_url: &str| {
+            from_str::<MemberEventContent>(&format!(
+                r#"{{"avatar_url":{},"membership":"join"}}"#,
+                avatar_url
+            ))
+            .unwrap()
+            .avatar_url
+        };
+
+        assert_eq!(
+            avatar_url(r#""mxc://example.org/SEsfnsuifSDFSSEF""#)
+                .unwrap()
+                .media_id(),
+            "SEsfnsuifSDFSSEF"
+        );
+        assert_eq!(avatar_url(r#""https://example.org/avatar.png""#), None);
+        assert_eq!(avatar_url("null"), None);
+        assert_eq!(
+            from_str::<MemberEventContent>(r#"{"membership":"join"}"#)
+                .unwrap()
+                .avatar_url,
+            None
+        );
+    }
+
+    #[test]
+    fn only_knock_is_knock() {
+        assert!(MembershipState::Knock.is_knock());
+        assert!(!MembershipState::Join.is_knock());
+        assert!(!MembershipState::Invite.is_knock());
+    }
+
+    #[test]
+    fn ban_content() {
+        let ban = BanMemberEventContent::new(
+            UserId::try_from("@spammer:example.org").unwrap(),
+            "Spam".to_string(),
+        );
+        let content = ban.content();
+
+        assert_eq!(ban.state_key(), "@spammer:example.org");
+        assert_eq!(content.membership, MembershipState::Ban);
+        assert_eq!(content.reason, Some("Spam".to_string()));
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(r#"{"membership":"ban","reason":"Spam"}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn content_from_third_party_invite() {
+        let invite = from_str::<ThirdPartyInviteEvent>(
+            r#"{
+                "content": {
+                    "display_name": "alice",
+                    "key_validity_url": "https://magic.forest/verifykey",
+                    "public_key": "abc123"
+                },
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1432735824653,
+                "sender": "@bob:example.org",
+                "state_key": "abc123",
+                "type": "m.room.third_party_invite"
+            }"#,
+        )
+        .unwrap();
+        let signed = from_str::<SignedContent>(
+            r#"{
+                "mxid": "@alice:example.org",
+                "signatures": {
+                    "magic.forest": {
+                        "ed25519:3": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+                    }
+                },
+                "token": "abc123"
+            }"#,
+        )
+        .unwrap();
+
+        let content = MemberEventContent::from_third_party_invite(&invite, signed);
+
+        assert_eq!(content.membership, MembershipState::Invite);
+        let third_party_invite = content.third_party_invite.unwrap();
+        assert_eq!(third_party_invite.display_name, Some("alice".to_string()));
+        assert_eq!(
+            third_party_invite.signed.mxid.to_string(),
+            "@alice:example.org"
+        );
+        assert_eq!(third_party_invite.signed.token, "abc123");
+    }
+
+    #[test]
+    fn verify_third_party_invite_token() {
+        let invite = |token: &str| {
+            from_str::<ThirdPartyInviteEvent>(&format!(
+                r#"{{
+                    "content": {{
+                        "display_name": "alice",
+                        "key_validity_url": "https://magic.forest/verifykey",
+                        "public_key": "abc123"
+                    }},
+                    "event_id": "$143273582443PhrSn:example.org",
+                    "origin_server_ts": 1432735824653,
+                    "sender": "@bob:example.org",
+                    "state_key": "{}",
+                    "type": "m.room.third_party_invite"
+                }}"#,
+                token
+            ))
+            .unwrap()
+        };
+        let signed = from_str::<SignedContent>(
+            r#"{
+                "mxid": "@alice:example.org",
+                "signatures": {},
+                "token": "abc123"
+            }"#,
+        )
+        .unwrap();
+
+        let mut content = MemberEventContent::from_third_party_invite(&invite("abc123"), signed);
+
+        assert!(content.verify_token(&invite("abc123")));
+        assert!(!content.verify_token(&invite("def456")));
+
+        content.membership = MembershipState::Join;
+        assert!(!content.verify_token(&invite("abc123")));
+
+        content.third_party_invite = None;
+        assert!(!content.verify_token(&invite("abc123")));
+    }
+
+    #[test]
+    fn valid_transitions() {
+        use super::MembershipState::*;
+
+        assert_eq!(validate_transition(None, Join, true), Ok(()));
+        assert_eq!(validate_transition(Some(Leave), Join, true), Ok(()));
+        assert_eq!(validate_transition(Some(Invite), Join, true), Ok(()));
+        assert_eq!(validate_transition(Some(Join), Join, true), Ok(()));
+        assert_eq!(validate_transition(Some(Knock), Join, true), Ok(()));
+        assert_eq!(validate_transition(Some(Leave), Invite, false), Ok(()));
+        assert_eq!(validate_transition(Some(Knock), Invite, false), Ok(()));
+        assert_eq!(validate_transition(Some(Leave), Knock, true), Ok(()));
+        assert_eq!(validate_transition(Some(Join), Leave, true), Ok(()));
+        assert_eq!(validate_transition(Some(Join), Leave, false), Ok(()));
+        assert_eq!(validate_transition(Some(Invite), Leave, true), Ok(()));
+        assert_eq!(validate_transition(Some(Invite), Leave, false), Ok(()));
+        assert_eq!(validate_transition(Some(Knock), Leave, true), Ok(()));
+        assert_eq!(validate_transition(Some(Ban), Leave, false), Ok(()));
+        assert_eq!(validate_transition(Some(Join), Ban, false), Ok(()));
+        assert_eq!(validate_transition(None, Ban, false), Ok(()));
+    }
+
+    #[test]
+    fn invalid_transitions_are_rejected() {
+        use super::MembershipState::*;
+
+        let invalid = |from, to, sender_is_target| {
+            assert_eq!(
+                validate_transition(from, to, sender_is_target),
+                Err(InvalidTransition { from, to })
+            );
+        };
+
+        invalid(Some(Ban), Join, true);
+        invalid(Some(Ban), Invite, false);
+        invalid(Some(Ban), Knock, true);
+        invalid(Some(Ban), Leave, true);
+        invalid(Some(Leave), Join, false);
+        invalid(Some(Knock), Join, false);
+        invalid(Some(Join), Invite, false);
+        invalid(Some(Leave), Invite, true);
+        invalid(Some(Join), Knock, true);
+        invalid(Some(Leave), Knock, false);
+        invalid(Some(Leave), Leave, true);
+        invalid(None, Leave, false);
+        invalid(Some(Join), Ban, true);
+    }
+
+    #[test]
+    fn validate_state_key() {
+        let mut event = member_event(ALICE, None, INVITE);
+        assert_eq!(event.validate_state_key(), Ok(()));
+
+        event.content.third_party_invite = Some(
+            from_str(
+                r#"{
+                    "display_name": "alice",
+                    "signed": {
+                        "mxid": "@alice:example.org",
+                        "signatures": {},
+                        "token": "abc123"
+                    }
+                }"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(event.validate_state_key(), Ok(()));
+
+        event.state_key = BOB.to_string();
+        assert_eq!(
+            event.validate_state_key(),
+            Err(MemberEventError::StateKeyMismatch)
+        );
+
+        event.state_key = "alice".to_string();
+        assert_eq!(
+            event.validate_state_key(),
+            Err(MemberEventError::InvalidStateKey)
+        );
+    }
+
+    fn member_event(sender: &str, prev_content: Option<&str>, content: &str) -> MemberEvent {
+        let prev_content = match prev_content {
+            Some(prev_content) => format!(r#","prev_content":{}"#, prev_content),
+            None => String::new(),
+        };
+
+        from_str::<MemberEvent>(&format!(
+            r#"{{
+                "content": {},
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1432735824653,
+                "sender": "{}",
+                "state_key": "@alice:example.org",
+                "type": "m.room.member"{}
+            }}"#,
+            content, sender, prev_content
+        ))
+        .unwrap()
+    }
+
+    fn change(sender: &str, prev_content: Option<&str>, content: &str) -> MembershipChange {
+        member_event(sender, prev_content, content).membership_change()
+    }
+
+    const ALICE: &str = "@alice:example.org";
+    const BOB: &str = "@bob:example.org";
+    const BAN: &str = r#"{"membership":"ban"}"#;
+    const INVITE: &str = r#"{"membership":"invite"}"#;
+    const JOIN: &str = r#"{"membership":"join"}"#;
+    const KNOCK: &str = r#"{"membership":"knock"}"#;
+    const LEAVE: &str = r#"{"membership":"leave"}"#;
+
+    #[test]
+    fn joined() {
+        assert_eq!(change(ALICE, None, JOIN), MembershipChange::Joined);
+        assert_eq!(change(ALICE, Some(LEAVE), JOIN), MembershipChange::Joined);
+        assert_eq!(change(ALICE, Some(INVITE), JOIN), MembershipChange::Joined);
+    }
+
+    #[test]
+    fn left_and_kicked() {
+        assert_eq!(change(ALICE, Some(JOIN), LEAVE), MembershipChange::Left);
+        assert_eq!(change(BOB, Some(JOIN), LEAVE), MembershipChange::Kicked);
+    }
+
+    #[test]
+    fn banned_and_unbanned() {
+        assert_eq!(change(BOB, Some(LEAVE), BAN), MembershipChange::Banned);
+        assert_eq!(
+            change(BOB, Some(JOIN), BAN),
+            MembershipChange::KickedAndBanned
+        );
+        assert_eq!(change(BOB, Some(BAN), LEAVE), MembershipChange::Unbanned);
+    }
+
+    #[test]
+    fn invitations() {
+        assert_eq!(
+            change(BOB, Some(LEAVE), INVITE),
+            MembershipChange::Invited { is_direct: false }
+        );
+        assert_eq!(
+            change(BOB, Some(INVITE), LEAVE),
+            MembershipChange::InvitationRevoked
+        );
+        assert_eq!(
+            change(ALICE, Some(INVITE), LEAVE),
+            MembershipChange::InvitationRejected
+        );
+    }
+
+    #[test]
+    fn direct_invitations() {
+        let direct_invite = r#"{"is_direct":true,"membership":"invite"}"#;
+
+        assert_eq!(
+            change(BOB, Some(LEAVE), direct_invite),
+            MembershipChange::Invited { is_direct: true }
+        );
+        assert!(from_str::<MemberEventContent>(direct_invite)
+            .unwrap()
+            .is_direct_message_invite());
+        assert!(!from_str::<MemberEventContent>(INVITE)
+            .unwrap()
+            .is_direct_message_invite());
+        assert!(
+            !from_str::<MemberEventContent>(r#"{"is_direct":true,"membership":"join"}"#)
+                .unwrap()
+                .is_direct_message_invite()
+        );
+    }
+
+    #[test]
+    fn knocked() {
+        assert_eq!(change(ALICE, Some(LEAVE), KNOCK), MembershipChange::Knocked);
+        assert_eq!(
+            change(BOB, Some(KNOCK), INVITE),
+            MembershipChange::Invited { is_direct: false }
+        );
+    }
+
+    #[test]
+    fn profile_changed() {
+        assert_eq!(
+            change(
+                ALICE,
+                Some(r#"{"displayname":"Alice","membership":"join"}"#),
+                r#"{"avatar_url":"mxc://example.org/abc","displayname":"Alice M","membership":"join"}"#
+            ),
+            MembershipChange::ProfileChanged {
+                displayname_changed: true,
+                avatar_url_changed: true,
+            }
+        );
+        assert_eq!(
+            change(
+                ALICE,
+                Some(r#"{"displayname":"Alice","membership":"join"}"#),
+                r#"{"displayname":"Alice M","membership":"join"}"#
+            ),
+            MembershipChange::ProfileChanged {
+                displayname_changed: true,
+                avatar_url_changed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn display_name_and_avatar_url_changes() {
+        let alice =
+            r#"{"avatar_url":"mxc://example.org/abc","displayname":"Alice","membership":"join"}"#;
+        let renamed =
+            r#"{"avatar_url":"mxc://example.org/abc","displayname":"Alice M","membership":"join"}"#;
+        let new_avatar =
+            r#"{"avatar_url":"mxc://example.org/def","displayname":"Alice","membership":"join"}"#;
+        let both =
+            r#"{"avatar_url":"mxc://example.org/def","displayname":"Alice M","membership":"join"}"#;
+
+        let changes = |prev_content: &str, content: &str| {
+            let event = member_event(ALICE, Some(prev_content), content);
+            (event.display_name_changed(), event.avatar_url_changed())
+        };
+
+        assert_eq!(changes(alice, alice), (false, false));
+        assert_eq!(changes(alice, renamed), (true, false));
+        assert_eq!(changes(alice, new_avatar), (false, true));
+        assert_eq!(changes(alice, both), (true, true));
+        assert_eq!(changes(LEAVE, alice), (false, false));
+
+        let event = member_event(ALICE, Some(alice), renamed);
+        assert_eq!(event.previous_display_name(), Some("Alice"));
+        assert_eq!(
+            event.previous_avatar_url().map(|url| url.to_string()),
+            Some("mxc://example.org/abc".to_string())
+        );
+
+        let event = member_event(ALICE, None, JOIN);
+        assert!(!event.display_name_changed());
+        assert_eq!(event.previous_display_name(), None);
+        assert_eq!(event.previous_avatar_url(), None);
+    }
+
+    #[test]
+    fn previous_profile_from_unsigned() {
+        let event = from_str::<MemberEvent>(
+            r#"{
+                "content": {"displayname": "Alice M", "membership": "join"},
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1432735824653,
+                "sender": "@alice:example.org",
+                "state_key": "@alice:example.org",
+                "type": "m.room.member",
+                "unsigned": {"prev_content": {"avatar_url": "mxc://example.org/abc", "displayname": "Alice", "membership": "join"}}
+            }"#,
+        )
+        .unwrap();
+
+        assert!(event.display_name_changed());
+        assert!(event.avatar_url_changed());
+        assert_eq!(event.previous_display_name(), Some("Alice"));
+        assert_eq!(
+            event.previous_avatar_url().map(|url| url.to_string()),
+            Some("mxc://example.org/abc".to_string())
+        );
+    }
+
+    #[test]
+    fn not_changed() {
+        assert_eq!(
+            change(ALICE, Some(JOIN), JOIN),
+            MembershipChange::NotChanged
+        );
+        assert_eq!(change(BOB, Some(BAN), BAN), MembershipChange::NotChanged);
+        assert_eq!(change(ALICE, None, LEAVE), MembershipChange::NotChanged);
+    }
+
+    #[test]
+    fn invalid_transitions() {
+        assert_eq!(change(ALICE, Some(BAN), JOIN), MembershipChange::Error);
+        assert_eq!(change(BOB, Some(JOIN), INVITE), MembershipChange::Error);
+        assert_eq!(change(ALICE, Some(INVITE), KNOCK), MembershipChange::Error);
+    }
+
+    #[test]
+    fn typed_invite_room_state() {
+        let event = from_str::<MemberEvent>(
+            r#"{
+                "content": {"membership": "invite"},
+                "event_id": "$143273582443PhrSn:example.org",
+                "invite_room_state": [
+                    {
+                        "content": {"name": "Example Room"},
+                        "state_key": "",
+                        "type": "m.room.name"
+                    },
+                    {
+                        "content": {"join_rule": "invite"},
+                        "state_key": "",
+                        "type": "m.room.join_rules"
+                    }
+                ],
+                "origin_server_ts": 1432735824653,
+                "sender": "@example:example.org",
+                "state_key": "@alice:example.org",
+                "type": "m.room.member"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.room_name().unwrap().content.name, "Example Room");
+        assert_eq!(
+            event.join_rules().unwrap().content.join_rule,
+            JoinRule::Invite
+        );
+        assert!(event.room_avatar().is_none());
+        assert!(event.canonical_alias().is_none());
+        assert!(event
+            .invite_room_state_for(&EventType::RoomJoinRules)
+            .is_some());
+        assert!(event.invite_room_state_for(&EventType::RoomTopic).is_none());
+    }
+
+    #[test]
+    fn typed_invite_room_state_without_state() {
+        let event = member_event(ALICE, None, JOIN);
+
+        assert!(event.room_name().is_none());
+        assert!(event.invite_room_state_for(&EventType::RoomName).is_none());
+    }
+
+    #[test]
+    fn previous_membership() {
+        assert_eq!(member_event(ALICE, None, JOIN).previous_membership(), None);
+        assert_eq!(
+            member_event(
+                ALICE,
+                Some(JOIN),
+                r#"{"displayname":"Alice","membership":"join"}"#
+            )
+            .previous_membership(),
+            Some(MembershipState::Join)
+        );
+        assert_eq!(
+            member_event(ALICE, Some(INVITE), JOIN).previous_membership(),
+            Some(MembershipState::Invite)
+        );
+    }
+
+    #[test]
+    fn previous_membership_from_unsigned() {
+        let event = from_str::<MemberEvent>(
+            r#"{
+                "content": {"membership": "join"},
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1432735824653,
+                "sender": "@alice:example.org",
+                "state_key": "@alice:example.org",
+                "type": "m.room.member",
+                "unsigned": {"prev_content": {"membership": "invite"}}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.previous_membership(), Some(MembershipState::Invite));
+        assert_eq!(event.membership_change(), MembershipChange::Joined);
+    }
+
+    #[test]
+    fn redact() {
+        let event = from_str::<MemberEvent>(
+            r#"{
+                "content": {
+                    "avatar_url": "mxc://example.org/SEsfnsuifSDFSSEF",
+                    "displayname": "Alice Margatroid",
+                    "is_direct": true,
+                    "join_authorised_via_users_server": "@bob:example.org",
+                    "membership": "invite",
+                    "reason": "Come in",
+                    "third_party_invite": {
+                        "display_name": "alice",
+                        "signed": {
+                            "mxid": "@alice:example.org",
+                            "signatures": {
+                                "magic.forest": {
+                                    "ed25519:3": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+                                }
+                            },
+                            "token": "abc123"
+                        }
+                    }
+                },
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1432735824653,
+                "sender": "@bob:example.org",
+                "state_key": "@alice:example.org",
+                "type": "m.room.member",
+                "unsigned": {"age": 1234}
+            }"#,
+        )
+        .unwrap();
+        let redaction_json = r#"{
+            "content": {"reason": "Spam"},
+            "event_id": "$redaction:example.org",
+            "origin_server_ts": 1432735824700,
+            "redacts": "$143273582443PhrSn:example.org",
+            "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+            "sender": "@bob:example.org",
+            "type": "m.room.redaction"
+        }"#;
+        let redaction = from_str::<RedactionEvent>(redaction_json).unwrap();
+
+        let redacted = event.redact(&redaction, &RoomVersion::V8);
+        assert_eq!(
+            to_value(&redacted.content).unwrap(),
+            from_str::<Value>(r#"{"membership":"invite"}"#).unwrap()
+        );
+        assert_eq!(
+            redacted.unsigned,
+            Some(
+                from_str::<Value>(&format!(
+                    r#"{{"age":1234,"redacted_because":{}}}"#,
+                    redaction_json
+                ))
+                .unwrap()
+            )
+        );
+        assert_eq!(redacted.event_id, event.event_id);
+        assert_eq!(redacted.state_key, event.state_key);
+
+        assert_eq!(
+            to_value(&event.redact(&redaction, &RoomVersion::V9).content).unwrap(),
+            from_str::<Value>(
+                r#"{"join_authorised_via_users_server":"@bob:example.org","membership":"invite"}"#
+            )
+            .unwrap()
+        );
+
+        let redacted = event.redact(&redaction, &RoomVersion::V11);
+        assert_eq!(
+            redacted.content.join_authorised_via_users_server,
+            event.content.join_authorised_via_users_server
+        );
+        let third_party_invite = redacted.content.third_party_invite.unwrap();
+        assert_eq!(third_party_invite.display_name, None);
+        assert_eq!(third_party_invite.signed.token, "abc123");
+    }
+}
@@ -1,10 +1,27 @@
 //! Types for the *m.room.message* event.
 
-use serde::de::Error;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use ruma_identifiers::{EventId, UserId};
+use serde::de::{DeserializeOwned, Error, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::{from_value, Value};
+use serde_json::{from_value, to_value, to_vec, Error as JsonError, Value};
+
+use super::{
+    thumbnail_mxc_uri, EncryptedFile, ImageInfo, MediaSource, ThumbnailFields, ThumbnailInfo,
+};
+use {deserialize_lenient_mxc_uri, MxcUri};
 
-use super::{ImageInfo, ThumbnailInfo};
+/// The recommended maximum size in bytes of the serialized content of a message.
+pub const MAX_CONTENT_SIZE_BYTES: usize = 65535;
+
+/// The recommended maximum length in bytes of the `body` of a message.
+pub const MAX_BODY_LENGTH_BYTES: usize = 65535;
 
 room_event! {
     /// A message sent to a room.
@@ -38,6 +55,10 @@ pub enum MessageType {
     #[serde(rename = "m.notice")]
     Notice,
 
+    /// A notice from the homeserver.
+    #[serde(rename = "m.server_notice")]
+    ServerNotice,
+
     /// A text message.
     #[serde(rename = "m.text")]
     Text,
@@ -68,25 +89,57 @@ pub enum MessageEventContent {
     /// A notice message.
     Notice(NoticeMessageEventContent),
 
+    /// A notice from the homeserver.
+    ServerNotice(ServerNoticeMessageEventContent),
+
     /// An text message.
     Text(TextMessageEventContent),
 
     /// A video message.
     Video(VideoMessageEventContent),
+
+    /// A message with a message type not known to this library.
+    Custom(CustomMessageEventContent),
 }
 
+/// The payload of a message event after a redaction.
+///
+/// No field of a message survives redaction, so this serializes as an empty object. Clients can
+/// display `MessageType::redacted_body` in place of the message.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RedactedMessageEventContent {}
+
 /// The payload of an audio message.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct AudioMessageEventContent {
     /// The textual representation of this message.
     pub body: String,
+    /// The original filename of the uploaded audio clip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
     /// Metadata for the audio clip referred to in `url`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<AudioInfo>,
     /// The message type. Always *m.audio*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
     /// The URL to the audio clip.
     pub url: String,
+    /// Marks the audio clip as a voice message, as proposed in MSC3245.
+    #[serde(rename = "org.matrix.msc3245.voice")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<Voice>,
+    /// The amplitude of the audio clip over time, each value between 0 and 1024.
+    #[serde(rename = "org.matrix.msc3245.waveform")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waveform: Option<Vec<u16>>,
 }
 
 /// Metadata about an audio clip.
@@ -103,13 +156,78 @@ pub struct AudioInfo {
     pub size: Option<u64>,
 }
 
+/// The marker of a voice message. It has no fields.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Voice {}
+
+/// The payload of an audio message that is known to be a voice message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoiceMessageContent(AudioMessageEventContent);
+
+/// An error when attempting to create a `VoiceMessageContent` from an audio message that is not a
+/// voice message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NotAVoiceMessage;
+
+/// The payload of a message with a message type not known to this library.
+///
+/// Clients that do not understand the message type should fall back to displaying `body`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CustomMessageEventContent {
+    /// The textual representation of this message.
+    pub body: String,
+    /// The message type.
+    pub msgtype: String,
+    /// The remaining fields of the message content.
+    #[serde(flatten)]
+    pub extra_fields: Value,
+}
+
 /// The payload of an emote message.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct EmoteMessageEventContent {
     /// The emote action to perform.
     pub body: String,
+    /// A formatted version of the emote action, e.g. in HTML.
+    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<FormattedBody>,
+    /// The users and rooms mentioned by this message, used to decide whom to notify.
+    #[serde(rename = "m.mentions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mentions: Option<Mentions>,
     /// The message type. Always *m.emote*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
+    /// Previews of the URLs in the message, generated by the sender.
+    #[serde(rename = "m.url_previews")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_previews: Option<Vec<UrlPreview>>,
+}
+
+/// A formatted version of a message's `body`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct FormattedBody {
+    /// The format used in `body`.
+    pub format: MessageFormat,
+    /// The formatted version of the message.
+    #[serde(rename = "formatted_body")]
+    pub body: String,
+}
+
+/// The format of a `FormattedBody`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MessageFormat {
+    /// HTML.
+    #[serde(rename = "org.matrix.custom.html")]
+    Html,
 }
 
 /// The payload of a file message.
@@ -119,12 +237,21 @@ pub struct FileMessageEventContent {
     /// original upload.
     pub body: String,
     /// The original filename of the uploaded file.
-    pub filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
     /// Metadata about the file referred to in `url`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<FileInfo>,
     /// The message type. Always *m.file*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
     /// The URL to the file.
     pub url: String,
 }
@@ -136,6 +263,9 @@ pub struct FileInfo {
     pub mimetype: String,
     /// The size of the file in bytes.
     pub size: u64,
+    /// The encrypted thumbnail of the file, used instead of `thumbnail_url` in encrypted rooms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_file: Option<Box<EncryptedFile>>,
     /// Metadata about the image referred to in `thumbnail_url`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_info: Option<ThumbnailInfo>,
@@ -150,11 +280,22 @@ pub struct ImageMessageEventContent {
     /// A textual representation of the image. This could be the alt text of the image, the filename
     /// of the image, or some kind of content description for accessibility e.g. "image attachment."
     pub body: String,
+    /// The original filename of the uploaded image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
     /// Metadata about the image referred to in `url`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<ImageInfo>,
     /// The message type. Always *m.image*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
     /// The URL to the image.
     pub url: String,
 }
@@ -166,12 +307,114 @@ pub struct LocationMessageEventContent {
     /// for accessibility, e.g. "location attachment."
     pub body: String,
     /// A geo URI representing the location.
+    ///
+    /// This is kept as sent, so that a malformed geo URI from a non-compliant client does not make
+    /// the whole message fail to deserialize. It can be validated by parsing it as a `GeoUri`.
     pub geo_uri: String,
     /// The message type. Always *m.location*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
     /// Info about the location being represented.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<LocationInfo>,
+    /// What the location refers to and whether it is being shared live.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location_info: Option<GeolocationInfo>,
+}
+
+/// A geo URI as defined by RFC 5870, e.g. "geo:51.5008,0.1247;u=35".
+///
+/// The latitude, longitude, and optional altitude are validated on construction. Any parameters
+/// following the coordinates are preserved but not interpreted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoUri {
+    uri: String,
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>,
+}
+
+/// An error when attempting to parse a string that is not a valid geo URI.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GeoUriError;
+
+/// A relationship between a message and another event, sent as the *m.relates_to* field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Relation {
+    /// A rich reply to another event.
+    ///
+    /// This is the *m.in_reply_to* format, which has no `rel_type`.
+    Reply(InReplyTo),
+
+    /// A reference to another event, with a `rel_type` of *m.reference*.
+    Reference(Reference),
+
+    /// An edit of another event, with a `rel_type` of *m.replace*.
+    Replacement(Replacement),
+
+    /// A message in a thread, with a `rel_type` of *m.thread*.
+    Thread(Thread),
+
+    /// A relationship of a type not known to this crate, kept as raw JSON.
+    Custom(Value),
+}
+
+/// The event that a rich reply is in reply to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InReplyTo {
+    /// The event being replied to.
+    pub event_id: EventId,
+}
+
+/// The event that a message references.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Reference {
+    /// The event being referenced.
+    pub event_id: EventId,
+}
+
+/// The event that a message replaces.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Replacement {
+    /// The event being replaced.
+    pub event_id: EventId,
+}
+
+/// The thread that a message is in.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Thread {
+    /// The root event of the thread.
+    pub event_id: EventId,
+    /// The latest message in the thread, for clients without support for threads to show this
+    /// message as a reply to.
+    #[serde(rename = "m.in_reply_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<InReplyTo>,
+    /// Whether or not `in_reply_to` is only a fallback, rather than a real reply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_falling_back: Option<bool>,
+}
+
+/// The JSON form of `Relation::Reply`.
+#[derive(Deserialize, Serialize)]
+struct ReplyRelationJson<T> {
+    #[serde(rename = "m.in_reply_to")]
+    in_reply_to: T,
+}
+
+/// The JSON form of relations with a `rel_type`.
+#[derive(Deserialize, Serialize)]
+struct TypedRelationJson<T> {
+    rel_type: String,
+    #[serde(flatten)]
+    content: T,
 }
 
 /// Thumbnail info associated with a location.
@@ -185,13 +428,122 @@ pub struct LocationInfo {
     pub thumbnail_url: Option<String>,
 }
 
+/// Info about what a location message refers to, as proposed in MSC3489 for live location sharing.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GeolocationInfo {
+    /// What is being located.
+    pub asset: Asset,
+    /// Whether or not the location is being shared live, i.e. will be updated by later messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live: Option<bool>,
+    /// The time in milliseconds since the unix epoch at which the location was measured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<u64>,
+    /// The zoom level a map of the location should be shown at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom_level: Option<f64>,
+}
+
+/// The thing being located by a location message.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Asset {
+    /// The type of the asset.
+    #[serde(rename = "type")]
+    pub asset_type: AssetType,
+}
+
+/// The type of the thing being located by a location message.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AssetType {
+    /// The location of the sender.
+    #[serde(rename = "m.self")]
+    Self_,
+    /// A location chosen by the sender, e.g. a meeting point.
+    #[serde(rename = "m.pin")]
+    Pin,
+}
+
+/// The users mentioned by a message, and whether it mentions the whole room.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Mentions {
+    /// Whether or not the message mentions the whole room, i.e. contains *@room*.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub room: bool,
+    /// The users mentioned by the message.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub user_ids: Vec<UserId>,
+}
+
 /// The payload of a notice message.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct NoticeMessageEventContent {
     /// The notice text to send.
     pub body: String,
+    /// A formatted version of the notice text, e.g. in HTML.
+    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<FormattedBody>,
+    /// The users and rooms mentioned by this message, used to decide whom to notify.
+    #[serde(rename = "m.mentions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mentions: Option<Mentions>,
     /// The message type. Always *m.notice*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
+    /// Previews of the URLs in the message, generated by the sender.
+    #[serde(rename = "m.url_previews")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_previews: Option<Vec<UrlPreview>>,
+}
+
+/// The payload of a notice from the homeserver, e.g. about the server's limits.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ServerNoticeMessageEventContent {
+    /// A URI the user can use to contact the homeserver administrator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_contact: Option<String>,
+    /// The notice text.
+    pub body: String,
+    /// The kind of limit that was reached, e.g. *monthly_active_user*, if the notice is about a
+    /// limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_type: Option<String>,
+    /// The message type. Always *m.server_notice*.
+    pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
+    /// The kind of notice.
+    pub server_notice_type: ServerNoticeType,
+}
+
+/// The kind of a notice from the homeserver.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerNoticeType {
+    /// The homeserver has reached a usage limit, e.g. of monthly active users.
+    UsageLimitReached,
+    /// A usage threshold of the homeserver has been exceeded.
+    ThresholdExceeded,
+    /// A connection of the homeserver timed out.
+    ConnectionTimeout,
+    /// The user has exceeded the number of messages they may send per day.
+    DailyMessagesExceeded,
+    /// A kind of notice not known to this library.
+    Custom(String),
 }
 
 /// The payload of a text message.
@@ -199,8 +551,93 @@ pub struct NoticeMessageEventContent {
 pub struct TextMessageEventContent {
     /// The body of the message.
     pub body: String,
+    /// A formatted version of the message, e.g. in HTML.
+    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<FormattedBody>,
+    /// The users and rooms mentioned by this message, used to decide whom to notify.
+    #[serde(rename = "m.mentions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mentions: Option<Mentions>,
+    /// Representations of the message text in different formats, as proposed in MSC1767. When
+    /// present, these supersede `body`.
+    #[cfg(feature = "msc1767")]
+    #[serde(rename = "m.text")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub m_text: Option<Vec<TextPart>>,
     /// The message type. Always *m.text*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
+    /// Previews of the URLs in the message, generated by the sender.
+    #[serde(rename = "m.url_previews")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_previews: Option<Vec<UrlPreview>>,
+}
+
+/// One representation of a message's text, as proposed in MSC1767.
+#[cfg(feature = "msc1767")]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TextPart {
+    /// The text in the format given by `mimetype`.
+    pub body: String,
+    /// The format of `body`, e.g. "text/plain" or "text/html".
+    pub mimetype: String,
+}
+
+/// The HTML that `sanitize_html` keeps.
+///
+/// The default policy allows `<a href>`, `<b>`, `<br>`, `<code>`, `<em>`, `<h1>` to `<h6>`, `<i>`,
+/// `<li>`, `<mx-reply>`, `<ol>`, `<p>`, `<pre>`, `<strong>` and `<ul>`, with links to *https*,
+/// *http*, *ftp*, *mailto* and *magnet* URLs.
+#[cfg(feature = "html-sanitize")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SanitizePolicy {
+    /// The names of the allowed tags, in lowercase.
+    pub allowed_tags: Vec<String>,
+    /// The names of the allowed attributes of each allowed tag, in lowercase.
+    pub allowed_attributes: HashMap<String, Vec<String>>,
+    /// The schemes allowed in URLs in `href` and `src` attributes, in lowercase. URLs without one
+    /// of these schemes are removed, including relative URLs.
+    pub allowed_url_schemes: Vec<String>,
+}
+
+/// A preview of a URL in a message, as proposed in MSC4095.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UrlPreview {
+    /// The URL in the message that this preview is for.
+    #[serde(rename = "matched_url")]
+    pub url: String,
+    /// The title of the page.
+    #[serde(rename = "og:title")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_title: Option<String>,
+    /// A description of the page.
+    #[serde(rename = "og:description")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_description: Option<String>,
+    /// The image for the page, uploaded to the content repository.
+    ///
+    /// An image URL that is not a valid *mxc://* URI is treated as absent.
+    #[serde(rename = "og:image")]
+    #[serde(default, deserialize_with = "deserialize_lenient_mxc_uri")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_image: Option<MxcUri>,
+    /// The width of the image in pixels.
+    #[serde(rename = "og:image:width")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_image_width: Option<u64>,
+    /// The height of the image in pixels.
+    #[serde(rename = "og:image:height")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_image_height: Option<u64>,
 }
 
 /// The payload of a video message.
@@ -209,11 +646,22 @@ pub struct VideoMessageEventContent {
     /// A description of the video, e.g. "Gangnam Style," or some kind of content description for
     /// accessibility, e.g. "video attachment."
     pub body: String,
+    /// The original filename of the uploaded video.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
     /// Metadata about the video clip referred to in `url`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<VideoInfo>,
     /// The message type. Always *m.video*.
     pub msgtype: MessageType,
+    /// The replacement content, if this message is an edit of another message.
+    #[serde(rename = "m.new_content")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_content: Option<Box<MessageEventContent>>,
+    /// Information about related messages, e.g. the message this one replies to.
+    #[serde(rename = "m.relates_to")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<Relation>,
     /// The URL to the video clip.
     pub url: String,
 }
@@ -234,6 +682,9 @@ pub struct VideoInfo {
     /// The size of the video in bytes.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<u64>,
+    /// The encrypted thumbnail of the video, used instead of `thumbnail_url` in encrypted rooms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_file: Option<Box<EncryptedFile>>,
     /// Metadata about an image.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_info: Option<ThumbnailInfo>,
@@ -254,159 +705,2971 @@ impl_enum! {
         Image => "m.image",
         Location => "m.location",
         Notice => "m.notice",
+        ServerNotice => "m.server_notice",
         Text => "m.text",
         Video => "m.video",
     }
 }
 
-impl Serialize for MessageEventContent {
+impl MessageType {
+    /// The string form of this message type, as used in the `msgtype` field, e.g. "m.text".
+    pub fn msgtype(&self) -> &'static str {
+        self.as_str()
+    }
+
+    /// Whether or not this is *m.text*.
+    pub fn is_text(&self) -> bool {
+        *self == MessageType::Text
+    }
+
+    /// Whether or not this is *m.image*.
+    pub fn is_image(&self) -> bool {
+        *self == MessageType::Image
+    }
+
+    /// Whether or not this is *m.file*.
+    pub fn is_file(&self) -> bool {
+        *self == MessageType::File
+    }
+
+    /// Whether or not messages of this type have a media attachment, i.e. this is *m.audio*,
+    /// *m.file*, *m.image* or *m.video*.
+    pub fn is_media(&self) -> bool {
+        matches!(
+            *self,
+            MessageType::Audio | MessageType::File | MessageType::Image | MessageType::Video
+        )
+    }
+
+    /// The text to display in place of a redacted message of this type, e.g. "[Image redacted]".
+    pub fn redacted_body(&self) -> &'static str {
+        match *self {
+            MessageType::Audio => "[Audio redacted]",
+            MessageType::File => "[File redacted]",
+            MessageType::Image => "[Image redacted]",
+            MessageType::Location => "[Location redacted]",
+            MessageType::Video => "[Video redacted]",
+            MessageType::Emote
+            | MessageType::Notice
+            | MessageType::ServerNotice
+            | MessageType::Text => "[Message redacted]",
+        }
+    }
+}
+
+impl FileInfo {
+    /// The URL of the thumbnail of the file, if there is one and it is a valid MXC URI.
+    pub fn thumbnail_mxc_uri(&self) -> Option<MxcUri> {
+        thumbnail_mxc_uri(self.thumbnail_url.as_ref())
+    }
+
+    /// Whether or not the file has a thumbnail with a valid MXC URI.
+    pub fn has_thumbnail(&self) -> bool {
+        self.thumbnail_mxc_uri().is_some()
+    }
+}
+
+impl VideoInfo {
+    /// The URL of the thumbnail of the video, if there is one and it is a valid MXC URI.
+    pub fn thumbnail_mxc_uri(&self) -> Option<MxcUri> {
+        thumbnail_mxc_uri(self.thumbnail_url.as_ref())
+    }
+
+    /// Whether or not the video has a thumbnail with a valid MXC URI.
+    pub fn has_thumbnail(&self) -> bool {
+        self.thumbnail_mxc_uri().is_some()
+    }
+}
+
+impl ThumbnailFields for FileInfo {
+    fn thumbnail_fields(&self) -> (Option<&String>, Option<&EncryptedFile>) {
+        (self.thumbnail_url.as_ref(), self.thumbnail_file.as_deref())
+    }
+}
+
+impl ThumbnailFields for VideoInfo {
+    fn thumbnail_fields(&self) -> (Option<&String>, Option<&EncryptedFile>) {
+        (self.thumbnail_url.as_ref(), self.thumbnail_file.as_deref())
+    }
+}
+
+impl Display for ServerNoticeType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let server_notice_type = match *self {
+            ServerNoticeType::UsageLimitReached => "m.server_notice.usage_limit_reached",
+            ServerNoticeType::ThresholdExceeded => "m.threshold.exceeded",
+            ServerNoticeType::ConnectionTimeout => "m.connection.timeout",
+            ServerNoticeType::DailyMessagesExceeded => "m.daily_messages_exceeded",
+            ServerNoticeType::Custom(ref server_notice_type) => server_notice_type,
+        };
+
+        write!(f, "{}", server_notice_type)
+    }
+}
+
+impl<'a> From<&'a str> for ServerNoticeType {
+    fn from(s: &'a str) -> ServerNoticeType {
+        match s {
+            "m.server_notice.usage_limit_reached" => ServerNoticeType::UsageLimitReached,
+            "m.threshold.exceeded" => ServerNoticeType::ThresholdExceeded,
+            "m.connection.timeout" => ServerNoticeType::ConnectionTimeout,
+            "m.daily_messages_exceeded" => ServerNoticeType::DailyMessagesExceeded,
+            server_notice_type => ServerNoticeType::Custom(server_notice_type.to_string()),
+        }
+    }
+}
+
+impl Serialize for ServerNoticeType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match *self {
-            MessageEventContent::Audio(ref content) => content.serialize(serializer),
-            MessageEventContent::Emote(ref content) => content.serialize(serializer),
-            MessageEventContent::File(ref content) => content.serialize(serializer),
-            MessageEventContent::Image(ref content) => content.serialize(serializer),
-            MessageEventContent::Location(ref content) => content.serialize(serializer),
-            MessageEventContent::Notice(ref content) => content.serialize(serializer),
-            MessageEventContent::Text(ref content) => content.serialize(serializer),
-            MessageEventContent::Video(ref content) => content.serialize(serializer),
-        }
+        serializer.serialize_str(&self.to_string())
     }
 }
 
-impl<'de> Deserialize<'de> for MessageEventContent {
+impl<'de> Deserialize<'de> for ServerNoticeType {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let value: Value = Deserialize::deserialize(deserializer)?;
-
-        let message_type_value = match value.get("msgtype") {
-            Some(value) => value.clone(),
-            None => return Err(D::Error::missing_field("msgtype")),
-        };
-
-        let message_type = match from_value::<MessageType>(message_type_value.clone()) {
-            Ok(message_type) => message_type,
-            Err(error) => return Err(D::Error::custom(error.to_string())),
-        };
+        struct ServerNoticeTypeVisitor;
 
-        match message_type {
-            MessageType::Audio => {
-                let content = match from_value::<AudioMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+        impl<'de> Visitor<'de> for ServerNoticeTypeVisitor {
+            type Value = ServerNoticeType;
 
-                Ok(MessageEventContent::Audio(content))
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                write!(formatter, "a server notice type as a string")
             }
-            MessageType::Emote => {
-                let content = match from_value::<EmoteMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
 
-                Ok(MessageEventContent::Emote(content))
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(ServerNoticeType::from(v))
             }
-            MessageType::File => {
-                let content = match from_value::<FileMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+        }
 
-                Ok(MessageEventContent::File(content))
-            }
-            MessageType::Image => {
-                let content = match from_value::<ImageMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+        deserializer.deserialize_str(ServerNoticeTypeVisitor)
+    }
+}
 
-                Ok(MessageEventContent::Image(content))
-            }
-            MessageType::Location => {
-                let content = match from_value::<LocationMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+impl_enum! {
+    AssetType {
+        Self_ => "m.self",
+        Pin => "m.pin",
+    }
+}
 
-                Ok(MessageEventContent::Location(content))
-            }
-            MessageType::Notice => {
-                let content = match from_value::<NoticeMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+impl_enum! {
+    MessageFormat {
+        Html => "org.matrix.custom.html",
+    }
+}
 
-                Ok(MessageEventContent::Notice(content))
-            }
-            MessageType::Text => {
-                let content = match from_value::<TextMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+impl GeoUri {
+    /// The latitude in decimal degrees.
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
 
-                Ok(MessageEventContent::Text(content))
-            }
-            MessageType::Video => {
-                let content = match from_value::<VideoMessageEventContent>(value) {
-                    Ok(content) => content,
-                    Err(error) => return Err(D::Error::custom(error.to_string())),
-                };
+    /// The longitude in decimal degrees.
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
 
-                Ok(MessageEventContent::Video(content))
-            }
-        }
+    /// The altitude in meters, if any.
+    pub fn altitude(&self) -> Option<f64> {
+        self.altitude
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use serde_json::{from_str, to_string};
-
-    use super::{AudioMessageEventContent, MessageEventContent, MessageType};
+impl Display for GeoUri {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl AsRef<str> for GeoUri {
+    fn as_ref(&self) -> &str {
+        &self.uri
+    }
+}
+
+impl FromStr for GeoUri {
+    type Err = GeoUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("geo:") {
+            return Err(GeoUriError);
+        }
+
+        let coordinates = s["geo:".len()..].split(';').next().unwrap_or("");
+        let parts = coordinates
+            .split(',')
+            .map(|part| part.parse::<f64>().map_err(|_| GeoUriError))
+            .collect::<Result<Vec<f64>, GeoUriError>>()?;
+
+        let (latitude, longitude, altitude) = match parts.as_slice() {
+            [latitude, longitude] => (*latitude, *longitude, None),
+            [latitude, longitude, altitude] => (*latitude, *longitude, Some(*altitude)),
+            _ => return Err(GeoUriError),
+        };
+
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(GeoUriError);
+        }
+
+        if let Some(altitude) = altitude {
+            if !altitude.is_finite() {
+                return Err(GeoUriError);
+            }
+        }
+
+        Ok(GeoUri {
+            uri: s.to_string(),
+            latitude,
+            longitude,
+            altitude,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a str> for GeoUri {
+    type Error = GeoUriError;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Serialize for GeoUri {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.uri)
+    }
+}
+
+impl<'de> Deserialize<'de> for GeoUri {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct GeoUriVisitor;
+
+        impl<'de> Visitor<'de> for GeoUriVisitor {
+            type Value = GeoUri;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                write!(formatter, "a geo URI as a string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                v.parse()
+                    .map_err(|_| E::custom(format!("invalid geo URI: {}", v)))
+            }
+        }
+
+        deserializer.deserialize_str(GeoUriVisitor)
+    }
+}
+
+impl Relation {
+    /// The reply that clients without support for threads show a message in a thread as.
+    ///
+    /// This is `None` unless this is a thread relation with an *m.in_reply_to*. Use
+    /// `is_thread_reply_fallback` to tell whether the reply is only a fallback or a real reply
+    /// within the thread.
+    pub fn thread_reply_to_fallback(&self) -> Option<&InReplyTo> {
+        match *self {
+            Relation::Thread(ref thread) => thread.in_reply_to.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether or not this is a thread relation whose *m.in_reply_to* is only a fallback, i.e.
+    /// `is_falling_back` is `true`.
+    pub fn is_thread_reply_fallback(&self) -> bool {
+        match *self {
+            Relation::Thread(ref thread) => {
+                thread.in_reply_to.is_some() && thread.is_falling_back == Some(true)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for Relation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Relation::Reply(ref in_reply_to) => {
+                ReplyRelationJson { in_reply_to }.serialize(serializer)
+            }
+            Relation::Reference(ref reference) => TypedRelationJson {
+                rel_type: "m.reference".to_string(),
+                content: reference,
+            }
+            .serialize(serializer),
+            Relation::Replacement(ref replacement) => TypedRelationJson {
+                rel_type: "m.replace".to_string(),
+                content: replacement,
+            }
+            .serialize(serializer),
+            Relation::Thread(ref thread) => TypedRelationJson {
+                rel_type: "m.thread".to_string(),
+                content: thread,
+            }
+            .serialize(serializer),
+            Relation::Custom(ref value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Relation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+
+        match value.get("rel_type").and_then(Value::as_str) {
+            Some("m.reference") => {
+                let relation = match from_value::<TypedRelationJson<Reference>>(value) {
+                    Ok(relation) => relation,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Relation::Reference(relation.content))
+            }
+            Some("m.replace") => {
+                let relation = match from_value::<TypedRelationJson<Replacement>>(value) {
+                    Ok(relation) => relation,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Relation::Replacement(relation.content))
+            }
+            Some("m.thread") => {
+                let relation = match from_value::<TypedRelationJson<Thread>>(value) {
+                    Ok(relation) => relation,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Relation::Thread(relation.content))
+            }
+            None if value.get("m.in_reply_to").is_some() => {
+                let relation = match from_value::<ReplyRelationJson<InReplyTo>>(value) {
+                    Ok(relation) => relation,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(Relation::Reply(relation.in_reply_to))
+            }
+            _ => Ok(Relation::Custom(value)),
+        }
+    }
+}
+
+impl AudioMessageEventContent {
+    /// The name to display for the audio clip: `filename` if it is set, otherwise `body`.
+    pub fn display_name(&self) -> &str {
+        attachment_display_name(self.filename.as_ref(), &self.body)
+    }
+
+    /// Whether or not this audio clip is a voice message, i.e. it has the MSC3245 `voice` marker.
+    pub fn is_voice_message(&self) -> bool {
+        self.voice.is_some()
+    }
+}
+
+impl VoiceMessageContent {
+    /// The audio message.
+    pub fn content(&self) -> &AudioMessageEventContent {
+        &self.0
+    }
+
+    /// Consumes the voice message, returning the audio message.
+    pub fn into_content(self) -> AudioMessageEventContent {
+        self.0
+    }
+}
+
+impl TryFrom<AudioMessageEventContent> for VoiceMessageContent {
+    type Error = NotAVoiceMessage;
+
+    fn try_from(audio: AudioMessageEventContent) -> Result<Self, Self::Error> {
+        if audio.is_voice_message() {
+            Ok(VoiceMessageContent(audio))
+        } else {
+            Err(NotAVoiceMessage)
+        }
+    }
+}
+
+impl CustomMessageEventContent {
+    /// Deserializes the whole content, including `body` and `msgtype`, as a type that knows about
+    /// this message type.
+    pub fn try_as<T: DeserializeOwned>(&self) -> Result<T, JsonError> {
+        from_value(to_value(self)?)
+    }
+}
+
+impl FileMessageEventContent {
+    /// The name to display for the file: `filename` if it is set, otherwise `body`.
+    pub fn display_name(&self) -> &str {
+        attachment_display_name(self.filename.as_ref(), &self.body)
+    }
+
+    /// Where to fetch the thumbnail of the file from, whether it is plain or encrypted.
+    pub fn thumbnail_source(&self) -> Option<MediaSource> {
+        MediaSource::thumbnail(self.info.as_ref())
+    }
+}
+
+impl ImageMessageEventContent {
+    /// The name to display for the image: `filename` if it is set, otherwise `body`.
+    pub fn display_name(&self) -> &str {
+        attachment_display_name(self.filename.as_ref(), &self.body)
+    }
+
+    /// Where to fetch the thumbnail of the image from, whether it is plain or encrypted.
+    pub fn thumbnail_source(&self) -> Option<MediaSource> {
+        MediaSource::thumbnail(self.info.as_ref())
+    }
+}
+
+impl LocationMessageEventContent {
+    /// The latitude, longitude and altitude of the location, in that order, or `None` if
+    /// `geo_uri` is not a valid geo URI.
+    pub fn coordinates(&self) -> Option<(f64, f64, Option<f64>)> {
+        let geo_uri = self.geo_uri.parse::<GeoUri>().ok()?;
+
+        Some((geo_uri.latitude(), geo_uri.longitude(), geo_uri.altitude()))
+    }
+}
+
+impl TextMessageEventContent {
+    /// Creates a text message without formatting.
+    pub fn plain(body: impl Into<String>) -> Self {
+        TextMessageEventContent {
+            body: body.into(),
+            formatted: None,
+            mentions: None,
+            #[cfg(feature = "msc1767")]
+            m_text: None,
+            msgtype: MessageType::Text,
+            new_content: None,
+            relates_to: None,
+            url_previews: None,
+        }
+    }
+
+    /// Creates a text message formatted as HTML, with `body` as its plain text version.
+    pub fn html(body: impl Into<String>, html: impl Into<String>) -> Self {
+        TextMessageEventContent {
+            formatted: Some(FormattedBody {
+                format: MessageFormat::Html,
+                body: html.into(),
+            }),
+            ..TextMessageEventContent::plain(body)
+        }
+    }
+}
+
+impl VideoMessageEventContent {
+    /// The name to display for the video: `filename` if it is set, otherwise `body`.
+    pub fn display_name(&self) -> &str {
+        attachment_display_name(self.filename.as_ref(), &self.body)
+    }
+
+    /// Where to fetch the thumbnail of the video from, whether it is plain or encrypted.
+    pub fn thumbnail_source(&self) -> Option<MediaSource> {
+        MediaSource::thumbnail(self.info.as_ref())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl EmoteMessageEventContent {
+    /// Normalizes `body` and the formatted body, if any, to Unicode Normalization Form C.
+    pub fn normalize_body_nfc(&mut self) {
+        self.body = nfc(&self.body);
+
+        if let Some(ref mut formatted) = self.formatted {
+            formatted.body = nfc(&formatted.body);
+        }
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl NoticeMessageEventContent {
+    /// Normalizes `body` and the formatted body, if any, to Unicode Normalization Form C.
+    pub fn normalize_body_nfc(&mut self) {
+        self.body = nfc(&self.body);
+
+        if let Some(ref mut formatted) = self.formatted {
+            formatted.body = nfc(&formatted.body);
+        }
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl TextMessageEventContent {
+    /// Normalizes `body` and the formatted body, if any, to Unicode Normalization Form C.
+    pub fn normalize_body_nfc(&mut self) {
+        self.body = nfc(&self.body);
+
+        if let Some(ref mut formatted) = self.formatted {
+            formatted.body = nfc(&formatted.body);
+        }
+    }
+}
+
+#[cfg(feature = "msc1767")]
+impl TextMessageEventContent {
+    /// The richest available text of the message.
+    ///
+    /// If `m_text` is present, its HTML part is preferred over its plain text part. Otherwise the
+    /// formatted body is preferred over `body`. If `m_text` contains neither an HTML nor a plain
+    /// text part, this is `body`.
+    pub fn best_text_representation(&self) -> &str {
+        if let Some(ref parts) = self.m_text {
+            return parts
+                .iter()
+                .find(|part| part.mimetype == "text/html")
+                .or_else(|| parts.iter().find(|part| part.mimetype == "text/plain"))
+                .map_or(&self.body, |part| &part.body);
+        }
+
+        match self.formatted {
+            Some(ref formatted) => &formatted.body,
+            None => &self.body,
+        }
+    }
+}
+
+#[cfg(feature = "html-sanitize")]
+impl SanitizePolicy {
+    /// Whether or not the attribute `name` with the given value is allowed on the tag `tag`.
+    fn allows_attribute(&self, tag: &str, name: &str, value: &str) -> bool {
+        match self.allowed_attributes.get(tag) {
+            Some(attributes) if attributes.iter().any(|attribute| attribute == name) => {}
+            _ => return false,
+        }
+
+        if name != "href" && name != "src" {
+            return true;
+        }
+
+        let url = value.trim().to_ascii_lowercase();
+        self.allowed_url_schemes
+            .iter()
+            .any(|scheme| url.starts_with(scheme.as_str()) && url[scheme.len()..].starts_with(':'))
+    }
+}
+
+#[cfg(feature = "html-sanitize")]
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+        let mut allowed_attributes = HashMap::new();
+        allowed_attributes.insert("a".to_string(), strings(&["href"]));
+
+        SanitizePolicy {
+            allowed_tags: strings(&[
+                "a", "b", "br", "code", "em", "h1", "h2", "h3", "h4", "h5", "h6", "i", "li",
+                "mx-reply", "ol", "p", "pre", "strong", "ul",
+            ]),
+            allowed_attributes,
+            allowed_url_schemes: strings(&["https", "http", "ftp", "mailto", "magnet"]),
+        }
+    }
+}
+
+/// A tag found by `sanitize_html`.
+#[cfg(feature = "html-sanitize")]
+struct Tag<'a> {
+    closing: bool,
+    name: String,
+    attributes: Vec<(String, &'a str)>,
+}
+
+/// The position of the `>` ending the tag at the start of `html`, ignoring any in quoted
+/// attribute values.
+#[cfg(any(feature = "html-sanitize", feature = "html-strip"))]
+fn find_tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in html.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return Some(i),
+            None => {}
+        }
+    }
+
+    None
+}
+
+/// Parses the text between `<` and `>`, returning `None` if it is not a tag, e.g. a doctype.
+#[cfg(feature = "html-sanitize")]
+fn parse_tag(tag: &str) -> Option<Tag<'_>> {
+    let (closing, tag) = if let Some(tag) = tag.strip_prefix('/') {
+        (true, tag)
+    } else {
+        (false, tag)
+    };
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = &tag[name_end..];
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            break;
+        }
+
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = "";
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            rest = after_equals.trim_start();
+
+            match rest.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    let value_end = rest[1..].find(quote).map_or(rest.len(), |end| end + 1);
+                    value = &rest[1..value_end];
+                    rest = rest.get(value_end + 1..).unwrap_or("");
+                }
+                _ => {
+                    let value_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    value = &rest[..value_end];
+                    rest = &rest[value_end..];
+                }
+            }
+        }
+
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+    }
+
+    Some(Tag {
+        closing,
+        name,
+        attributes,
+    })
+}
+
+/// Appends text found between tags to `html`, escaping any `<` and `>`.
+#[cfg(feature = "html-sanitize")]
+fn push_escaped_text(html: &mut String, text: &str) {
+    html.push_str(&text.replace('<', "&lt;").replace('>', "&gt;"));
+}
+
+/// Escapes an attribute value to be quoted with `"`.
+#[cfg(feature = "html-sanitize")]
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The `display_name` of the audio, file, image and video message contents.
+fn attachment_display_name<'a>(filename: Option<&'a String>, body: &'a str) -> &'a str {
+    filename.map_or(body, |filename| filename)
+}
+
+#[cfg(feature = "unicode-normalization")]
+fn nfc(s: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+
+    s.nfc().collect()
+}
+
+/// Converts the HTML of a `formatted_body` to plain text.
+///
+/// Tags and comments are removed, `<script>` and `<style>` with their content. Line breaks and the
+/// ends of paragraphs and other block elements become newlines. The character references `&amp;`,
+/// `&lt;`, `&gt;`, `&quot;`, `&#39;` and `&nbsp;` and numeric character references such as
+/// `&#8212;` are decoded. A `<` that does not start a tag, e.g. in "I <3 you", and a tag that
+/// never ends are kept as text. This is meant for notification text and screen readers, not for
+/// sanitizing HTML.
+#[cfg(feature = "html-strip")]
+pub fn strip_html_tags(formatted_body: &str) -> String {
+    let mut text = String::with_capacity(formatted_body.len());
+    let mut rest = formatted_body;
+
+    while let Some(start) = rest.find(&['<', '&'][..]) {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with('<') {
+            if !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') {
+                text.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+
+            if rest.starts_with("<!--") {
+                rest = match rest.find("-->") {
+                    Some(end) => &rest[end + "-->".len()..],
+                    None => "",
+                };
+                continue;
+            }
+
+            let end = match find_tag_end(rest) {
+                Some(end) => end,
+                None => break,
+            };
+            let tag = rest[1..end].trim().trim_end_matches('/').to_lowercase();
+            let name = tag.split_whitespace().next().unwrap_or("");
+            rest = &rest[end + 1..];
+
+            match name {
+                "br" => text.push('\n'),
+                "/p" | "/div" | "/li" | "/blockquote" | "/pre" | "/h1" | "/h2" | "/h3" | "/h4"
+                | "/h5" | "/h6"
+                    if !text.is_empty() && !text.ends_with('\n') =>
+                {
+                    text.push('\n')
+                }
+                "script" | "style" => {
+                    let closing_tag = format!("</{}", name);
+                    rest = match rest.to_ascii_lowercase().find(&closing_tag) {
+                        Some(end) => &rest[end..],
+                        None => "",
+                    };
+                }
+                _ => {}
+            }
+        } else {
+            match decode_character_reference(rest) {
+                Some((c, len)) => {
+                    text.push(c);
+                    rest = &rest[len..];
+                }
+                None => {
+                    text.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+
+    text.push_str(rest);
+
+    let len = text.trim_end_matches('\n').len();
+    text.truncate(len);
+    text
+}
+
+/// Decodes the character reference at the start of `html`, returning the character and the
+/// length of the reference.
+#[cfg(feature = "html-strip")]
+fn decode_character_reference(html: &str) -> Option<(char, usize)> {
+    let named = [
+        ("&amp;", '&'),
+        ("&lt;", '<'),
+        ("&gt;", '>'),
+        ("&quot;", '"'),
+        ("&#39;", '\''),
+        ("&nbsp;", '\u{a0}'),
+    ]
+    .iter()
+    .find(|&&(entity, _)| html.starts_with(entity));
+
+    if let Some(&(entity, c)) = named {
+        return Some((c, entity.len()));
+    }
+
+    let end = html.find(';')?;
+    let number = html[..end].strip_prefix("&#")?;
+    let code_point = match number.strip_prefix(|c| c == 'x' || c == 'X') {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse().ok()?,
+    };
+
+    std::char::from_u32(code_point).map(|c| (c, end + 1))
+}
+
+/// Removes the tags and attributes that `policy` does not allow from the HTML of a
+/// `formatted_body`, so that it can be rendered safely.
+///
+/// Tags that are not allowed are removed but their content is kept, except for `<script>` and
+/// `<style>`, which are removed with their content. Comments are removed and tags left open are
+/// closed at the end. A `<` that does not start a tag, e.g. in "1 < 2", and a tag that never ends
+/// are kept as escaped text. This is a simple scanner rather than a full HTML parser, so it errs
+/// on the side of escaping markup it does not understand.
+#[cfg(feature = "html-sanitize")]
+pub fn sanitize_html(html: &str, policy: &SanitizePolicy) -> String {
+    let mut sanitized = String::with_capacity(html.len());
+    let mut open_tags = Vec::<String>::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        push_escaped_text(&mut sanitized, &rest[..start]);
+        rest = &rest[start..];
+
+        if !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') {
+            sanitized.push_str("&lt;");
+            rest = &rest[1..];
+            continue;
+        }
+
+        if rest.starts_with("<!--") {
+            rest = match rest.find("-->") {
+                Some(end) => &rest[end + "-->".len()..],
+                None => "",
+            };
+            continue;
+        }
+
+        let end = match find_tag_end(rest) {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = parse_tag(&rest[1..end]);
+        rest = &rest[end + 1..];
+
+        let tag = match tag {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        if !policy.allowed_tags.contains(&tag.name) {
+            if !tag.closing && (tag.name == "script" || tag.name == "style") {
+                let closing_tag = format!("</{}", tag.name);
+                rest = match rest.to_ascii_lowercase().find(&closing_tag) {
+                    Some(end) => &rest[end..],
+                    None => "",
+                };
+            }
+        } else if tag.closing {
+            if let Some(position) = open_tags.iter().rposition(|name| *name == tag.name) {
+                for name in open_tags.drain(position..).rev() {
+                    sanitized.push_str(&format!("</{}>", name));
+                }
+            }
+        } else {
+            sanitized.push('<');
+            sanitized.push_str(&tag.name);
+
+            for (name, value) in tag.attributes {
+                if policy.allows_attribute(&tag.name, &name, value) {
+                    sanitized.push_str(&format!(
+                        " {}=\"{}\"",
+                        name,
+                        escape_attribute(value.trim())
+                    ));
+                }
+            }
+
+            sanitized.push('>');
+
+            if !["br", "hr", "img"].contains(&tag.name.as_str()) {
+                open_tags.push(tag.name);
+            }
+        }
+    }
+
+    push_escaped_text(&mut sanitized, rest);
+
+    for name in open_tags.iter().rev() {
+        sanitized.push_str(&format!("</{}>", name));
+    }
+
+    sanitized
+}
+
+/// The body of a reply to `reply_to`, quoting it as a fallback for clients without support for
+/// rich replies.
+///
+/// Each line of the body of `reply_to` is prefixed with `> `, and the first line with the sender,
+/// e.g. `> <@alice:example.org> Hello`. An earlier fallback in the body of `reply_to` is not quoted
+/// again.
+pub fn with_reply_fallback(reply_to: &MessageEvent, new_body: &str) -> String {
+    let quoted = strip_reply_fallback(reply_to.content.body());
+    let mut body = String::with_capacity(quoted.len() + new_body.len() + 64);
+
+    for (i, line) in quoted.split('\n').enumerate() {
+        if i > 0 {
+            body.push_str(&format!("> {}\n", line));
+        } else if let MessageEventContent::Emote(_) = reply_to.content {
+            body.push_str(&format!("> * <{}> {}\n", reply_to.sender, line));
+        } else {
+            body.push_str(&format!("> <{}> {}\n", reply_to.sender, line));
+        }
+    }
+
+    body.push('\n');
+    body.push_str(new_body);
+    body
+}
+
+/// The body of a reply without the fallback quoting the message it replies to.
+///
+/// The fallback is the leading lines starting with `>` and the empty line following them. A body
+/// without a fallback is returned unchanged.
+pub fn strip_reply_fallback(body: &str) -> &str {
+    let mut rest = body;
+
+    while rest.starts_with('>') {
+        rest = match rest.find('\n') {
+            Some(end) => &rest[end + 1..],
+            None => "",
+        };
+    }
+
+    if rest.len() < body.len() && rest.starts_with('\n') {
+        &rest[1..]
+    } else {
+        rest
+    }
+}
+
+/// The content to display for `original` after applying the most recent of `edits`.
+///
+/// Only edits that replace `original` and were sent by the same user are considered. The most
+/// recent one by `origin_server_ts` is applied with `MessageEventContent::apply_edit`. The content
+/// of `original` is borrowed if there is no such edit.
+pub fn resolve_edits<'a>(
+    original: &'a MessageEvent,
+    edits: impl IntoIterator<Item = &'a MessageEvent>,
+) -> Cow<'a, MessageEventContent> {
+    let latest_edit = edits
+        .into_iter()
+        .filter(|edit| {
+            edit.sender == original.sender
+                && edit.content.edited_event_id() == Some(&original.event_id)
+        })
+        .max_by_key(|edit| edit.origin_server_ts);
+
+    match latest_edit.and_then(|edit| edit.content.apply_edit(&original.content)) {
+        Some(content) => Cow::Owned(content),
+        None => Cow::Borrowed(&original.content),
+    }
+}
+
+/// The text of a notification for `event`, sent by the user with the given display name.
+///
+/// Messages with a text body are shown as "Alice: Hello", without the reply fallback if they are
+/// replies, and emotes as "* Alice waves". Attachments and locations are described instead, e.g.
+/// "Alice sent an image".
+pub fn notification_text(event: &MessageEvent, sender_display_name: &str) -> String {
+    let body = match event.content.relates_to() {
+        Some(&Relation::Reply(_)) => match strip_reply_fallback(event.content.body()) {
+            "" => event.content.body(),
+            stripped => stripped,
+        },
+        _ => event.content.body(),
+    };
+
+    match event.content {
+        MessageEventContent::Audio(ref content) if content.is_voice_message() => {
+            format!("{} sent a voice message", sender_display_name)
+        }
+        MessageEventContent::Audio(_) => format!("{} sent an audio clip", sender_display_name),
+        MessageEventContent::Emote(_) => format!("* {} {}", sender_display_name, body),
+        MessageEventContent::File(_) => format!("{} sent a file", sender_display_name),
+        MessageEventContent::Image(_) => format!("{} sent an image", sender_display_name),
+        MessageEventContent::Location(_) => format!("{} shared a location", sender_display_name),
+        MessageEventContent::Video(_) => format!("{} sent a video", sender_display_name),
+        _ => format!("{}: {}", sender_display_name, body),
+    }
+}
+
+/// The number of `events` in the thread whose root is the event with the given ID.
+///
+/// The root itself is not counted, and neither are messages that only reply to the root without
+/// being in its thread.
+pub fn count_thread_replies<'a>(
+    root_event_id: &EventId,
+    events: impl IntoIterator<Item = &'a MessageEvent>,
+) -> usize {
+    events
+        .into_iter()
+        .filter(|event| event.content.thread_root_id() == Some(root_event_id))
+        .count()
+}
+
+/// A source of the messages that replies refer to, e.g. a client's event store.
+pub trait ReplyFetcher {
+    /// The message with the given ID, if it is available.
+    fn fetch(&self, event_id: &EventId) -> Option<MessageEvent>;
+}
+
+/// A `ReplyFetcher` that remembers the messages fetched by another `ReplyFetcher`.
+///
+/// Only messages that were found are cached, so missing messages are fetched again next time.
+#[derive(Debug)]
+pub struct CachingReplyFetcher<F> {
+    cache: RefCell<HashMap<EventId, MessageEvent>>,
+    fetcher: F,
+}
+
+impl<F: ReplyFetcher> CachingReplyFetcher<F> {
+    /// Creates an empty cache in front of the given fetcher.
+    pub fn new(fetcher: F) -> Self {
+        CachingReplyFetcher {
+            cache: RefCell::new(HashMap::new()),
+            fetcher,
+        }
+    }
+}
+
+impl<F: ReplyFetcher> ReplyFetcher for CachingReplyFetcher<F> {
+    fn fetch(&self, event_id: &EventId) -> Option<MessageEvent> {
+        if let Some(event) = self.cache.borrow().get(event_id) {
+            return Some(event.clone());
+        }
+
+        let event = self.fetcher.fetch(event_id)?;
+        self.cache
+            .borrow_mut()
+            .insert(event_id.clone(), event.clone());
+
+        Some(event)
+    }
+}
+
+impl MessageEvent {
+    /// The transaction ID the sending client used for this message, if the server included it.
+    ///
+    /// Homeservers only set `unsigned.transaction_id` on events echoed back to the client that sent
+    /// them, so this can be used to match a local echo with the event it was sent as.
+    pub fn transaction_id(&self) -> Option<&str> {
+        self.unsigned
+            .as_ref()
+            .and_then(|unsigned| unsigned.get("transaction_id"))
+            .and_then(Value::as_str)
+    }
+}
+
+impl MessageEventContent {
+    /// The textual representation of this message.
+    pub fn body(&self) -> &str {
+        match *self {
+            MessageEventContent::Audio(ref content) => &content.body,
+            MessageEventContent::Emote(ref content) => &content.body,
+            MessageEventContent::File(ref content) => &content.body,
+            MessageEventContent::Image(ref content) => &content.body,
+            MessageEventContent::Location(ref content) => &content.body,
+            MessageEventContent::Notice(ref content) => &content.body,
+            MessageEventContent::ServerNotice(ref content) => &content.body,
+            MessageEventContent::Text(ref content) => &content.body,
+            MessageEventContent::Video(ref content) => &content.body,
+            MessageEventContent::Custom(ref content) => &content.body,
+        }
+    }
+
+    /// A plain text summary of this message, e.g. for a notification.
+    ///
+    /// Attachments and locations are prefixed with their kind, e.g. "[Image] cat.png". Files are
+    /// summarized by their `filename`, falling back to the `body`, and locations by their geo URI.
+    pub fn fallback_text(&self) -> String {
+        match *self {
+            MessageEventContent::Audio(ref content) => format!("[Audio] {}", content.body),
+            MessageEventContent::File(ref content) => format!(
+                "[File] {}",
+                content.filename.as_ref().unwrap_or(&content.body)
+            ),
+            MessageEventContent::Image(ref content) => format!("[Image] {}", content.body),
+            MessageEventContent::Location(ref content) => {
+                format!("[Location] {}", content.geo_uri)
+            }
+            MessageEventContent::Video(ref content) => format!("[Video] {}", content.body),
+            _ => self.body().to_string(),
+        }
+    }
+
+    /// The content of this message after a redaction, which keeps no field.
+    pub fn redact(&self) -> RedactedMessageEventContent {
+        RedactedMessageEventContent {}
+    }
+
+    /// Whether the body of this message is no longer than the recommended
+    /// `MAX_BODY_LENGTH_BYTES`.
+    pub fn body_is_within_limit(&self) -> bool {
+        self.body().len() <= MAX_BODY_LENGTH_BYTES
+    }
+
+    /// The body of this message, shortened to at most `max_bytes` bytes if it is longer.
+    ///
+    /// A shortened body is cut at a character boundary and ends with `...`, which counts towards
+    /// `max_bytes`. If `max_bytes` is too small to fit the ellipsis, the body is only cut.
+    pub fn truncated_body(&self, max_bytes: usize) -> Cow<'_, str> {
+        let body = self.body();
+
+        if body.len() <= max_bytes {
+            return Cow::Borrowed(body);
+        }
+
+        let ellipsis = if max_bytes < "...".len() { "" } else { "..." };
+        let mut end = max_bytes - ellipsis.len();
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        Cow::Owned(format!("{}{}", &body[..end], ellipsis))
+    }
+
+    /// The size in bytes of the file attached to this message, if known.
+    ///
+    /// This is taken from the `size` of the attachment's metadata, so it is `None` for messages
+    /// without an attachment or whose metadata does not include a size.
+    pub fn content_size_bytes(&self) -> Option<u64> {
+        match *self {
+            MessageEventContent::Audio(ref content) => {
+                content.info.as_ref().and_then(|info| info.size)
+            }
+            MessageEventContent::File(ref content) => content.info.as_ref().map(|info| info.size),
+            MessageEventContent::Image(ref content) => content.info.as_ref().map(|info| info.size),
+            MessageEventContent::Video(ref content) => {
+                content.info.as_ref().and_then(|info| info.size)
+            }
+            MessageEventContent::Custom(_)
+            | MessageEventContent::Emote(_)
+            | MessageEventContent::Location(_)
+            | MessageEventContent::Notice(_)
+            | MessageEventContent::ServerNotice(_)
+            | MessageEventContent::Text(_) => None,
+        }
+    }
+
+    /// Information about related messages, e.g. the message this one replies to.
+    ///
+    /// This is always `None` for custom messages.
+    pub fn relates_to(&self) -> Option<&Relation> {
+        match *self {
+            MessageEventContent::Audio(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Emote(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::File(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Image(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Location(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Notice(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::ServerNotice(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Text(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Video(ref content) => content.relates_to.as_ref(),
+            MessageEventContent::Custom(_) => None,
+        }
+    }
+
+    /// The replacement content, if this message is an edit of another message.
+    ///
+    /// This is always `None` for custom messages.
+    pub fn new_content(&self) -> Option<&MessageEventContent> {
+        let new_content = match *self {
+            MessageEventContent::Audio(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Emote(ref content) => content.new_content.as_ref(),
+            MessageEventContent::File(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Image(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Location(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Notice(ref content) => content.new_content.as_ref(),
+            MessageEventContent::ServerNotice(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Text(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Video(ref content) => content.new_content.as_ref(),
+            MessageEventContent::Custom(_) => None,
+        };
+
+        new_content.map(|new_content| &**new_content)
+    }
+
+    /// Whether or not this message was sent by an automated client, such as a bot, rather than by a
+    /// person.
+    ///
+    /// Automated messages are sent as notices, by bots, or server notices, by the homeserver.
+    /// Clients may display them differently.
+    pub fn is_bot_message(&self) -> bool {
+        matches!(
+            *self,
+            MessageEventContent::Notice(_) | MessageEventContent::ServerNotice(_)
+        )
+    }
+
+    /// The users and rooms mentioned by this message, if it is a text, notice or emote message.
+    pub fn mentions(&self) -> Option<&Mentions> {
+        match *self {
+            MessageEventContent::Emote(ref content) => content.mentions.as_ref(),
+            MessageEventContent::Notice(ref content) => content.mentions.as_ref(),
+            MessageEventContent::Text(ref content) => content.mentions.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether or not this message mentions the whole room, e.g. with *@room*.
+    pub fn mentions_room(&self) -> bool {
+        self.mentions().map(|mentions| mentions.room) == Some(true)
+    }
+
+    /// Whether or not this message mentions the given user.
+    pub fn mentions_user(&self, user_id: &UserId) -> bool {
+        match self.mentions() {
+            Some(mentions) => mentions.user_ids.contains(user_id),
+            None => false,
+        }
+    }
+
+    /// The body of the message this message replies to, without its own reply fallback.
+    ///
+    /// The message is looked up with `fetcher`, so this is `None` if this message is not a reply or
+    /// the message it replies to is not available. The body is returned by value because the
+    /// fetched message does not outlive this call.
+    pub fn reply_preview(&self, fetcher: &impl ReplyFetcher) -> Option<String> {
+        let event_id = match self.relates_to() {
+            Some(Relation::Reply(in_reply_to)) => &in_reply_to.event_id,
+            _ => return None,
+        };
+
+        fetcher
+            .fetch(event_id)
+            .map(|event| strip_reply_fallback(event.content.body()).to_string())
+    }
+
+    /// Whether or not this message is an edit of another message.
+    pub fn is_edit(&self) -> bool {
+        self.edited_event_id().is_some()
+    }
+
+    /// The ID of the event this message edits, if it is an edit.
+    pub fn edited_event_id(&self) -> Option<&EventId> {
+        match self.relates_to() {
+            Some(Relation::Replacement(replacement)) => Some(&replacement.event_id),
+            _ => None,
+        }
+    }
+
+    /// The ID of the root event of the thread this message is in, if it is in a thread.
+    pub fn thread_root_id(&self) -> Option<&EventId> {
+        match self.relates_to() {
+            Some(Relation::Thread(thread)) => Some(&thread.event_id),
+            _ => None,
+        }
+    }
+
+    /// The content of `original` after applying this edit to it.
+    ///
+    /// The result is the `new_content` of this message, keeping the relation of `original` (e.g.
+    /// the message it replies to). Returns `None` if this message is not an edit or has no
+    /// `new_content`.
+    pub fn apply_edit(&self, original: &MessageEventContent) -> Option<MessageEventContent> {
+        if !self.is_edit() {
+            return None;
+        }
+
+        let mut edited = self.new_content()?.clone();
+        let relates_to = original.relates_to().cloned();
+
+        match edited {
+            MessageEventContent::Audio(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Emote(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::File(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Image(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Location(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Notice(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::ServerNotice(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Text(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Video(ref mut content) => content.relates_to = relates_to,
+            MessageEventContent::Custom(_) => {}
+        }
+
+        Some(edited)
+    }
+
+    /// The size in bytes of this content when serialized as JSON.
+    pub fn serialized_size_bytes(&self) -> Result<usize, JsonError> {
+        to_vec(self).map(|bytes| bytes.len())
+    }
+
+    /// Whether this content is larger than the recommended `MAX_CONTENT_SIZE_BYTES` when
+    /// serialized, and would likely be rejected by the homeserver.
+    pub fn exceeds_event_size_limit(&self) -> Result<bool, JsonError> {
+        self.serialized_size_bytes()
+            .map(|size| size > MAX_CONTENT_SIZE_BYTES)
+    }
+
+    /// Whether the file attached to this message is known to be larger than `limit_bytes`.
+    ///
+    /// Returns `false` if the size of the attachment is unknown.
+    pub fn exceeds_size_limit(&self, limit_bytes: u64) -> bool {
+        match self.content_size_bytes() {
+            Some(size) => size > limit_bytes,
+            None => false,
+        }
+    }
+}
+
+impl Serialize for MessageEventContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            MessageEventContent::Audio(ref content) => content.serialize(serializer),
+            MessageEventContent::Emote(ref content) => content.serialize(serializer),
+            MessageEventContent::File(ref content) => content.serialize(serializer),
+            MessageEventContent::Image(ref content) => content.serialize(serializer),
+            MessageEventContent::Location(ref content) => content.serialize(serializer),
+            MessageEventContent::Notice(ref content) => content.serialize(serializer),
+            MessageEventContent::ServerNotice(ref content) => content.serialize(serializer),
+            MessageEventContent::Text(ref content) => content.serialize(serializer),
+            MessageEventContent::Video(ref content) => content.serialize(serializer),
+            MessageEventContent::Custom(ref content) => content.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageEventContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+
+        let message_type_value = match value.get("msgtype") {
+            Some(value) => value.clone(),
+            None => return Err(D::Error::missing_field("msgtype")),
+        };
+
+        let message_type = match from_value::<MessageType>(message_type_value.clone()) {
+            Ok(message_type) => message_type,
+            Err(error) => {
+                if !message_type_value.is_string() {
+                    return Err(D::Error::custom(error.to_string()));
+                }
+
+                let content = match from_value::<CustomMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                return Ok(MessageEventContent::Custom(content));
+            }
+        };
+
+        match message_type {
+            MessageType::Audio => {
+                let content = match from_value::<AudioMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Audio(content))
+            }
+            MessageType::Emote => {
+                let content = match from_value::<EmoteMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Emote(content))
+            }
+            MessageType::File => {
+                let content = match from_value::<FileMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::File(content))
+            }
+            MessageType::Image => {
+                let content = match from_value::<ImageMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Image(content))
+            }
+            MessageType::Location => {
+                let content = match from_value::<LocationMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Location(content))
+            }
+            MessageType::Notice => {
+                let content = match from_value::<NoticeMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Notice(content))
+            }
+            MessageType::ServerNotice => {
+                let content = match from_value::<ServerNoticeMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::ServerNotice(content))
+            }
+            MessageType::Text => {
+                let content = match from_value::<TextMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Text(content))
+            }
+            MessageType::Video => {
+                let content = match from_value::<VideoMessageEventContent>(value) {
+                    Ok(content) => content,
+                    Err(error) => return Err(D::Error::custom(error.to_string())),
+                };
+
+                Ok(MessageEventContent::Video(content))
+            }
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::cell::Cell;
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::{EventId, UserId};
+    use serde_json::{from_str, to_string, to_value, Value};
+
+    use super::{
+        strip_reply_fallback, with_reply_fallback, Asset, AssetType, AudioInfo,
+        AudioMessageEventContent, CachingReplyFetcher, CustomMessageEventContent,
+        EmoteMessageEventContent, FileInfo, FileMessageEventContent, FormattedBody, GeoUri,
+        GeolocationInfo, ImageMessageEventContent, InReplyTo, LocationMessageEventContent,
+        Mentions, MessageEvent, MessageEventContent, MessageFormat, MessageType, NotAVoiceMessage,
+        RedactedMessageEventContent, Reference, Relation, Replacement, ReplyFetcher,
+        ServerNoticeType, TextMessageEventContent, UrlPreview, VideoInfo, VideoMessageEventContent,
+        Voice, VoiceMessageContent, MAX_BODY_LENGTH_BYTES, MAX_CONTENT_SIZE_BYTES,
+    };
+
+    #[test]
+    fn serialization() {
+        let message_event_content = MessageEventContent::Audio(AudioMessageEventContent {
+            body: "test".to_string(),
+            filename: None,
+            info: None,
+            msgtype: MessageType::Audio,
+            new_content: None,
+            relates_to: None,
+            url: "http://example.com/audio.mp3".to_string(),
+            voice: None,
+            waveform: None,
+        });
+
+        assert_eq!(
+            to_string(&message_event_content).unwrap(),
+            r#"{"body":"test","msgtype":"m.audio","url":"http://example.com/audio.mp3"}"#
+        );
+    }
+
+    #[test]
+    fn deserialization() {
+        let message_event_content = MessageEventContent::Audio(AudioMessageEventContent {
+            body: "test".to_string(),
+            filename: None,
+            info: None,
+            msgtype: MessageType::Audio,
+            new_content: None,
+            relates_to: None,
+            url: "http://example.com/audio.mp3".to_string(),
+            voice: None,
+            waveform: None,
+        });
+
+        assert_eq!(
+            from_str::<MessageEventContent>(
+                r#"{"body":"test","msgtype":"m.audio","url":"http://example.com/audio.mp3"}"#
+            ).unwrap(),
+            message_event_content
+        );
+    }
+
+    #[test]
+    fn voice_message_with_waveform() {
+        let content = MessageEventContent::Audio(AudioMessageEventContent {
+            body: "Voice message".to_string(),
+            filename: None,
+            info: None,
+            msgtype: MessageType::Audio,
+            new_content: None,
+            relates_to: None,
+            url: "mxc://example.org/ffed755USFFxlgbQYZGtryd".to_string(),
+            voice: Some(Voice {}),
+            waveform: Some(vec![0, 512, 1024]),
+        });
+        let json = r#"{"body":"Voice message","msgtype":"m.audio","org.matrix.msc3245.voice":{},"org.matrix.msc3245.waveform":[0,512,1024],"url":"mxc://example.org/ffed755USFFxlgbQYZGtryd"}"#;
+
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+        assert_eq!(from_str::<MessageEventContent>(json).unwrap(), content);
+    }
+
+    #[test]
+    fn voice_message_detection() {
+        let mut audio = from_str::<AudioMessageEventContent>(
+            r#"{"body":"Voice message","msgtype":"m.audio","org.matrix.msc3245.voice":{},"url":"mxc://example.org/ffed755USFFxlgbQYZGtryd"}"#,
+        )
+        .unwrap();
+        assert!(audio.is_voice_message());
+
+        let voice = VoiceMessageContent::try_from(audio.clone()).unwrap();
+        assert!(voice.content().is_voice_message());
+        assert_eq!(voice.into_content(), audio);
+
+        audio.voice = None;
+        assert!(!audio.is_voice_message());
+        assert_eq!(VoiceMessageContent::try_from(audio), Err(NotAVoiceMessage));
+    }
+
+    #[test]
+    fn text_constructors() {
+        assert_eq!(
+            to_value(TextMessageEventContent::plain("Hello")).unwrap(),
+            from_str::<Value>(r#"{"body":"Hello","msgtype":"m.text"}"#).unwrap()
+        );
+
+        let json = r#"{"body":"*Hello*","format":"org.matrix.custom.html","formatted_body":"<em>Hello</em>","msgtype":"m.text"}"#;
+        let content = TextMessageEventContent::html("*Hello*", "<em>Hello</em>".to_string());
+
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+        assert_eq!(
+            from_str::<MessageEventContent>(json).unwrap(),
+            MessageEventContent::Text(content)
+        );
+    }
+
+    #[test]
+    fn redact() {
+        let messages = [
+            r#"{"body":"song.mp3","msgtype":"m.audio","url":"mxc://example.org/song"}"#,
+            r#"{"body":"waves","msgtype":"m.emote"}"#,
+            r#"{"body":"a.pdf","msgtype":"m.file","url":"mxc://example.org/a"}"#,
+            r#"{"body":"cat.png","msgtype":"m.image","url":"mxc://example.org/cat"}"#,
+            r#"{"body":"Home","geo_uri":"geo:51.5,-0.1","msgtype":"m.location"}"#,
+            r#"{"body":"Down soon","msgtype":"m.notice"}"#,
+            r#"{"body":"Limit reached","msgtype":"m.server_notice","server_notice_type":"m.server_notice.usage_limit_reached"}"#,
+            r#"{"body":"Hello","msgtype":"m.text"}"#,
+            r#"{"body":"clip.mp4","msgtype":"m.video","url":"mxc://example.org/clip"}"#,
+            r#"{"body":"Hello","msgtype":"org.example.custom"}"#,
+        ];
+
+        for json in &messages {
+            let redacted = from_str::<MessageEventContent>(json).unwrap().redact();
+
+            assert_eq!(to_string(&redacted).unwrap(), "{}");
+        }
+
+        assert_eq!(
+            from_str::<RedactedMessageEventContent>("{}").unwrap(),
+            RedactedMessageEventContent {}
+        );
+    }
+
+    #[test]
+    fn redacted_bodies() {
+        assert_eq!(MessageType::Audio.redacted_body(), "[Audio redacted]");
+        assert_eq!(MessageType::Emote.redacted_body(), "[Message redacted]");
+        assert_eq!(MessageType::File.redacted_body(), "[File redacted]");
+        assert_eq!(MessageType::Image.redacted_body(), "[Image redacted]");
+        assert_eq!(MessageType::Location.redacted_body(), "[Location redacted]");
+        assert_eq!(MessageType::Notice.redacted_body(), "[Message redacted]");
+        assert_eq!(
+            MessageType::ServerNotice.redacted_body(),
+            "[Message redacted]"
+        );
+        assert_eq!(MessageType::Text.redacted_body(), "[Message redacted]");
+        assert_eq!(MessageType::Video.redacted_body(), "[Video redacted]");
+    }
+
+    #[test]
+    fn formatted_notice_is_bot_message() {
+        let json = r#"{"body":"Build failed","format":"org.matrix.custom.html","formatted_body":"Build <b>failed</b>","msgtype":"m.notice"}"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        match content {
+            MessageEventContent::Notice(ref notice) => assert_eq!(
+                notice.formatted,
+                Some(FormattedBody {
+                    format: MessageFormat::Html,
+                    body: "Build <b>failed</b>".to_string(),
+                })
+            ),
+            _ => panic!("expected a notice"),
+        }
+        assert!(content.is_bot_message());
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+
+        let text = from_str::<MessageEventContent>(r#"{"body":"Hi","msgtype":"m.text"}"#).unwrap();
+        assert!(!text.is_bot_message());
+    }
+
+    fn message_event(content: &str) -> MessageEvent {
+        from_str::<MessageEvent>(&format!(
+            r#"{{
+                "content": {},
+                "event_id": "$143273582443PhrSn:example.org",
+                "origin_server_ts": 1,
+                "sender": "@alice:example.org",
+                "type": "m.room.message"
+            }}"#,
+            content
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn reply_fallback() {
+        let single_line = message_event(r#"{"body":"Hello","msgtype":"m.text"}"#);
+        assert_eq!(
+            with_reply_fallback(&single_line, "Hi Alice!"),
+            "> <@alice:example.org> Hello\n\nHi Alice!"
+        );
+
+        let multi_line = message_event(r#"{"body":"Hello\nall of you","msgtype":"m.text"}"#);
+        assert_eq!(
+            with_reply_fallback(&multi_line, "Hi Alice!"),
+            "> <@alice:example.org> Hello\n> all of you\n\nHi Alice!"
+        );
+
+        let emote = message_event(r#"{"body":"waves","msgtype":"m.emote"}"#);
+        assert_eq!(
+            with_reply_fallback(&emote, "Hi Alice!"),
+            "> * <@alice:example.org> waves\n\nHi Alice!"
+        );
+
+        let reply =
+            message_event(r#"{"body":"> <@bob:example.org> Hi\n\nHello","msgtype":"m.text"}"#);
+        assert_eq!(
+            with_reply_fallback(&reply, "Hi Alice!"),
+            "> <@alice:example.org> Hello\n\nHi Alice!"
+        );
+    }
+
+    fn edit(sender: &str, origin_server_ts: u64, event_id: &str, body: &str) -> MessageEvent {
+        from_str::<MessageEvent>(&format!(
+            r#"{{
+                "content": {{
+                    "body": "* {body}",
+                    "m.new_content": {{"body": "{body}", "msgtype": "m.text"}},
+                    "m.relates_to": {{"event_id": "{event_id}", "rel_type": "m.replace"}},
+                    "msgtype": "m.text"
+                }},
+                "event_id": "$edit{origin_server_ts}:example.org",
+                "origin_server_ts": {origin_server_ts},
+                "sender": "{sender}",
+                "type": "m.room.message"
+            }}"#,
+            body = body,
+            event_id = event_id,
+            origin_server_ts = origin_server_ts,
+            sender = sender
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn notification_text() {
+        let text = |content: &str| super::notification_text(&message_event(content), "Alice");
+
+        assert_eq!(
+            text(
+                r#"{"body":"> <@bob:example.org> Hi\n\nHello","m.relates_to":{"m.in_reply_to":{"event_id":"$bob:example.org"}},"msgtype":"m.text"}"#
+            ),
+            "Alice: Hello"
+        );
+        assert_eq!(
+            text(
+                r#"{"body":"> <@bob:example.org> Hi","m.relates_to":{"m.in_reply_to":{"event_id":"$bob:example.org"}},"msgtype":"m.text"}"#
+            ),
+            "Alice: > <@bob:example.org> Hi"
+        );
+        assert_eq!(
+            text(r#"{"body":">be me\n>write rust","msgtype":"m.text"}"#),
+            "Alice: >be me\n>write rust"
+        );
+        assert_eq!(
+            text(r#"{"body":"Server restarts soon","msgtype":"m.notice"}"#),
+            "Alice: Server restarts soon"
+        );
+        assert_eq!(
+            text(r#"{"body":"waves","msgtype":"m.emote"}"#),
+            "* Alice waves"
+        );
+        assert_eq!(
+            text(r#"{"body":"cat.png","msgtype":"m.image","url":"mxc://example.org/cat"}"#),
+            "Alice sent an image"
+        );
+        assert_eq!(
+            text(r#"{"body":"song.mp3","msgtype":"m.audio","url":"mxc://example.org/song"}"#),
+            "Alice sent an audio clip"
+        );
+        assert_eq!(
+            text(
+                r#"{"body":"Voice message","msgtype":"m.audio","org.matrix.msc3245.voice":{},"url":"mxc://example.org/voice"}"#
+            ),
+            "Alice sent a voice message"
+        );
+        assert_eq!(
+            text(r#"{"body":"Home","geo_uri":"geo:51.5,-0.1","msgtype":"m.location"}"#),
+            "Alice shared a location"
+        );
+    }
+
+    #[test]
+    fn resolve_edits() {
+        let original = message_event(r#"{"body":"Helo","msgtype":"m.text"}"#);
+        let original_id = "$143273582443PhrSn:example.org";
+
+        match super::resolve_edits(&original, &[]) {
+            Cow::Borrowed(content) => assert_eq!(content.body(), "Helo"),
+            Cow::Owned(_) => panic!("expected the original content"),
+        }
+
+        let first = edit("@alice:example.org", 2, original_id, "Hello");
+        assert_eq!(
+            super::resolve_edits(&original, vec![&first]).body(),
+            "Hello"
+        );
+
+        let second = edit("@alice:example.org", 3, original_id, "Hello!");
+        let third = edit("@alice:example.org", 4, original_id, "Hello world!");
+        let by_someone_else = edit("@mallory:example.org", 5, original_id, "Goodbye");
+        let of_another_message = edit("@alice:example.org", 6, "$other:example.org", "Other");
+        let edits = vec![second, third, first, by_someone_else, of_another_message];
+
+        let resolved = super::resolve_edits(&original, &edits);
+        assert_eq!(resolved.body(), "Hello world!");
+        assert_eq!(resolved.relates_to(), None);
+    }
+
+    struct CountingFetcher {
+        event: MessageEvent,
+        fetches: Cell<usize>,
+    }
+
+    impl ReplyFetcher for CountingFetcher {
+        fn fetch(&self, event_id: &EventId) -> Option<MessageEvent> {
+            self.fetches.set(self.fetches.get() + 1);
+
+            if *event_id == self.event.event_id {
+                Some(self.event.clone())
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn reply_preview() {
+        let fetcher = CachingReplyFetcher::new(CountingFetcher {
+            event: message_event(
+                r#"{"body":"> <@bob:example.org> Hi\n\nHello","msgtype":"m.text"}"#,
+            ),
+            fetches: Cell::new(0),
+        });
+        let reply = from_str::<MessageEventContent>(
+            r#"{"body":"> <@alice:example.org> Hello\n\nHi!","msgtype":"m.text","m.relates_to":{"m.in_reply_to":{"event_id":"$143273582443PhrSn:example.org"}}}"#,
+        )
+        .unwrap();
+        let reply_to_missing = from_str::<MessageEventContent>(
+            r#"{"body":"Hi!","msgtype":"m.text","m.relates_to":{"m.in_reply_to":{"event_id":"$missing:example.org"}}}"#,
+        )
+        .unwrap();
+        let not_a_reply =
+            from_str::<MessageEventContent>(r#"{"body":"Hi!","msgtype":"m.text"}"#).unwrap();
+
+        assert_eq!(reply.reply_preview(&fetcher), Some("Hello".to_string()));
+        assert_eq!(reply.reply_preview(&fetcher), Some("Hello".to_string()));
+        assert_eq!(fetcher.fetcher.fetches.get(), 1);
+
+        assert_eq!(reply_to_missing.reply_preview(&fetcher), None);
+        assert_eq!(reply_to_missing.reply_preview(&fetcher), None);
+        assert_eq!(fetcher.fetcher.fetches.get(), 3);
+
+        assert_eq!(not_a_reply.reply_preview(&fetcher), None);
+        assert_eq!(fetcher.fetcher.fetches.get(), 3);
+    }
+
+    #[test]
+    fn strip_reply_fallbacks() {
+        assert_eq!(
+            strip_reply_fallback("> <@alice:example.org> Hello\n\nHi Alice!"),
+            "Hi Alice!"
+        );
+        assert_eq!(
+            strip_reply_fallback("> <@alice:example.org> Hello\n> all of you\n\nHi Alice!\nBye"),
+            "Hi Alice!\nBye"
+        );
+        assert_eq!(strip_reply_fallback("Hi Alice!"), "Hi Alice!");
+        assert_eq!(strip_reply_fallback("\nHi Alice!"), "\nHi Alice!");
+    }
+
+    #[test]
+    fn server_notice_types() {
+        let server_notice_type = |server_notice_type: &str| {
+            let json = format!(
+                r#"{{"body":"Notice","msgtype":"m.server_notice","server_notice_type":"{}"}}"#,
+                server_notice_type
+            );
+
+            match from_str::<MessageEventContent>(&json).unwrap() {
+                MessageEventContent::ServerNotice(content) => content.server_notice_type,
+                _ => panic!("expected a server notice"),
+            }
+        };
+
+        assert_eq!(
+            server_notice_type("m.server_notice.usage_limit_reached"),
+            ServerNoticeType::UsageLimitReached
+        );
+        assert_eq!(
+            server_notice_type("m.threshold.exceeded"),
+            ServerNoticeType::ThresholdExceeded
+        );
+        assert_eq!(
+            server_notice_type("m.connection.timeout"),
+            ServerNoticeType::ConnectionTimeout
+        );
+        assert_eq!(
+            server_notice_type("m.daily_messages_exceeded"),
+            ServerNoticeType::DailyMessagesExceeded
+        );
+        assert_eq!(
+            server_notice_type("org.example.maintenance"),
+            ServerNoticeType::Custom("org.example.maintenance".to_string())
+        );
+    }
+
+    #[test]
+    fn server_notice_round_trip() {
+        let json = r#"{"admin_contact":"mailto:admin@example.org","body":"This server has exceeded its monthly active user limit.","limit_type":"monthly_active_user","msgtype":"m.server_notice","server_notice_type":"m.server_notice.usage_limit_reached"}"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        assert!(content.is_bot_message());
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn body_length() {
+        let text = |body: &str| {
+            from_str::<MessageEventContent>(&format!(r#"{{"body":"{}","msgtype":"m.text"}}"#, body))
+                .unwrap()
+        };
+
+        let short = text("Hello world");
+        assert!(short.body_is_within_limit());
+        assert_eq!(short.truncated_body(11), "Hello world");
+        assert_eq!(short.truncated_body(8), "Hello...");
+
+        let multi_byte = text("Grüße!");
+        assert_eq!(multi_byte.truncated_body(6), "Gr...");
+        assert_eq!(multi_byte.truncated_body(7), "Grü...");
+        assert_eq!(multi_byte.truncated_body(3), "...");
+        assert_eq!(multi_byte.truncated_body(2), "Gr");
+        assert_eq!(multi_byte.truncated_body(0), "");
+
+        for max_bytes in 0..12 {
+            assert!(multi_byte.truncated_body(max_bytes).len() <= max_bytes);
+        }
+
+        let long = text(&"a".repeat(MAX_BODY_LENGTH_BYTES + 1));
+        assert!(!long.body_is_within_limit());
+        assert_eq!(
+            long.truncated_body(MAX_BODY_LENGTH_BYTES).len(),
+            MAX_BODY_LENGTH_BYTES
+        );
+    }
+
+    #[test]
+    fn deserialization_failure() {
+        assert!(
+            from_str::<MessageEventContent>(
+                r#"{"body":"test","msgtype":"m.location","url":"http://example.com/audio.mp3"}"#
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn content_size_of_attachments() {
+        let audio = MessageEventContent::Audio(AudioMessageEventContent {
+            body: "test".to_string(),
+            filename: None,
+            info: Some(AudioInfo {
+                duration: None,
+                mimetype: None,
+                size: Some(2048),
+            }),
+            msgtype: MessageType::Audio,
+            new_content: None,
+            relates_to: None,
+            url: "http://example.com/audio.mp3".to_string(),
+            voice: None,
+            waveform: None,
+        });
+        let file = MessageEventContent::File(FileMessageEventContent {
+            body: "test".to_string(),
+            filename: Some("test.pdf".to_string()),
+            info: Some(FileInfo {
+                mimetype: "application/pdf".to_string(),
+                size: 4096,
+                thumbnail_file: None,
+                thumbnail_info: None,
+                thumbnail_url: None,
+            }),
+            msgtype: MessageType::File,
+            new_content: None,
+            relates_to: None,
+            url: "http://example.com/test.pdf".to_string(),
+        });
+
+        assert_eq!(audio.content_size_bytes(), Some(2048));
+        assert_eq!(file.content_size_bytes(), Some(4096));
+        assert!(file.exceeds_size_limit(2048));
+        assert!(!file.exceeds_size_limit(4096));
+    }
+
+    #[test]
+    fn content_size_without_attachment() {
+        let text = MessageEventContent::Text(TextMessageEventContent {
+            body: "test".to_string(),
+            formatted: None,
+            mentions: None,
+            #[cfg(feature = "msc1767")]
+            m_text: None,
+            msgtype: MessageType::Text,
+            new_content: None,
+            relates_to: None,
+            url_previews: None,
+        });
+        let audio = MessageEventContent::Audio(AudioMessageEventContent {
+            body: "test".to_string(),
+            filename: None,
+            info: None,
+            msgtype: MessageType::Audio,
+            new_content: None,
+            relates_to: None,
+            url: "http://example.com/audio.mp3".to_string(),
+            voice: None,
+            waveform: None,
+        });
+
+        assert_eq!(text.content_size_bytes(), None);
+        assert_eq!(audio.content_size_bytes(), None);
+        assert!(!text.exceeds_size_limit(0));
+    }
+
+    #[test]
+    fn geo_uri_without_altitude() {
+        let geo_uri = "geo:51.5008,0.1247".parse::<GeoUri>().unwrap();
+
+        assert_eq!(geo_uri.latitude(), 51.5008);
+        assert_eq!(geo_uri.longitude(), 0.1247);
+        assert_eq!(geo_uri.altitude(), None);
+    }
+
+    #[test]
+    fn geo_uri_with_altitude() {
+        let geo_uri = "geo:-33.8688,151.2093,58".parse::<GeoUri>().unwrap();
+
+        assert_eq!(geo_uri.latitude(), -33.8688);
+        assert_eq!(geo_uri.longitude(), 151.2093);
+        assert_eq!(geo_uri.altitude(), Some(58.0));
+    }
+
+    #[test]
+    fn geo_uri_with_parameters() {
+        let geo_uri = "geo:51.5008,0.1247;u=35".parse::<GeoUri>().unwrap();
+
+        assert_eq!(geo_uri.latitude(), 51.5008);
+        assert_eq!(geo_uri.longitude(), 0.1247);
+        assert_eq!(geo_uri.to_string(), "geo:51.5008,0.1247;u=35");
+    }
+
+    #[test]
+    fn malformed_geo_uris() {
+        assert!("51.5008,0.1247".parse::<GeoUri>().is_err());
+        assert!("geo:51.5008".parse::<GeoUri>().is_err());
+        assert!("geo:north,west".parse::<GeoUri>().is_err());
+        assert!("geo:91,0".parse::<GeoUri>().is_err());
+        assert!("geo:1,2,3,4".parse::<GeoUri>().is_err());
+    }
+
+    #[test]
+    fn location_message_with_geo_uri() {
+        let json = r#"{"body":"Big Ben","geo_uri":"geo:51.5008,0.1247","msgtype":"m.location"}"#;
+
+        match from_str::<MessageEventContent>(json).unwrap() {
+            MessageEventContent::Location(LocationMessageEventContent { ref geo_uri, .. }) => {
+                assert_eq!(geo_uri.parse::<GeoUri>().unwrap().latitude(), 51.5008);
+            }
+            _ => panic!("expected a location message"),
+        }
+
+        assert!(from_str::<MessageEventContent>(
+            r#"{"body":"Big Ben","geo_uri":"Big Ben","msgtype":"m.location"}"#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn location_coordinates() {
+        let coordinates = |geo_uri: &str| match from_str::<MessageEventContent>(&format!(
+            r#"{{"body":"here","geo_uri":"{}","msgtype":"m.location"}}"#,
+            geo_uri
+        ))
+        .unwrap()
+        {
+            MessageEventContent::Location(content) => content.coordinates(),
+            _ => panic!("expected a location message"),
+        };
+
+        assert_eq!(coordinates("geo:90,0"), Some((90.0, 0.0, None)));
+        assert_eq!(coordinates("geo:-90,0"), Some((-90.0, 0.0, None)));
+        assert_eq!(coordinates("geo:51.4778,0"), Some((51.4778, 0.0, None)));
+        assert_eq!(
+            coordinates("geo:27.9881,86.925,8848"),
+            Some((27.9881, 86.925, Some(8848.0)))
+        );
+        assert_eq!(
+            coordinates("geo:51.5008,0.1247,10;crs=wgs84;u=35"),
+            Some((51.5008, 0.1247, Some(10.0)))
+        );
+        assert_eq!(coordinates("geo:51.5,-0.1,abc"), None);
+        assert_eq!(coordinates("Big Ben"), None);
+    }
+
+    #[test]
+    fn live_location_message() {
+        let json = r#"{"body":"Alice's location","geo_uri":"geo:51.5008,0.1247","location_info":{"asset":{"type":"m.self"},"live":true,"ts":1636829458432,"zoom_level":15.0},"msgtype":"m.location"}"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        match content {
+            MessageEventContent::Location(LocationMessageEventContent {
+                ref location_info,
+                ..
+            }) => assert_eq!(
+                *location_info,
+                Some(GeolocationInfo {
+                    asset: Asset {
+                        asset_type: AssetType::Self_,
+                    },
+                    live: Some(true),
+                    ts: Some(1_636_829_458_432),
+                    zoom_level: Some(15.0),
+                })
+            ),
+            _ => panic!("expected a location message"),
+        }
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn deserialize_legacy_reply() {
+        let json = r#"{
+            "body": "> <@alice:example.org> Hello\n\nHi Alice!",
+            "format": "org.matrix.custom.html",
+            "formatted_body": "<mx-reply><blockquote><a href=\"https://matrix.to/#/!room:example.org/$143273582443PhrSn:example.org\">In reply to</a> <a href=\"https://matrix.to/#/@alice:example.org\">@alice:example.org</a><br>Hello</blockquote></mx-reply>Hi Alice!",
+            "m.relates_to": {
+                "m.in_reply_to": {
+                    "event_id": "$143273582443PhrSn:example.org"
+                }
+            },
+            "msgtype": "m.text"
+        }"#;
+
+        match from_str::<MessageEventContent>(json).unwrap() {
+            MessageEventContent::Text(content) => {
+                assert_eq!(
+                    content.relates_to,
+                    Some(Relation::Reply(InReplyTo {
+                        event_id: EventId::try_from("$143273582443PhrSn:example.org").unwrap(),
+                    }))
+                );
+            }
+            _ => panic!("expected a text message"),
+        }
+    }
+
+    #[test]
+    fn serialize_legacy_reply() {
+        let content = MessageEventContent::Text(TextMessageEventContent {
+            body: "Hi Alice!".to_string(),
+            formatted: None,
+            mentions: None,
+            #[cfg(feature = "msc1767")]
+            m_text: None,
+            msgtype: MessageType::Text,
+            new_content: None,
+            relates_to: Some(Relation::Reply(InReplyTo {
+                event_id: EventId::try_from("$143273582443PhrSn:example.org").unwrap(),
+            })),
+            url_previews: None,
+        });
+
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r#"{"body":"Hi Alice!","msgtype":"m.text","m.relates_to":{"m.in_reply_to":{"event_id":"$143273582443PhrSn:example.org"}}}"#
+        );
+    }
+
+    #[test]
+    fn reference_relation_round_trips() {
+        let json = r#"{"event_id":"$143273582443PhrSn:example.org","rel_type":"m.reference"}"#;
+        let relation = from_str::<Relation>(json).unwrap();
+
+        assert_eq!(
+            relation,
+            Relation::Reference(Reference {
+                event_id: EventId::try_from("$143273582443PhrSn:example.org").unwrap(),
+            })
+        );
+        assert_eq!(
+            to_value(&relation).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn media_thumbnails() {
+        let video =
+            from_str::<VideoInfo>(r#"{"duration":1000,"thumbnail_url":"mxc://example.org/thumb"}"#)
+                .unwrap();
+        assert!(video.has_thumbnail());
+        assert_eq!(video.thumbnail_mxc_uri().unwrap().media_id(), "thumb");
+
+        let video = from_str::<VideoInfo>(r#"{"duration":1000}"#).unwrap();
+        assert!(!video.has_thumbnail());
+        assert_eq!(video.thumbnail_mxc_uri(), None);
+
+        let file = from_str::<FileInfo>(
+            r#"{"mimetype":"application/pdf","size":1024,"thumbnail_url":"mxc://example.org/thumb"}"#,
+        )
+        .unwrap();
+        assert!(file.has_thumbnail());
+
+        let file = from_str::<FileInfo>(
+            r#"{"mimetype":"application/pdf","size":1024,"thumbnail_url":"http://example.org/thumb"}"#,
+        )
+        .unwrap();
+        assert!(!file.has_thumbnail());
+    }
+
+    #[test]
+    fn thumbnail_sources() {
+        let video = |info: &str| {
+            from_str::<VideoMessageEventContent>(&format!(
+                r#"{{"body":"video","info":{},"msgtype":"m.video","url":"mxc://example.org/video"}}"#,
+                info
+            ))
+            .unwrap()
+        };
+
+        let plain = video(r#"{"thumbnail_url":"mxc://example.org/thumb"}"#)
+            .thumbnail_source()
+            .unwrap();
+        assert_eq!(plain.url.unwrap().media_id(), "thumb");
+        assert_eq!(plain.file, None);
+
+        let encrypted = video(
+            r#"{"thumbnail_file":{
+                "hashes":{"sha256":"fdSLu/YkRx3Wyh3KQabP3rd6+SFiKg5lsJZQHtkSAYA"},
+                "iv":"X85+XgHN+HEAAAAAAAAAAA",
+                "key":{"alg":"A256CTR","ext":true,"k":"qcHVMSgYg-71CauWBezXI5qkaRb0LuIy","key_ops":["encrypt","decrypt"],"kty":"oct"},
+                "url":"mxc://example.org/encrypted",
+                "v":"v2"
+            }}"#,
+        )
+        .thumbnail_source()
+        .unwrap();
+        assert_eq!(encrypted.url, None);
+        assert_eq!(encrypted.file.unwrap().url, "mxc://example.org/encrypted");
+
+        assert_eq!(video("{}").thumbnail_source(), None);
+        assert_eq!(
+            video(r#"{"thumbnail_url":"http://example.org/thumb"}"#).thumbnail_source(),
+            None
+        );
+        assert_eq!(
+            from_str::<ImageMessageEventContent>(
+                r#"{"body":"image","msgtype":"m.image","url":"mxc://example.org/image"}"#
+            )
+            .unwrap()
+            .thumbnail_source(),
+            None
+        );
+    }
 
     #[test]
-    fn serialization() {
-        let message_event_content = MessageEventContent::Audio(AudioMessageEventContent {
-            body: "test".to_string(),
-            info: None,
-            msgtype: MessageType::Audio,
-            url: "http://example.com/audio.mp3".to_string(),
+    fn message_type_discriminants() {
+        let message_types = [
+            MessageType::Audio,
+            MessageType::Emote,
+            MessageType::File,
+            MessageType::Image,
+            MessageType::Location,
+            MessageType::Notice,
+            MessageType::ServerNotice,
+            MessageType::Text,
+            MessageType::Video,
+        ];
+
+        for message_type in &message_types {
+            assert_eq!(message_type.msgtype(), message_type.to_string());
+        }
+
+        assert!(MessageType::Text.is_text());
+        assert!(!MessageType::Notice.is_text());
+        assert!(MessageType::Image.is_image());
+        assert!(!MessageType::Video.is_image());
+        assert!(MessageType::File.is_file());
+        assert!(!MessageType::Image.is_file());
+
+        let media = message_types
+            .iter()
+            .filter(|message_type| message_type.is_media())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            media,
+            vec![
+                &MessageType::Audio,
+                &MessageType::File,
+                &MessageType::Image,
+                &MessageType::Video,
+            ]
+        );
+    }
+
+    #[test]
+    fn thread_reply_fallbacks() {
+        let relation = |json: &str| {
+            from_str::<MessageEventContent>(json)
+                .unwrap()
+                .relates_to()
+                .cloned()
+                .unwrap()
+        };
+
+        let fallback = relation(
+            r#"{
+                "body": "Sounds good",
+                "m.relates_to": {
+                    "event_id": "$root:matrix.org",
+                    "is_falling_back": true,
+                    "m.in_reply_to": {"event_id": "$latest:matrix.org"},
+                    "rel_type": "m.thread"
+                },
+                "msgtype": "m.text"
+            }"#,
+        );
+        assert_eq!(
+            fallback.thread_reply_to_fallback(),
+            Some(&InReplyTo {
+                event_id: EventId::try_from("$latest:matrix.org").unwrap(),
+            })
+        );
+        assert!(fallback.is_thread_reply_fallback());
+
+        let reply_in_thread = relation(
+            r#"{
+                "body": "> <@alice:matrix.org> What about Tuesday?\n\nSounds good",
+                "m.relates_to": {
+                    "event_id": "$root:matrix.org",
+                    "is_falling_back": false,
+                    "m.in_reply_to": {"event_id": "$question:matrix.org"},
+                    "rel_type": "m.thread"
+                },
+                "msgtype": "m.text"
+            }"#,
+        );
+        assert_eq!(
+            reply_in_thread.thread_reply_to_fallback(),
+            Some(&InReplyTo {
+                event_id: EventId::try_from("$question:matrix.org").unwrap(),
+            })
+        );
+        assert!(!reply_in_thread.is_thread_reply_fallback());
+
+        let reply = relation(
+            r#"{"body":"Hi","m.relates_to":{"m.in_reply_to":{"event_id":"$latest:matrix.org"}},"msgtype":"m.text"}"#,
+        );
+        assert_eq!(reply.thread_reply_to_fallback(), None);
+        assert!(!reply.is_thread_reply_fallback());
+    }
+
+    #[test]
+    fn thread_root_id() {
+        let json = r#"{
+            "body": "Ruma is great",
+            "m.relates_to": {
+                "event_id": "$thread_root:example.org",
+                "is_falling_back": true,
+                "m.in_reply_to": {
+                    "event_id": "$latest:example.org"
+                },
+                "rel_type": "m.thread"
+            },
+            "msgtype": "m.text"
+        }"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        assert_eq!(
+            content.thread_root_id(),
+            Some(&EventId::try_from("$thread_root:example.org").unwrap())
+        );
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+
+        let reply = from_str::<MessageEventContent>(
+            r#"{"body":"Hi","m.relates_to":{"m.in_reply_to":{"event_id":"$latest:example.org"}},"msgtype":"m.text"}"#,
+        )
+        .unwrap();
+        assert_eq!(reply.thread_root_id(), None);
+    }
+
+    #[test]
+    fn count_thread_replies() {
+        let root = message_event(r#"{"body":"Root","msgtype":"m.text"}"#);
+        let in_thread = |thread_root: &str| {
+            message_event(&format!(
+                r#"{{"body":"Reply","m.relates_to":{{"event_id":"{}","rel_type":"m.thread"}},"msgtype":"m.text"}}"#,
+                thread_root
+            ))
+        };
+        let reply = message_event(
+            r#"{"body":"Hi","m.relates_to":{"m.in_reply_to":{"event_id":"$143273582443PhrSn:example.org"}},"msgtype":"m.text"}"#,
+        );
+
+        assert_eq!(super::count_thread_replies(&root.event_id, &[]), 0);
+        assert_eq!(
+            super::count_thread_replies(&root.event_id, &[root.clone(), reply.clone()]),
+            0
+        );
+
+        let events = vec![
+            root.clone(),
+            in_thread("$143273582443PhrSn:example.org"),
+            reply,
+            in_thread("$other:example.org"),
+            in_thread("$143273582443PhrSn:example.org"),
+        ];
+        assert_eq!(super::count_thread_replies(&root.event_id, &events), 2);
+        assert_eq!(
+            super::count_thread_replies(&EventId::try_from("$other:example.org").unwrap(), &events),
+            1
+        );
+    }
+
+    #[test]
+    fn unknown_relation_is_kept() {
+        let json =
+            r#"{"event_id":"$143273582443PhrSn:example.org","rel_type":"org.example.custom"}"#;
+
+        match from_str::<Relation>(json).unwrap() {
+            Relation::Custom(value) => assert_eq!(value, from_str::<Value>(json).unwrap()),
+            _ => panic!("expected a custom relation"),
+        }
+    }
+
+    #[test]
+    fn transaction_id_from_unsigned() {
+        let json = r#"{
+            "content": {"body": "Hello", "msgtype": "m.text"},
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message",
+            "unsigned": {"age": 1234, "transaction_id": "m1476648745605.19"}
+        }"#;
+
+        let event = from_str::<MessageEvent>(json).unwrap();
+
+        assert_eq!(event.transaction_id(), Some("m1476648745605.19"));
+    }
+
+    #[test]
+    fn transaction_id_missing() {
+        let json = r#"{
+            "content": {"body": "Hello", "msgtype": "m.text"},
+            "event_id": "$h29iv0s8:example.com",
+            "origin_server_ts": 1,
+            "sender": "@carl:example.com",
+            "type": "m.room.message",
+            "unsigned": {"age": 1234}
+        }"#;
+
+        let event = from_str::<MessageEvent>(json).unwrap();
+
+        assert_eq!(event.transaction_id(), None);
+    }
+
+    #[test]
+    fn unknown_msgtype_deserializes_as_custom() {
+        let content = from_str::<MessageEventContent>(
+            r#"{"body":"Hello","custom_field":[1,2],"msgtype":"org.example.custom"}"#,
+        )
+        .unwrap();
+
+        match content {
+            MessageEventContent::Custom(ref content) => {
+                assert_eq!(content.body, "Hello");
+                assert_eq!(content.msgtype, "org.example.custom");
+                assert_eq!(
+                    content.extra_fields,
+                    from_str::<Value>(r#"{"custom_field":[1,2]}"#).unwrap()
+                );
+            }
+            _ => panic!("expected a custom message"),
+        }
+    }
+
+    #[test]
+    fn custom_message_round_trips() {
+        let json = r#"{"body":"Hello","custom_field":[1,2],"msgtype":"org.example.custom"}"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_message_as_typed_content() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Dice {
+            body: String,
+            sides: u8,
+            value: u8,
+        }
+
+        let json = r#"{"body":"Rolled a 4","msgtype":"org.example.dice","sides":6,"value":4}"#;
+        let content = match from_str::<MessageEventContent>(json).unwrap() {
+            MessageEventContent::Custom(content) => content,
+            _ => panic!("expected a custom message"),
+        };
+
+        assert_eq!(content.msgtype, "org.example.dice");
+        assert_eq!(
+            content.try_as::<Dice>().unwrap(),
+            Dice {
+                body: "Rolled a 4".to_string(),
+                sides: 6,
+                value: 4,
+            }
+        );
+        assert!(content.try_as::<TextMessageEventContent>().is_err());
+        assert_eq!(
+            to_value(MessageEventContent::Custom(content)).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_message_serialization() {
+        let content = MessageEventContent::Custom(CustomMessageEventContent {
+            body: "Hello".to_string(),
+            msgtype: "org.example.custom".to_string(),
+            extra_fields: from_str::<Value>(r#"{"custom_field":true}"#).unwrap(),
         });
 
         assert_eq!(
-            to_string(&message_event_content).unwrap(),
-            r#"{"body":"test","msgtype":"m.audio","url":"http://example.com/audio.mp3"}"#
+            to_value(&content).unwrap(),
+            from_str::<Value>(
+                r#"{"body":"Hello","custom_field":true,"msgtype":"org.example.custom"}"#
+            )
+            .unwrap()
         );
     }
 
     #[test]
-    fn deserialization() {
-        let message_event_content = MessageEventContent::Audio(AudioMessageEventContent {
-            body: "test".to_string(),
-            info: None,
-            msgtype: MessageType::Audio,
-            url: "http://example.com/audio.mp3".to_string(),
+    fn non_string_msgtype_is_rejected() {
+        assert!(from_str::<MessageEventContent>(r#"{"body":"Hello","msgtype":5}"#).is_err());
+    }
+
+    #[test]
+    fn custom_message_requires_body() {
+        assert!(from_str::<MessageEventContent>(r#"{"msgtype":"org.example.custom"}"#).is_err());
+    }
+
+    #[test]
+    fn serialized_size() {
+        let content = MessageEventContent::Text(TextMessageEventContent {
+            body: "Hello".to_string(),
+            formatted: None,
+            mentions: None,
+            #[cfg(feature = "msc1767")]
+            m_text: None,
+            msgtype: MessageType::Text,
+            new_content: None,
+            relates_to: None,
+            url_previews: None,
         });
 
         assert_eq!(
-            from_str::<MessageEventContent>(
-                r#"{"body":"test","msgtype":"m.audio","url":"http://example.com/audio.mp3"}"#
-            ).unwrap(),
-            message_event_content
+            content.serialized_size_bytes().unwrap(),
+            to_string(&content).unwrap().len()
         );
+        assert!(!content.exceeds_event_size_limit().unwrap());
     }
 
     #[test]
-    fn deserialization_failure() {
-        assert!(
-            from_str::<MessageEventContent>(
-                r#"{"body":"test","msgtype":"m.location","url":"http://example.com/audio.mp3"}"#
-            ).is_err()
+    fn oversized_content_exceeds_event_size_limit() {
+        let content = MessageEventContent::Text(TextMessageEventContent {
+            body: "a".repeat(MAX_CONTENT_SIZE_BYTES),
+            formatted: None,
+            mentions: None,
+            #[cfg(feature = "msc1767")]
+            m_text: None,
+            msgtype: MessageType::Text,
+            new_content: None,
+            relates_to: None,
+            url_previews: None,
+        });
+
+        assert!(content.exceeds_event_size_limit().unwrap());
+    }
+
+    #[test]
+    fn emote_round_trips() {
+        let json = r#"{"body":"waves","format":"org.matrix.custom.html","formatted_body":"<em>waves</em>","msgtype":"m.emote"}"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        match content {
+            MessageEventContent::Emote(ref content) => {
+                assert_eq!(content.body, "waves");
+                assert_eq!(
+                    content.formatted,
+                    Some(FormattedBody {
+                        format: MessageFormat::Html,
+                        body: "<em>waves</em>".to_string(),
+                    })
+                );
+            }
+            _ => panic!("expected an emote message"),
+        }
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn emote_serializes_as_emote_msgtype() {
+        let content = MessageEventContent::Emote(EmoteMessageEventContent {
+            body: "waves".to_string(),
+            formatted: None,
+            mentions: None,
+            msgtype: MessageType::Emote,
+            new_content: None,
+            relates_to: None,
+            url_previews: None,
+        });
+
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r#"{"body":"waves","msgtype":"m.emote"}"#
+        );
+        assert_eq!(
+            from_str::<MessageEventContent>(r#"{"body":"waves","msgtype":"m.emote"}"#).unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn replacement_relation() {
+        let json = r#"{"event_id":"$143273582443PhrSn:example.org","rel_type":"m.replace"}"#;
+        let relation = from_str::<Relation>(json).unwrap();
+
+        assert_eq!(
+            relation,
+            Relation::Replacement(Replacement {
+                event_id: EventId::try_from("$143273582443PhrSn:example.org").unwrap(),
+            })
+        );
+        assert_eq!(
+            to_value(&relation).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn apply_edit() {
+        let original = from_str::<MessageEventContent>(
+            r#"{
+                "body": "Helo",
+                "msgtype": "m.text",
+                "m.relates_to": {"m.in_reply_to": {"event_id": "$original:example.org"}}
+            }"#,
+        )
+        .unwrap();
+        let edit = from_str::<MessageEventContent>(
+            r#"{
+                "body": " * Hello",
+                "msgtype": "m.text",
+                "m.new_content": {"body": "Hello", "msgtype": "m.text"},
+                "m.relates_to": {"event_id": "$edited:example.org", "rel_type": "m.replace"}
+            }"#,
+        )
+        .unwrap();
+
+        assert!(edit.is_edit());
+        assert!(!original.is_edit());
+        assert_eq!(
+            edit.edited_event_id(),
+            Some(&EventId::try_from("$edited:example.org").unwrap())
+        );
+
+        match edit.apply_edit(&original).unwrap() {
+            MessageEventContent::Text(content) => {
+                assert_eq!(content.body, "Hello");
+                assert_eq!(content.new_content, None);
+                assert_eq!(content.relates_to, original.relates_to().cloned());
+            }
+            _ => panic!("expected a text message"),
+        }
+        assert_eq!(original.apply_edit(&edit), None);
+    }
+
+    #[test]
+    fn edit_without_new_content_cannot_be_applied() {
+        let original =
+            from_str::<MessageEventContent>(r#"{"body":"Helo","msgtype":"m.text"}"#).unwrap();
+        let edit = from_str::<MessageEventContent>(
+            r#"{
+                "body": " * Hello",
+                "msgtype": "m.text",
+                "m.relates_to": {"event_id": "$edited:example.org", "rel_type": "m.replace"}
+            }"#,
+        )
+        .unwrap();
+
+        assert!(edit.is_edit());
+        assert_eq!(edit.apply_edit(&original), None);
+    }
+
+    #[test]
+    fn attachment_display_name() {
+        let content = from_str::<MessageEventContent>(
+            r#"{"body":"My document","filename":"report.pdf","msgtype":"m.file","url":"mxc://example.org/abc"}"#,
+        )
+        .unwrap();
+        match content {
+            MessageEventContent::File(ref content) => {
+                assert_eq!(content.filename, Some("report.pdf".to_string()));
+                assert_eq!(content.display_name(), "report.pdf");
+            }
+            _ => panic!("expected a file message"),
+        }
+
+        let content = from_str::<MessageEventContent>(
+            r#"{"body":"cat.png","msgtype":"m.image","url":"mxc://example.org/abc"}"#,
+        )
+        .unwrap();
+        match content {
+            MessageEventContent::Image(ref content) => {
+                assert_eq!(content.filename, None);
+                assert_eq!(content.display_name(), "cat.png");
+            }
+            _ => panic!("expected an image message"),
+        }
+    }
+
+    #[test]
+    fn url_previews() {
+        let json = r#"{"body":"https://matrix.org","msgtype":"m.text","m.url_previews":[{"matched_url":"https://matrix.org","og:image":"mxc://matrix.org/abc","og:image:height":400,"og:image:width":800,"og:title":"Matrix"}]}"#;
+        let content = from_str::<MessageEventContent>(json).unwrap();
+
+        match content {
+            MessageEventContent::Text(ref content) => assert_eq!(
+                content.url_previews,
+                Some(vec![UrlPreview {
+                    url: "https://matrix.org".to_string(),
+                    og_title: Some("Matrix".to_string()),
+                    og_description: None,
+                    og_image: Some("mxc://matrix.org/abc".parse().unwrap()),
+                    og_image_width: Some(800),
+                    og_image_height: Some(400),
+                }])
+            ),
+            _ => panic!("expected a text message"),
+        }
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+
+        assert_eq!(
+            from_str::<UrlPreview>(
+                r#"{"matched_url":"https://matrix.org","og:image":"https://matrix.org/logo.png"}"#
+            )
+            .unwrap()
+            .og_image,
+            None
+        );
+    }
+
+    #[test]
+    fn fallback_text() {
+        let fallback_text = |json: &str| {
+            from_str::<MessageEventContent>(json)
+                .unwrap()
+                .fallback_text()
+        };
+
+        assert_eq!(
+            fallback_text(r#"{"body":"hello","msgtype":"m.text"}"#),
+            "hello"
+        );
+        assert_eq!(
+            fallback_text(r#"{"body":"beep","msgtype":"m.notice"}"#),
+            "beep"
+        );
+        assert_eq!(
+            fallback_text(r#"{"body":"waves","msgtype":"m.emote"}"#),
+            "waves"
+        );
+        assert_eq!(
+            fallback_text(
+                r#"{"body":"cat.png","msgtype":"m.image","url":"mxc://example.org/cat"}"#
+            ),
+            "[Image] cat.png"
+        );
+        assert_eq!(
+            fallback_text(
+                r#"{"body":"My report","filename":"report.pdf","msgtype":"m.file","url":"mxc://example.org/report"}"#
+            ),
+            "[File] report.pdf"
+        );
+        assert_eq!(
+            fallback_text(
+                r#"{"body":"report.pdf","msgtype":"m.file","url":"mxc://example.org/report"}"#
+            ),
+            "[File] report.pdf"
+        );
+        assert_eq!(
+            fallback_text(
+                r#"{"body":"song.ogg","msgtype":"m.audio","url":"mxc://example.org/song"}"#
+            ),
+            "[Audio] song.ogg"
+        );
+        assert_eq!(
+            fallback_text(
+                r#"{"body":"clip.mp4","msgtype":"m.video","url":"mxc://example.org/clip"}"#
+            ),
+            "[Video] clip.mp4"
+        );
+        assert_eq!(
+            fallback_text(
+                r#"{"body":"Big Ben","geo_uri":"geo:51.5008,0.1247","msgtype":"m.location"}"#
+            ),
+            "[Location] geo:51.5008,0.1247"
+        );
+    }
+
+    #[test]
+    fn mentions() {
+        let alice = UserId::try_from("@alice:example.org").unwrap();
+        let bob = UserId::try_from("@bob:example.org").unwrap();
+
+        let users = from_str::<MessageEventContent>(
+            r#"{"body":"alice: hi","msgtype":"m.text","m.mentions":{"user_ids":["@alice:example.org"]}}"#,
+        )
+        .unwrap();
+        assert!(users.mentions_user(&alice));
+        assert!(!users.mentions_user(&bob));
+        assert!(!users.mentions_room());
+
+        let room = from_str::<MessageEventContent>(
+            r#"{"body":"@room: hi","msgtype":"m.notice","m.mentions":{"room":true}}"#,
+        )
+        .unwrap();
+        assert!(room.mentions_room());
+        assert!(!room.mentions_user(&alice));
+        assert_eq!(
+            room.mentions(),
+            Some(&Mentions {
+                room: true,
+                user_ids: Vec::new(),
+            })
+        );
+
+        let none = from_str::<MessageEventContent>(r#"{"body":"hi","msgtype":"m.text"}"#).unwrap();
+        assert_eq!(none.mentions(), None);
+        assert!(!none.mentions_room());
+        assert!(!none.mentions_user(&alice));
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn normalize_body_nfc() {
+        let mut content = from_str::<MessageEventContent>(
+            "{\"body\":\"Cafe\u{301}\",\"format\":\"org.matrix.custom.html\",\"formatted_body\":\"<b>Cafe\u{301}</b>\",\"msgtype\":\"m.notice\"}",
+        )
+        .unwrap();
+
+        match content {
+            MessageEventContent::Notice(ref mut content) => {
+                content.normalize_body_nfc();
+
+                assert_eq!(content.body, "Caf\u{e9}");
+                assert_eq!(content.formatted.as_ref().unwrap().body, "<b>Caf\u{e9}</b>");
+            }
+            _ => panic!("expected a notice"),
+        }
+
+        let mut content = TextMessageEventContent {
+            body: "Caf\u{e9}".to_string(),
+            formatted: None,
+            mentions: None,
+            #[cfg(feature = "msc1767")]
+            m_text: None,
+            msgtype: MessageType::Text,
+            new_content: None,
+            relates_to: None,
+            url_previews: None,
+        };
+        content.normalize_body_nfc();
+        assert_eq!(content.body, "Caf\u{e9}");
+    }
+
+    #[cfg(feature = "msc1767")]
+    #[test]
+    fn best_text_representation() {
+        let json = r#"{"body":"*hi*","m.text":[{"body":"*hi*","mimetype":"text/plain"},{"body":"<em>hi</em>","mimetype":"text/html"}],"msgtype":"m.text"}"#;
+        let mut content = from_str::<TextMessageEventContent>(json).unwrap();
+
+        assert_eq!(content.best_text_representation(), "<em>hi</em>");
+        assert_eq!(to_string(&content).unwrap(), json);
+
+        content.m_text.as_mut().unwrap().pop();
+        assert_eq!(content.best_text_representation(), "*hi*");
+
+        content.m_text = Some(vec![super::TextPart {
+            body: "hi".to_string(),
+            mimetype: "text/markdown".to_string(),
+        }]);
+        assert_eq!(content.best_text_representation(), "*hi*");
+
+        let mut content = TextMessageEventContent::plain("hi");
+        assert_eq!(content.best_text_representation(), "hi");
+
+        content = TextMessageEventContent::html("hi", "<b>hi</b>");
+        assert_eq!(content.best_text_representation(), "<b>hi</b>");
+    }
+
+    #[cfg(feature = "html-sanitize")]
+    #[test]
+    fn sanitize_html() {
+        use super::SanitizePolicy;
+
+        let sanitize = |html: &str| super::sanitize_html(html, &SanitizePolicy::default());
+
+        assert_eq!(
+            sanitize("<p>Hello <b>world</b><script>alert('<b>hi</b>')</script></p>"),
+            "<p>Hello <b>world</b></p>"
+        );
+        assert_eq!(
+            sanitize("<mx-reply><blockquote>Quote</blockquote></mx-reply><h1>Title</h1><ul><li><em>One</em></li></ul>"),
+            "<mx-reply>Quote</mx-reply><h1>Title</h1><ul><li><em>One</em></li></ul>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="https://matrix.org" onclick="steal()">Matrix</a>"#),
+            r#"<a href="https://matrix.org">Matrix</a>"#
+        );
+        assert_eq!(
+            sanitize(
+                r#"<A HREF='javascript:alert(1)'>x</A><a href="java&#115;cript:alert(1)">y</a>"#
+            ),
+            "<a>x</a><a>y</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<img src="x" onerror="alert(1)">line<br/>break<!-- <script> -->"#),
+            "line<br>break"
+        );
+        assert_eq!(
+            sanitize(r#"<b title="a>b">bold <i>and italic"#),
+            "<b>bold <i>and italic</i></b>"
+        );
+        assert_eq!(
+            sanitize("<code>1 < 2 && 3 > 2</code></p>"),
+            "<code>1 &lt; 2 && 3 &gt; 2</code>"
+        );
+        assert_eq!(sanitize("a < b and c > d"), "a &lt; b and c &gt; d");
+        assert_eq!(sanitize("<b>1<2</b>"), "<b>1&lt;2</b>");
+        assert_eq!(
+            sanitize(r#"<a href="https://x"" onclick=y>z</a>"#),
+            r#"&lt;a href="https://x"" onclick=y&gt;z&lt;/a&gt;"#
+        );
+    }
+
+    #[cfg(feature = "html-strip")]
+    #[test]
+    fn strip_html_tags() {
+        assert_eq!(
+            super::strip_html_tags("<p>Hello <b>world</b> &amp; <a href=\"https://matrix.org\">friends</a></p><p>line<br/>break</p>"),
+            "Hello world & friends\nline\nbreak"
+        );
+        assert_eq!(
+            super::strip_html_tags("1 &lt; 2 &unknown; 3"),
+            "1 < 2 &unknown; 3"
+        );
+        assert_eq!(super::strip_html_tags("unclosed <b"), "unclosed <b");
+        assert_eq!(super::strip_html_tags("I <3 you"), "I <3 you");
+        assert_eq!(super::strip_html_tags("a < b and c > d"), "a < b and c > d");
+        assert_eq!(
+            super::strip_html_tags(
+                "<style>p { color: red; }</style>Hi<script>alert('<b>x</b>')</script><!-- a > b -->!"
+            ),
+            "Hi!"
+        );
+        assert_eq!(
+            super::strip_html_tags("1&#8212;2 &#x2014; &#xD800; &#;"),
+            "1\u{2014}2 \u{2014} &#xD800; &#;"
         );
     }
 }
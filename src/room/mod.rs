@@ -2,10 +2,18 @@
 //!
 //! This module also contains types shared by events in its child namespaces.
 
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use ruma_identifiers::RoomAliasId;
+
+use MxcUri;
+
 pub mod aliases;
 pub mod avatar;
 pub mod canonical_alias;
 pub mod create;
+pub mod encrypted;
 pub mod guest_access;
 pub mod history_visibility;
 pub mod join_rules;
@@ -15,11 +23,27 @@ pub mod name;
 pub mod pinned_events;
 pub mod power_levels;
 pub mod redaction;
+pub mod server_acl;
 pub mod third_party_invite;
 pub mod topic;
 
+/// An error when a room alias is not of the form `#localpart:server_name`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AliasFormatError {
+    /// The alias does not start with `#`.
+    MissingSigil,
+    /// The alias has no `:` between the localpart and the server name.
+    MissingColon,
+    /// The localpart of the alias is empty.
+    EmptyLocalpart,
+    /// The server name of the alias is not a valid host, optionally followed by a port.
+    InvalidServerName,
+    /// The alias is longer than 255 bytes.
+    TooLong,
+}
+
 /// Metadata about an image.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ImageInfo {
     /// The height of the image in pixels.
     #[serde(rename = "h")]
@@ -28,6 +52,9 @@ pub struct ImageInfo {
     pub mimetype: String,
     /// The file size of the image in bytes.
     pub size: u64,
+    /// The encrypted thumbnail of the image, used instead of `thumbnail_url` in encrypted rooms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_file: Option<Box<EncryptedFile>>,
     /// Metadata about the image referred to in `thumbnail_url`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_info: Option<ThumbnailInfo>,
@@ -39,8 +66,20 @@ pub struct ImageInfo {
     pub width: u64,
 }
 
+impl ImageInfo {
+    /// The URL of the thumbnail of the image, if there is one and it is a valid MXC URI.
+    pub fn thumbnail_mxc_uri(&self) -> Option<MxcUri> {
+        thumbnail_mxc_uri(self.thumbnail_url.as_ref())
+    }
+
+    /// Whether or not the image has a thumbnail with a valid MXC URI.
+    pub fn has_thumbnail(&self) -> bool {
+        self.thumbnail_mxc_uri().is_some()
+    }
+}
+
 /// Metadata about a thumbnail.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ThumbnailInfo {
     /// The height of the thumbnail in pixels.
     #[serde(rename = "h")]
@@ -53,3 +92,207 @@ pub struct ThumbnailInfo {
     #[serde(rename = "w")]
     pub width: u64,
 }
+
+/// A file encrypted on the client, as sent in encrypted rooms.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EncryptedFile {
+    /// The SHA-256 hashes of the ciphertext, keyed by algorithm and encoded as unpadded base64.
+    pub hashes: BTreeMap<String, String>,
+    /// The initialization vector used by AES-CTR, encoded as unpadded base64.
+    pub iv: String,
+    /// The key used to encrypt the file.
+    pub key: JsonWebKey,
+    /// The URL to the encrypted file.
+    pub url: String,
+    /// The version of the encrypted attachments protocol. Must be "v2".
+    pub v: String,
+}
+
+/// A JSON Web Key used to encrypt an `EncryptedFile`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct JsonWebKey {
+    /// The algorithm of the key. Must be "A256CTR".
+    pub alg: String,
+    /// Whether or not the key is extractable. Must be `true`.
+    pub ext: bool,
+    /// The key, encoded as urlsafe unpadded base64.
+    pub k: String,
+    /// The operations the key may be used for. Must include "encrypt" and "decrypt".
+    pub key_ops: Vec<String>,
+    /// The type of the key. Must be "oct".
+    pub kty: String,
+}
+
+/// Where to fetch a piece of media from, either as plain or as encrypted content.
+///
+/// `url` is `None` if the plain URL is not a valid MXC URI, in which case `file` is always set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MediaSource {
+    /// The URL of the unencrypted media.
+    pub url: Option<MxcUri>,
+    /// The encrypted media.
+    pub file: Option<EncryptedFile>,
+}
+
+impl MediaSource {
+    /// Where to fetch the thumbnail described by `info` from, or `None` if there is neither an
+    /// encrypted thumbnail nor a plain one with a valid MXC URI.
+    pub(crate) fn thumbnail<T: ThumbnailFields>(info: Option<&T>) -> Option<Self> {
+        let (url, file) = info?.thumbnail_fields();
+        let url = thumbnail_mxc_uri(url);
+
+        if url.is_none() && file.is_none() {
+            return None;
+        }
+
+        Some(MediaSource {
+            url,
+            file: file.cloned(),
+        })
+    }
+}
+
+/// Metadata about media that can have a thumbnail: `ImageInfo`, `FileInfo` and `VideoInfo`.
+pub(crate) trait ThumbnailFields {
+    /// The `thumbnail_url` and `thumbnail_file` of the media.
+    fn thumbnail_fields(&self) -> (Option<&String>, Option<&EncryptedFile>);
+}
+
+impl ThumbnailFields for ImageInfo {
+    fn thumbnail_fields(&self) -> (Option<&String>, Option<&EncryptedFile>) {
+        (self.thumbnail_url.as_ref(), self.thumbnail_file.as_deref())
+    }
+}
+
+/// The `thumbnail_mxc_uri` of image, file and video metadata.
+///
+/// This is returned by value because `thumbnail_url` has to be parsed.
+pub(crate) fn thumbnail_mxc_uri(thumbnail_url: Option<&String>) -> Option<MxcUri> {
+    thumbnail_url.and_then(|url| url.parse().ok())
+}
+
+/// Checks that `alias` is a room alias of the form `#localpart:server_name`.
+///
+/// The server name must be a domain name, an IPv4 address or a bracketed IPv6 address, optionally
+/// followed by a port.
+pub fn validate_alias_format(alias: &str) -> Result<(), AliasFormatError> {
+    if !alias.starts_with('#') {
+        return Err(AliasFormatError::MissingSigil);
+    }
+
+    let colon = alias.find(':').ok_or(AliasFormatError::MissingColon)?;
+
+    if colon == 1 {
+        return Err(AliasFormatError::EmptyLocalpart);
+    }
+
+    if !is_valid_server_name(&alias[colon + 1..]) {
+        return Err(AliasFormatError::InvalidServerName);
+    }
+
+    if alias.len() > 255 {
+        return Err(AliasFormatError::TooLong);
+    }
+
+    Ok(())
+}
+
+/// Parses an alias after checking its format with `validate_alias_format`.
+fn parse_alias(alias: &str) -> Result<RoomAliasId, AliasFormatError> {
+    validate_alias_format(alias)?;
+
+    // Only the server name can still be rejected, because `RoomAliasId` parses it as a URL host.
+    RoomAliasId::try_from(alias).map_err(|_| AliasFormatError::InvalidServerName)
+}
+
+fn is_valid_server_name(server_name: &str) -> bool {
+    let (host, port) = if server_name.starts_with('[') {
+        match server_name.find(']') {
+            Some(end) => (&server_name[..=end], &server_name[end + 1..]),
+            None => return false,
+        }
+    } else {
+        match server_name.find(':') {
+            Some(colon) => (&server_name[..colon], &server_name[colon..]),
+            None => (server_name, ""),
+        }
+    };
+
+    let valid_host = if host.starts_with('[') {
+        host.len() > 2
+            && host[1..host.len() - 1]
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.')
+    } else {
+        !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    };
+    let valid_port = port.is_empty()
+        || (port.len() > 1 && port.len() <= 6 && port[1..].chars().all(|c| c.is_ascii_digit()));
+
+    valid_host && valid_port
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::{validate_alias_format, AliasFormatError, ImageInfo};
+
+    #[test]
+    fn image_thumbnail() {
+        let info = from_str::<ImageInfo>(
+            r#"{"h":480,"mimetype":"image/png","size":1024,"thumbnail_url":"mxc://example.org/thumb","w":640}"#,
+        )
+        .unwrap();
+        assert!(info.has_thumbnail());
+        assert_eq!(
+            info.thumbnail_mxc_uri().unwrap().to_string(),
+            "mxc://example.org/thumb"
+        );
+
+        let info = from_str::<ImageInfo>(r#"{"h":480,"mimetype":"image/png","size":1024,"w":640}"#)
+            .unwrap();
+        assert!(!info.has_thumbnail());
+        assert_eq!(info.thumbnail_mxc_uri(), None);
+    }
+
+    #[test]
+    fn alias_formats() {
+        assert_eq!(validate_alias_format("#room:example.org"), Ok(()));
+        assert_eq!(validate_alias_format("#room:example.org:8448"), Ok(()));
+        assert_eq!(validate_alias_format("#room:1.2.3.4"), Ok(()));
+        assert_eq!(validate_alias_format("#room:[2001:db8::1]:8448"), Ok(()));
+
+        assert_eq!(
+            validate_alias_format("room:example.org"),
+            Err(AliasFormatError::MissingSigil)
+        );
+        assert_eq!(
+            validate_alias_format("#room"),
+            Err(AliasFormatError::MissingColon)
+        );
+        assert_eq!(
+            validate_alias_format("#:example.org"),
+            Err(AliasFormatError::EmptyLocalpart)
+        );
+        assert_eq!(
+            validate_alias_format("#room:"),
+            Err(AliasFormatError::InvalidServerName)
+        );
+        assert_eq!(
+            validate_alias_format("#room:exa mple.org"),
+            Err(AliasFormatError::InvalidServerName)
+        );
+        assert_eq!(
+            validate_alias_format("#room:example.org:port"),
+            Err(AliasFormatError::InvalidServerName)
+        );
+        assert_eq!(
+            validate_alias_format(&format!("#{}:example.org", "a".repeat(250))),
+            Err(AliasFormatError::TooLong)
+        );
+    }
+}
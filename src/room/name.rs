@@ -1,13 +1,23 @@
 //! Types for the *m.room.name* event.
 
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
 state_event! {
     /// A human-friendly room name designed to be displayed to the end-user.
     pub struct NameEvent(NameEventContent) {}
 }
 
 /// The payload of a `NameEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct NameEventContent {
     /// The name of the room. This MUST NOT exceed 255 bytes.
     pub name: String,
 }
+
+impl NameEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
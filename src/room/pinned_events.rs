@@ -2,18 +2,28 @@
 
 use ruma_identifiers::EventId;
 
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
 state_event! {
     /// Used to "pin" particular events in a room for other participants to review later.
     pub struct PinnedEventsEvent(PinnedEventsContent) {}
 }
 
 /// The payload of a `NameEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct PinnedEventsContent {
     /// An ordered list of event IDs to pin.
     pub pinned: Vec<EventId>,
 }
 
+impl PinnedEventsContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ruma_identifiers::{EventId, RoomId, UserId};
@@ -1,9 +1,14 @@
 //! Types for the *m.room.power_levels* event.
 
+use std::cell::OnceCell;
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
 
 use ruma_identifiers::UserId;
+use serde::Serializer;
 
+use room::create::RoomVersion;
 use EventType;
 
 state_event! {
@@ -11,48 +16,930 @@ state_event! {
     pub struct PowerLevelsEvent(PowerLevelsEventContent) {}
 }
 
+/// The level required to ban a user if `ban` is not set.
+pub const DEFAULT_BAN_LEVEL: i64 = 50;
+
+/// The level required to send message events if `events_default` is not set.
+pub const DEFAULT_EVENTS_LEVEL: i64 = 0;
+
+/// The level required to invite a user if `invite` is not set.
+pub const DEFAULT_INVITE_LEVEL: i64 = 50;
+
+/// The level required to kick a user if `kick` is not set.
+pub const DEFAULT_KICK_LEVEL: i64 = 50;
+
+/// The level required to redact an event if `redact` is not set.
+pub const DEFAULT_REDACT_LEVEL: i64 = 50;
+
+/// The level required to send state events if `state_default` is not set.
+pub const DEFAULT_STATE_LEVEL: i64 = 50;
+
+/// The level of users not listed in `users` if `users_default` is not set.
+pub const DEFAULT_USERS_LEVEL: i64 = 0;
+
+/// The level required for an `@room` notification if `notifications.room` is not set.
+pub const DEFAULT_ROOM_NOTIFICATION_LEVEL: i64 = 50;
+
 /// The payload of a `PowerLevelsEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+///
+/// Fields holding their default value are omitted when serializing. The keys of `events` and
+/// `users` are serialized in lexicographic order, so equal contents produce the same JSON.
+///
+/// Levels outside the range of `i64` fail to deserialize. Levels are only ever compared, never
+/// added or subtracted, so `i64::MIN` and `i64::MAX` cannot overflow in the permission checks.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct PowerLevelsEventContent {
     /// The level required to ban a user.
-    #[serde(default = "default_power_level")]
-    pub ban: u64,
+    #[serde(default = "default_ban_level")]
+    #[serde(skip_serializing_if = "is_default_ban_level")]
+    pub ban: i64,
 
     /// The level required to send specific event types.
     ///
     /// This is a mapping from event type to power level required.
-    pub events: HashMap<EventType, u64>,
+    #[serde(default, serialize_with = "serialize_sorted")]
+    #[serde(skip_serializing_if = "is_default")]
+    pub events: HashMap<EventType, i64>,
 
     /// The default level required to send message events.
-    #[serde(default)]
-    pub events_default: u64,
+    #[serde(default = "default_events_level")]
+    #[serde(skip_serializing_if = "is_default_events_level")]
+    pub events_default: i64,
 
     /// The level required to invite a user.
-    #[serde(default = "default_power_level")]
-    pub invite: u64,
+    #[serde(default = "default_invite_level")]
+    #[serde(skip_serializing_if = "is_default_invite_level")]
+    pub invite: i64,
 
     /// The level required to kick a user.
-    #[serde(default = "default_power_level")]
-    pub kick: u64,
+    #[serde(default = "default_kick_level")]
+    #[serde(skip_serializing_if = "is_default_kick_level")]
+    pub kick: i64,
+
+    /// The power level requirements for specific notification types.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_default")]
+    pub notifications: NotificationPowerLevels,
 
     /// The level required to redact an event.
-    #[serde(default = "default_power_level")]
-    pub redact: u64,
+    #[serde(default = "default_redact_level")]
+    #[serde(skip_serializing_if = "is_default_redact_level")]
+    pub redact: i64,
 
     /// The default level required to send state events.
-    #[serde(default = "default_power_level")]
-    pub state_default: u64,
+    #[serde(default = "default_state_level")]
+    #[serde(skip_serializing_if = "is_default_state_level")]
+    pub state_default: i64,
 
     /// The power levels for specific users.
     ///
     /// This is a mapping from `user_id` to power level for that user.
-    pub users: HashMap<UserId, u64>,
+    #[serde(default, serialize_with = "serialize_sorted")]
+    #[serde(skip_serializing_if = "is_default")]
+    pub users: HashMap<UserId, i64>,
 
     /// The default power level for every user in the room.
-    #[serde(default)]
-    pub users_default: u64,
+    #[serde(default = "default_users_level")]
+    #[serde(skip_serializing_if = "is_default_users_level")]
+    pub users_default: i64,
+}
+
+/// The power level requirements for specific notification types.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NotificationPowerLevels {
+    /// The level required to trigger an `@room` notification.
+    #[serde(default = "default_room_notification_level")]
+    #[serde(skip_serializing_if = "is_default_room_notification_level")]
+    pub room: i64,
+}
+
+/// A likely mistake in a `PowerLevelsEventContent`, found by `PowerLevelsEventContent::validate`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PowerLevelWarning {
+    /// No user has the `state_default` level, so nobody can change most of the room's state.
+    AllUsersLockedOut,
+
+    /// The level required to ban is below the level required to kick, so users who may not kick
+    /// can still remove others by banning them.
+    BanLevelBelowKickLevel,
+
+    /// The level required to invite is below `users_default`, the level users have when they
+    /// join, so every member can invite other users.
+    InviteLevelBelowJoinLevel,
+
+    /// No user has the level required to send *m.room.power_levels*, so the power levels can never
+    /// be changed again.
+    AdminCannotChangeOwnLevels,
+}
+
+/// An action a user can take against another user, governed by the power levels.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PowerLevelAction {
+    /// Banning the user.
+    Ban,
+
+    /// Inviting the user.
+    Invite,
+
+    /// Kicking the user.
+    Kick,
+
+    /// Redacting an event sent by the user.
+    Redact,
+}
+
+/// Something a user may do in a room, depending on their power level. Listed in
+/// `PowerLevelsEventContent::permission_matrix`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Permission {
+    /// Banning users with a lower power level.
+    Ban,
+
+    /// Inviting users.
+    Invite,
+
+    /// Kicking users with a lower power level.
+    Kick,
+
+    /// Redacting events sent by other users.
+    Redact,
+
+    /// Triggering an `@room` notification.
+    NotifyRoom,
+
+    /// Sending message events without a level of their own in `events`.
+    SendMessages,
+
+    /// Sending state events without a level of their own in `events`.
+    SendStateEvents,
+
+    /// Sending events of a type with a level of its own in `events`.
+    SendEvent(EventType),
+}
+
+/// A `PowerLevelsEventContent` with an index of its users by power level.
+///
+/// The index is built on the first query and discarded whenever the content is changed through
+/// `content_mut` or `set_content`. Only users listed in `users` are indexed.
+#[derive(Clone, Debug)]
+pub struct PowerLevelsCache {
+    content: PowerLevelsEventContent,
+    index: OnceCell<UserIndex>,
+}
+
+/// The users listed in `users`, sorted from the highest power level to the lowest.
+#[derive(Clone, Debug)]
+struct UserIndex {
+    levels: Vec<i64>,
+    users: Vec<UserId>,
+}
+
+impl PowerLevelsEventContent {
+    /// The power level of the given user in the room.
+    ///
+    /// Users without an entry in `users` have the `users_default` level.
+    pub fn user_power_level(&self, user_id: &UserId) -> i64 {
+        self.users
+            .get(user_id)
+            .cloned()
+            .unwrap_or(self.users_default)
+    }
+
+    /// Whether or not `redacter` is allowed to redact an event sent by `target_sender`.
+    ///
+    /// Users may always redact their own events. Redacting other users' events requires at least
+    /// the `redact` power level.
+    pub fn user_can_redact_event(&self, redacter: &UserId, target_sender: &UserId) -> bool {
+        redacter == target_sender || self.user_power_level(redacter) >= self.redact
+    }
+
+    /// Whether or not the given user is allowed to trigger an `@room` notification.
+    pub fn user_can_notify_room(&self, user_id: &UserId) -> bool {
+        self.user_power_level(user_id) >= self.notifications.room
+    }
+
+    /// Whether or not `actor` is allowed to take `action` against `target`.
+    ///
+    /// `actor` needs at least the power level required for `action`. Kicking and banning also
+    /// require `target` to have a lower power level than `actor`. Users are never considered to
+    /// target themselves, so this is `false` if `actor` and `target` are the same user.
+    pub fn can_user_target_another(
+        &self,
+        actor: &UserId,
+        target: &UserId,
+        action: PowerLevelAction,
+    ) -> bool {
+        if actor == target {
+            return false;
+        }
+
+        let actor_level = self.user_power_level(actor);
+        let required_level = match action {
+            PowerLevelAction::Ban => self.ban,
+            PowerLevelAction::Invite => self.invite,
+            PowerLevelAction::Kick => self.kick,
+            PowerLevelAction::Redact => self.redact,
+        };
+
+        match action {
+            PowerLevelAction::Ban | PowerLevelAction::Kick => {
+                actor_level >= required_level && self.user_power_level(target) < actor_level
+            }
+            PowerLevelAction::Invite | PowerLevelAction::Redact => actor_level >= required_level,
+        }
+    }
+
+    /// What each user listed in `users` may do, sorted by user ID.
+    ///
+    /// The first row, with no user ID, is what users without an entry in `users` may do. Each
+    /// row lists the permissions in the order of `Permission`, followed by the event types in
+    /// `events` sorted by name.
+    pub fn permission_matrix(&self) -> Vec<(Option<UserId>, Vec<Permission>)> {
+        let mut event_levels = self.events.iter().collect::<Vec<_>>();
+        event_levels.sort_by_key(|&(event_type, _)| event_type.to_string());
+
+        let permissions = |level: i64| {
+            let mut permissions = vec![
+                (Permission::Ban, self.ban),
+                (Permission::Invite, self.invite),
+                (Permission::Kick, self.kick),
+                (Permission::Redact, self.redact),
+                (Permission::NotifyRoom, self.notifications.room),
+                (Permission::SendMessages, self.events_default),
+                (Permission::SendStateEvents, self.state_default),
+            ]
+            .into_iter()
+            .filter(|&(_, required_level)| level >= required_level)
+            .map(|(permission, _)| permission)
+            .collect::<Vec<_>>();
+
+            permissions.extend(
+                event_levels
+                    .iter()
+                    .filter(|&&(_, &required_level)| level >= required_level)
+                    .map(|&(event_type, _)| Permission::SendEvent(event_type.clone())),
+            );
+
+            permissions
+        };
+
+        let mut users = self.users.iter().collect::<Vec<_>>();
+        users.sort_by_key(|&(user_id, _)| user_id.to_string());
+
+        let mut matrix = vec![(None, permissions(self.users_default))];
+        matrix.extend(
+            users
+                .into_iter()
+                .map(|(user_id, &level)| (Some(user_id.clone()), permissions(level))),
+        );
+
+        matrix
+    }
+
+    /// The users listed in `users` whose power level is at least `level`, from the highest power
+    /// level to the lowest.
+    ///
+    /// Users relying on `users_default` are not listed, since they cannot be enumerated from the
+    /// power levels alone. `PowerLevelsCache` answers the same query without sorting each time.
+    pub fn users_with_level_at_least(&self, level: i64) -> Vec<&UserId> {
+        self.users_by_level(|user_level| user_level >= level)
+    }
+
+    /// The users listed in `users` whose power level is exactly `level`.
+    ///
+    /// Users relying on `users_default` are not listed, even if `level` is the default.
+    pub fn users_with_exact_level(&self, level: i64) -> Vec<&UserId> {
+        self.users_by_level(|user_level| user_level == level)
+    }
+
+    fn users_by_level<F>(&self, filter: F) -> Vec<&UserId>
+    where
+        F: Fn(i64) -> bool,
+    {
+        let mut users = self
+            .users
+            .iter()
+            .filter(|&(_, &level)| filter(level))
+            .map(|(user_id, &level)| (level, user_id))
+            .collect::<Vec<_>>();
+
+        users.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| a.1.to_string().cmp(&b.1.to_string()))
+        });
+
+        users.into_iter().map(|(_, user_id)| user_id).collect()
+    }
+
+    /// Checks these power levels for settings that contradict each other or lock users out.
+    ///
+    /// Only the levels in this content are considered, so a room whose creator is not listed in
+    /// `users` may be reported as locked out.
+    pub fn validate(&self) -> Vec<PowerLevelWarning> {
+        let mut warnings = Vec::new();
+        let highest_level = self
+            .users
+            .values()
+            .cloned()
+            .fold(self.users_default, i64::max);
+        let power_levels_level = self
+            .events
+            .get(&EventType::RoomPowerLevels)
+            .cloned()
+            .unwrap_or(self.state_default);
+
+        if highest_level < self.state_default {
+            warnings.push(PowerLevelWarning::AllUsersLockedOut);
+        } else if highest_level < power_levels_level {
+            warnings.push(PowerLevelWarning::AdminCannotChangeOwnLevels);
+        }
+
+        if self.ban < self.kick {
+            warnings.push(PowerLevelWarning::BanLevelBelowKickLevel);
+        }
+
+        if self.invite < self.users_default {
+            warnings.push(PowerLevelWarning::InviteLevelBelowJoinLevel);
+        }
+
+        warnings
+    }
+
+    /// The content of this event after a redaction in a room of the given version.
+    ///
+    /// `notifications` is reset to its default. Every other level survives, except `invite`
+    /// before room version 11.
+    pub fn redact(&self, room_version: &RoomVersion) -> PowerLevelsEventContent {
+        let invite = if room_version.is_older_than(11) {
+            DEFAULT_INVITE_LEVEL
+        } else {
+            self.invite
+        };
+
+        PowerLevelsEventContent {
+            invite,
+            notifications: NotificationPowerLevels::default(),
+            ..self.clone()
+        }
+    }
+}
+
+impl PowerLevelsCache {
+    /// Creates a cache for the given power levels.
+    pub fn new(content: PowerLevelsEventContent) -> Self {
+        PowerLevelsCache {
+            content,
+            index: OnceCell::new(),
+        }
+    }
+
+    /// The cached power levels.
+    pub fn content(&self) -> &PowerLevelsEventContent {
+        &self.content
+    }
+
+    /// Mutable access to the cached power levels. This invalidates the index.
+    pub fn content_mut(&mut self) -> &mut PowerLevelsEventContent {
+        self.index = OnceCell::new();
+        &mut self.content
+    }
+
+    /// Replaces the cached power levels, e.g. when a new *m.room.power_levels* event is received.
+    pub fn set_content(&mut self, content: PowerLevelsEventContent) {
+        self.content = content;
+        self.index = OnceCell::new();
+    }
+
+    /// Consumes the cache, returning the power levels.
+    pub fn into_content(self) -> PowerLevelsEventContent {
+        self.content
+    }
+
+    /// The users whose power level is at least `level`, from the highest power level to the
+    /// lowest.
+    pub fn users_at_or_above_level(&self, level: i64) -> &[UserId] {
+        let index = self.index.get_or_init(|| self.build_index());
+        let count = index
+            .levels
+            .partition_point(|&user_level| user_level >= level);
+
+        &index.users[..count]
+    }
+
+    fn build_index(&self) -> UserIndex {
+        let users_by_level = self.content.users_with_level_at_least(i64::MIN);
+
+        UserIndex {
+            levels: users_by_level
+                .iter()
+                .map(|user_id| self.content.users[*user_id])
+                .collect(),
+            users: users_by_level.into_iter().cloned().collect(),
+        }
+    }
+}
+
+impl Default for PowerLevelsEventContent {
+    fn default() -> Self {
+        PowerLevelsEventContent {
+            ban: DEFAULT_BAN_LEVEL,
+            events: HashMap::new(),
+            events_default: DEFAULT_EVENTS_LEVEL,
+            invite: DEFAULT_INVITE_LEVEL,
+            kick: DEFAULT_KICK_LEVEL,
+            notifications: NotificationPowerLevels::default(),
+            redact: DEFAULT_REDACT_LEVEL,
+            state_default: DEFAULT_STATE_LEVEL,
+            users: HashMap::new(),
+            users_default: DEFAULT_USERS_LEVEL,
+        }
+    }
+}
+
+impl Default for NotificationPowerLevels {
+    fn default() -> Self {
+        NotificationPowerLevels {
+            room: DEFAULT_ROOM_NOTIFICATION_LEVEL,
+        }
+    }
 }
 
-fn default_power_level() -> u64 {
-    50
+fn default_ban_level() -> i64 {
+    DEFAULT_BAN_LEVEL
+}
+
+fn is_default_ban_level(level: &i64) -> bool {
+    *level == DEFAULT_BAN_LEVEL
+}
+
+fn default_events_level() -> i64 {
+    DEFAULT_EVENTS_LEVEL
+}
+
+fn is_default_events_level(level: &i64) -> bool {
+    *level == DEFAULT_EVENTS_LEVEL
+}
+
+fn default_invite_level() -> i64 {
+    DEFAULT_INVITE_LEVEL
+}
+
+fn is_default_invite_level(level: &i64) -> bool {
+    *level == DEFAULT_INVITE_LEVEL
+}
+
+fn default_kick_level() -> i64 {
+    DEFAULT_KICK_LEVEL
+}
+
+fn is_default_kick_level(level: &i64) -> bool {
+    *level == DEFAULT_KICK_LEVEL
+}
+
+fn default_redact_level() -> i64 {
+    DEFAULT_REDACT_LEVEL
+}
+
+fn is_default_redact_level(level: &i64) -> bool {
+    *level == DEFAULT_REDACT_LEVEL
+}
+
+fn default_state_level() -> i64 {
+    DEFAULT_STATE_LEVEL
+}
+
+fn is_default_state_level(level: &i64) -> bool {
+    *level == DEFAULT_STATE_LEVEL
+}
+
+fn default_users_level() -> i64 {
+    DEFAULT_USERS_LEVEL
+}
+
+fn is_default_users_level(level: &i64) -> bool {
+    *level == DEFAULT_USERS_LEVEL
+}
+
+fn default_room_notification_level() -> i64 {
+    DEFAULT_ROOM_NOTIFICATION_LEVEL
+}
+
+fn is_default_room_notification_level(level: &i64) -> bool {
+    *level == DEFAULT_ROOM_NOTIFICATION_LEVEL
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Serializes a map of power levels with its keys in lexicographic order.
+fn serialize_sorted<K, S>(levels: &HashMap<K, i64>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Display + Eq + Hash,
+    S: Serializer,
+{
+    let mut levels = levels
+        .iter()
+        .map(|(key, &level)| (key.to_string(), level))
+        .collect::<Vec<_>>();
+    levels.sort();
+
+    serializer.collect_map(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ruma_identifiers::UserId;
+    use serde_json::{from_str, to_string};
+
+    use super::{
+        NotificationPowerLevels, Permission, PowerLevelAction, PowerLevelWarning, PowerLevelsCache,
+        PowerLevelsEventContent,
+    };
+    use room::create::RoomVersion;
+
+    #[test]
+    fn maximum_power_levels_round_trip() {
+        let json = format!(
+            r#"{{"ban":{max},"events":{{"m.room.name":{max}}},"events_default":{max},"invite":{max},"kick":{max},"redact":{max},"state_default":{max},"users":{{"@alice:example.com":{max}}},"users_default":{max}}}"#,
+            max = i64::MAX
+        );
+
+        let content = from_str::<PowerLevelsEventContent>(&json).unwrap();
+
+        assert_eq!(content.ban, i64::MAX);
+        assert_eq!(content.users_default, i64::MAX);
+        assert_eq!(content.users.values().next().cloned(), Some(i64::MAX));
+        assert_eq!(
+            from_str::<PowerLevelsEventContent>(&to_string(&content).unwrap())
+                .unwrap()
+                .ban,
+            i64::MAX
+        );
+    }
+
+    #[test]
+    fn extreme_power_levels() {
+        let content = from_str::<PowerLevelsEventContent>(&format!(
+            r#"{{"ban":{max},"kick":{min},"notifications":{{"room":{min}}},"redact":{max},"users":{{"@alice:example.com":{max},"@bob:example.com":{min}}},"users_default":{min}}}"#,
+            max = i64::MAX,
+            min = i64::MIN
+        ))
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let carl = UserId::try_from("@carl:example.com").unwrap();
+
+        assert_eq!(content.user_power_level(&bob), i64::MIN);
+        assert_eq!(content.user_power_level(&carl), i64::MIN);
+        assert!(content.user_can_redact_event(&alice, &bob));
+        assert!(!content.user_can_redact_event(&bob, &alice));
+        assert!(content.user_can_notify_room(&bob));
+        assert!(content.can_user_target_another(&alice, &bob, PowerLevelAction::Ban));
+        assert!(!content.can_user_target_another(&bob, &carl, PowerLevelAction::Invite));
+        assert!(!content.can_user_target_another(&bob, &carl, PowerLevelAction::Kick));
+        assert!(!content.can_user_target_another(&bob, &alice, PowerLevelAction::Ban));
+        assert_eq!(
+            content.users_with_level_at_least(i64::MIN),
+            vec![&alice, &bob]
+        );
+        assert_eq!(content.users_with_exact_level(i64::MAX), vec![&alice]);
+        assert_eq!(
+            from_str::<PowerLevelsEventContent>(&to_string(&content).unwrap()).unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn map_keys_are_sorted() {
+        let json = r#"{"events":{"m.room.avatar":50,"m.room.message":0,"m.room.name":50,"m.room.power_levels":100},"users":{"@alice:example.com":100,"@bob:example.com":50,"@carl:example.com":50,"@dan:example.com":0}}"#;
+        let content = from_str::<PowerLevelsEventContent>(json).unwrap();
+
+        for _ in 0..10 {
+            let content =
+                from_str::<PowerLevelsEventContent>(&to_string(&content).unwrap()).unwrap();
+            assert_eq!(to_string(&content).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn negative_power_levels() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"users":{"@alice:example.com":-1},"users_default":-10}"#,
+        )
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+
+        assert_eq!(content.users_default, -10);
+        assert_eq!(content.user_power_level(&alice), -1);
+        assert_eq!(content.user_power_level(&bob), -10);
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r#"{"users":{"@alice:example.com":-1},"users_default":-10}"#
+        );
+    }
+
+    #[test]
+    fn default_values_are_omitted() {
+        assert_eq!(
+            to_string(&PowerLevelsEventContent::default()).unwrap(),
+            "{}"
+        );
+
+        let content = PowerLevelsEventContent {
+            ban: 100,
+            notifications: NotificationPowerLevels { room: 0 },
+            users_default: 10,
+            ..PowerLevelsEventContent::default()
+        };
+
+        assert_eq!(
+            to_string(&content).unwrap(),
+            r#"{"ban":100,"notifications":{"room":0},"users_default":10}"#
+        );
+    }
+
+    #[test]
+    fn empty_content_uses_spec_defaults() {
+        let content = from_str::<PowerLevelsEventContent>("{}").unwrap();
+
+        assert_eq!(content.ban, 50);
+        assert!(content.events.is_empty());
+        assert_eq!(content.events_default, 0);
+        assert_eq!(content.invite, 50);
+        assert_eq!(content.kick, 50);
+        assert_eq!(content.notifications.room, 50);
+        assert_eq!(content.redact, 50);
+        assert_eq!(content.state_default, 50);
+        assert!(content.users.is_empty());
+        assert_eq!(content.users_default, 0);
+        assert_eq!(content, PowerLevelsEventContent::default());
+    }
+
+    #[test]
+    fn out_of_range_power_levels_are_rejected() {
+        assert!(from_str::<PowerLevelsEventContent>(
+            r#"{"ban":9223372036854775808,"events":{},"users":{}}"#
+        )
+        .is_err());
+        assert!(from_str::<PowerLevelsEventContent>(
+            r#"{"ban":50,"events":{},"users":{"@alice:example.com":-9223372036854775809}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn notify_room_power_level() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"notifications":{"room":20},"users":{"@alice:example.com":20},"users_default":10}"#,
+        )
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+
+        assert_eq!(content.notifications.room, 20);
+        assert!(content.user_can_notify_room(&alice));
+        assert!(!content.user_can_notify_room(&bob));
+    }
+
+    #[test]
+    fn empty_notifications_use_spec_default() {
+        let content = from_str::<PowerLevelsEventContent>(r#"{"notifications":{}}"#).unwrap();
+
+        assert_eq!(content.notifications.room, 50);
+    }
+
+    #[test]
+    fn cache_lists_users_at_or_above_level() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"users":{"@alice:example.com":100,"@bob:example.com":50,"@carl:example.com":0}}"#,
+        )
+        .unwrap();
+        let cache = PowerLevelsCache::new(content);
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let carl = UserId::try_from("@carl:example.com").unwrap();
+
+        assert_eq!(cache.users_at_or_above_level(100), &[alice.clone()][..]);
+        assert_eq!(
+            cache.users_at_or_above_level(50),
+            &[alice.clone(), bob.clone()][..]
+        );
+        assert_eq!(cache.users_at_or_above_level(0), &[alice, bob, carl][..]);
+        assert!(cache.users_at_or_above_level(101).is_empty());
+    }
+
+    #[test]
+    fn user_can_target_another() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"users":{"@alice:example.com":100,"@bob:example.com":50,"@carl:example.com":50}}"#,
+        )
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let carl = UserId::try_from("@carl:example.com").unwrap();
+        let dan = UserId::try_from("@dan:example.com").unwrap();
+
+        for &action in &[PowerLevelAction::Ban, PowerLevelAction::Kick] {
+            assert!(!content.can_user_target_another(&alice, &alice, action));
+            assert!(content.can_user_target_another(&alice, &bob, action));
+            assert!(content.can_user_target_another(&bob, &dan, action));
+            assert!(!content.can_user_target_another(&bob, &carl, action));
+            assert!(!content.can_user_target_another(&bob, &alice, action));
+            assert!(!content.can_user_target_another(&dan, &alice, action));
+        }
+
+        assert!(content.can_user_target_another(&bob, &carl, PowerLevelAction::Redact));
+        assert!(content.can_user_target_another(&bob, &alice, PowerLevelAction::Invite));
+        assert!(!content.can_user_target_another(&dan, &bob, PowerLevelAction::Invite));
+        assert!(!content.can_user_target_another(&bob, &bob, PowerLevelAction::Redact));
+    }
+
+    #[test]
+    fn permission_matrix() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"ban":100,"events":{"m.room.name":50,"m.room.avatar":0},"users":{"@bob:example.com":50,"@alice:example.com":100}}"#,
+        )
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let avatar = Permission::SendEvent("m.room.avatar".into());
+        let name = Permission::SendEvent("m.room.name".into());
+
+        assert_eq!(
+            content.permission_matrix(),
+            vec![
+                (None, vec![Permission::SendMessages, avatar.clone()]),
+                (
+                    Some(alice),
+                    vec![
+                        Permission::Ban,
+                        Permission::Invite,
+                        Permission::Kick,
+                        Permission::Redact,
+                        Permission::NotifyRoom,
+                        Permission::SendMessages,
+                        Permission::SendStateEvents,
+                        avatar.clone(),
+                        name.clone(),
+                    ]
+                ),
+                (
+                    Some(bob),
+                    vec![
+                        Permission::Invite,
+                        Permission::Kick,
+                        Permission::Redact,
+                        Permission::NotifyRoom,
+                        Permission::SendMessages,
+                        Permission::SendStateEvents,
+                        avatar,
+                        name,
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn users_by_power_level() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"users":{"@alice:example.com":100,"@bob:example.com":50,"@carl:example.com":50,"@dan:example.com":0}}"#,
+        )
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let carl = UserId::try_from("@carl:example.com").unwrap();
+        let dan = UserId::try_from("@dan:example.com").unwrap();
+
+        assert_eq!(content.users_with_level_at_least(100), vec![&alice]);
+        assert_eq!(
+            content.users_with_level_at_least(50),
+            vec![&alice, &bob, &carl]
+        );
+        assert_eq!(
+            content.users_with_level_at_least(i64::MIN),
+            vec![&alice, &bob, &carl, &dan]
+        );
+        assert!(content.users_with_level_at_least(i64::MAX).is_empty());
+
+        assert_eq!(content.users_with_exact_level(50), vec![&bob, &carl]);
+        assert_eq!(content.users_with_exact_level(0), vec![&dan]);
+        assert!(content.users_with_exact_level(75).is_empty());
+    }
+
+    #[test]
+    fn users_by_power_level_ignore_users_default() {
+        let content = from_str::<PowerLevelsEventContent>(r#"{"users_default":100}"#).unwrap();
+
+        assert!(content.users_with_level_at_least(0).is_empty());
+        assert!(content.users_with_exact_level(100).is_empty());
+
+        let json = format!(
+            r#"{{"users":{{"@alice:example.com":{max},"@bob:example.com":{min}}}}}"#,
+            max = i64::MAX,
+            min = i64::MIN
+        );
+        let content = from_str::<PowerLevelsEventContent>(&json).unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+
+        assert_eq!(content.users_with_level_at_least(i64::MAX), vec![&alice]);
+        assert_eq!(content.users_with_exact_level(i64::MIN), vec![&bob]);
+        assert_eq!(
+            content.users_with_level_at_least(i64::MIN),
+            vec![&alice, &bob]
+        );
+    }
+
+    #[test]
+    fn cache_is_invalidated_when_content_changes() {
+        let content =
+            from_str::<PowerLevelsEventContent>(r#"{"users":{"@alice:example.com":100}}"#).unwrap();
+        let mut cache = PowerLevelsCache::new(content);
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+
+        assert_eq!(cache.users_at_or_above_level(50), &[alice.clone()][..]);
+
+        cache.content_mut().users.insert(bob.clone(), 50);
+        assert_eq!(cache.users_at_or_above_level(50), &[alice, bob][..]);
+
+        cache.set_content(from_str::<PowerLevelsEventContent>("{}").unwrap());
+        assert!(cache.users_at_or_above_level(0).is_empty());
+    }
+
+    #[test]
+    fn redact_power_level() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"redact":50,"users":{"@alice:example.com":50,"@bob:example.com":49}}"#,
+        )
+        .unwrap();
+        let alice = UserId::try_from("@alice:example.com").unwrap();
+        let bob = UserId::try_from("@bob:example.com").unwrap();
+        let carl = UserId::try_from("@carl:example.com").unwrap();
+
+        assert!(content.user_can_redact_event(&alice, &bob));
+        assert!(!content.user_can_redact_event(&bob, &alice));
+        assert!(content.user_can_redact_event(&bob, &bob));
+        assert!(content.user_can_redact_event(&carl, &carl));
+        assert!(!content.user_can_redact_event(&carl, &bob));
+    }
+
+    #[test]
+    fn redact() {
+        let content = from_str::<PowerLevelsEventContent>(
+            r#"{"ban":100,"events":{"m.room.name":100},"events_default":10,"invite":0,"kick":75,"notifications":{"room":100},"redact":25,"state_default":60,"users":{"@alice:example.com":100},"users_default":5}"#,
+        )
+        .unwrap();
+
+        let redacted = content.redact(&RoomVersion::V11);
+
+        assert_eq!(redacted.notifications, NotificationPowerLevels::default());
+        assert_eq!(
+            PowerLevelsEventContent {
+                notifications: content.notifications,
+                ..redacted
+            },
+            content
+        );
+
+        let redacted = content.redact(&RoomVersion::V10);
+
+        assert_eq!(redacted.invite, 50);
+        assert_eq!(redacted.kick, 75);
+    }
+
+    #[test]
+    fn validate() {
+        let validate = |json: &str| {
+            from_str::<PowerLevelsEventContent>(json)
+                .unwrap()
+                .validate()
+        };
+
+        assert!(validate(r#"{"users":{"@alice:example.com":100}}"#).is_empty());
+        assert_eq!(
+            validate(r#"{"state_default":100,"users":{"@alice:example.com":99}}"#),
+            vec![PowerLevelWarning::AllUsersLockedOut]
+        );
+        assert_eq!(
+            validate(
+                r#"{"events":{"m.room.power_levels":101},"users":{"@alice:example.com":100}}"#
+            ),
+            vec![PowerLevelWarning::AdminCannotChangeOwnLevels]
+        );
+        assert_eq!(
+            validate(
+                r#"{"ban":10,"invite":0,"users":{"@alice:example.com":100},"users_default":5}"#
+            ),
+            vec![
+                PowerLevelWarning::BanLevelBelowKickLevel,
+                PowerLevelWarning::InviteLevelBelowJoinLevel,
+            ]
+        );
+    }
 }
@@ -0,0 +1,195 @@
+//! Types for the *m.room.server_acl* event.
+
+use std::net::Ipv4Addr;
+
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
+state_event! {
+    /// An event to indicate which servers are permitted to participate in the room.
+    pub struct ServerAclEvent(ServerAclEventContent) {}
+}
+
+/// The payload of a `ServerAclEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ServerAclEventContent {
+    /// Whether or not servers identified by an IP literal are allowed.
+    #[serde(default = "default_allow_ip_literals")]
+    pub allow_ip_literals: bool,
+    /// Glob patterns of the server names which are allowed in the room.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Glob patterns of the server names which are denied in the room.
+    ///
+    /// This takes precedence over `allow`.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl ServerAclEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    ///
+    /// Deserialized as `ServerAclEventContent`, the redacted content allows IP literals but no
+    /// server names.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+
+    /// Whether or not the given server is allowed to participate in the room.
+    ///
+    /// Any port is ignored. IP literals are checked against `allow_ip_literals` first, then the
+    /// server is denied if it matches `deny`, and finally allowed only if it matches `allow`.
+    pub fn server_is_allowed(&self, server_name: &str) -> bool {
+        let host = strip_port(server_name);
+
+        if !self.allow_ip_literals && is_ip_literal(host) {
+            return false;
+        }
+
+        if self
+            .deny
+            .iter()
+            .any(|pattern| matches_server(pattern, host))
+        {
+            return false;
+        }
+
+        self.allow
+            .iter()
+            .any(|pattern| matches_server(pattern, host))
+    }
+}
+
+/// Whether or not the server name matches the glob pattern.
+///
+/// `*` matches zero or more characters; every other character matches only itself.
+pub fn matches_server(pattern: &str, server_name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let server_name = server_name.as_bytes();
+
+    let (mut p, mut s) = (0, 0);
+    let mut backtrack = None;
+
+    while s < server_name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, s));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == server_name[s] {
+            p += 1;
+            s += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            s = matched + 1;
+            backtrack = Some((star, s));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
+
+/// Removes the port, if any, from a server name.
+fn strip_port(server_name: &str) -> &str {
+    if server_name.starts_with('[') {
+        match server_name.find(']') {
+            Some(end) => &server_name[..=end],
+            None => server_name,
+        }
+    } else {
+        match server_name.rfind(':') {
+            Some(colon) => &server_name[..colon],
+            None => server_name,
+        }
+    }
+}
+
+/// Whether or not the host is an IPv4 address or a bracketed IPv6 address.
+fn is_ip_literal(host: &str) -> bool {
+    host.starts_with('[') || host.parse::<Ipv4Addr>().is_ok()
+}
+
+fn default_allow_ip_literals() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, from_value, to_value};
+
+    use super::{matches_server, ServerAclEventContent};
+    use room::create::RoomVersion;
+
+    #[test]
+    fn glob_matching() {
+        assert!(matches_server("matrix.org", "matrix.org"));
+        assert!(!matches_server("matrix.org", "evil.matrix.org"));
+        assert!(matches_server("*.matrix.org", "evil.matrix.org"));
+        assert!(matches_server("*.matrix.org", "a.b.matrix.org"));
+        assert!(!matches_server("*.matrix.org", "matrix.org"));
+        assert!(matches_server("*", "anything.example"));
+        assert!(matches_server("ma*ix.o*", "matrix.org"));
+        assert!(!matches_server("matrix.?rg", "matrix.org"));
+        assert!(matches_server("matrix.?rg", "matrix.?rg"));
+    }
+
+    #[test]
+    fn deny_takes_precedence_over_allow() {
+        let content = from_str::<ServerAclEventContent>(
+            r#"{"allow":["*"],"deny":["*.evil.com","evil.com"]}"#,
+        )
+        .unwrap();
+
+        assert!(content.allow_ip_literals);
+        assert!(content.server_is_allowed("matrix.org"));
+        assert!(content.server_is_allowed("matrix.org:8448"));
+        assert!(!content.server_is_allowed("evil.com"));
+        assert!(!content.server_is_allowed("sub.evil.com:443"));
+    }
+
+    #[test]
+    fn servers_not_allowed_are_denied() {
+        let content = from_str::<ServerAclEventContent>(r#"{"allow":["*.matrix.org"]}"#).unwrap();
+
+        assert!(content.server_is_allowed("evil.matrix.org"));
+        assert!(!content.server_is_allowed("example.com"));
+        assert!(!ServerAclEventContent {
+            allow_ip_literals: true,
+            allow: Vec::new(),
+            deny: Vec::new(),
+        }
+        .server_is_allowed("matrix.org"));
+    }
+
+    #[test]
+    fn ip_literals() {
+        let mut content = from_str::<ServerAclEventContent>(r#"{"allow":["*"]}"#).unwrap();
+
+        assert!(content.server_is_allowed("1.2.3.4"));
+        assert!(content.server_is_allowed("[2001:db8::1]:8448"));
+
+        content.allow_ip_literals = false;
+
+        assert!(!content.server_is_allowed("1.2.3.4"));
+        assert!(!content.server_is_allowed("1.2.3.4:8448"));
+        assert!(!content.server_is_allowed("[2001:db8::1]"));
+        assert!(content.server_is_allowed("matrix.org"));
+    }
+
+    #[test]
+    fn redact() {
+        let content = from_str::<ServerAclEventContent>(
+            r#"{"allow":["*.matrix.org"],"allow_ip_literals":false,"deny":["evil.matrix.org"]}"#,
+        )
+        .unwrap();
+        let redacted = from_value::<ServerAclEventContent>(
+            to_value(content.redact(&RoomVersion::V11)).unwrap(),
+        )
+        .unwrap();
+
+        assert!(redacted.allow.is_empty());
+        assert!(redacted.allow_ip_literals);
+        assert!(redacted.deny.is_empty());
+        assert!(!redacted.server_is_allowed("matrix.org"));
+    }
+}
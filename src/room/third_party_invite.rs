@@ -1,5 +1,13 @@
 //! Types for the *m.room.third_party_invite* event.
 
+use std::collections::HashSet;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
 state_event! {
     /// An invitation to a room issued to a third party identifier, rather than a matrix user ID.
     ///
@@ -10,7 +18,10 @@ state_event! {
 }
 
 /// The payload of a `ThirdPartyInviteEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+///
+/// Older servers only send `public_key` and `key_validity_url`. When `public_keys` is absent, it is
+/// filled in from those fields, so it always contains at least one key after deserialization.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct ThirdPartyInviteEventContent {
     /// A user-readable string which represents the user who has been invited.
     pub display_name: String,
@@ -22,12 +33,11 @@ pub struct ThirdPartyInviteEventContent {
     pub public_key: String,
 
     /// Keys with which the token may be signed.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub public_keys: Option<Vec<PublicKey>>,
+    pub public_keys: Vec<PublicKey>,
 }
 
 /// A public key for signing a third party invite token.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct PublicKey {
     /// An optional URL which can be fetched to validate whether the key has been revoked.
     ///
@@ -39,3 +49,221 @@ pub struct PublicKey {
     /// A Base64-encoded Ed25519 key with which the token must be signed.
     pub public_key: String,
 }
+
+impl ThirdPartyInviteEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+
+    /// Whether or not two entries of `public_keys` have the same public key or the same
+    /// validity URL, which is a sign of a misconfigured server.
+    ///
+    /// Keys without a validity URL do not count as having the same URL.
+    pub fn has_duplicate_keys(&self) -> bool {
+        let mut public_keys = HashSet::new();
+        let mut key_validity_urls = HashSet::new();
+
+        self.public_keys.iter().any(|key| {
+            !public_keys.insert(&key.public_key)
+                || key
+                    .key_validity_url
+                    .as_ref()
+                    .map(|url| !key_validity_urls.insert(url))
+                    == Some(true)
+        })
+    }
+
+    /// The validity URLs of the entries of `public_keys`, in order, with `None` for keys without
+    /// one.
+    pub fn key_validity_urls(&self) -> impl Iterator<Item = Option<&str>> {
+        self.public_keys
+            .iter()
+            .map(|key| key.key_validity_url.as_deref())
+    }
+}
+
+/// The `ThirdPartyInviteEventContent` as it appears on the wire, with either key format.
+#[derive(Deserialize)]
+struct RawThirdPartyInviteEventContent {
+    display_name: String,
+    key_validity_url: Option<String>,
+    public_key: Option<String>,
+    public_keys: Option<Vec<PublicKey>>,
+}
+
+impl<'de> Deserialize<'de> for ThirdPartyInviteEventContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = RawThirdPartyInviteEventContent::deserialize(deserializer)?;
+
+        let public_keys = match (raw.public_keys, raw.public_key.as_ref()) {
+            (Some(public_keys), _) => public_keys,
+            (None, Some(public_key)) => vec![PublicKey {
+                key_validity_url: raw.key_validity_url.clone(),
+                public_key: public_key.clone(),
+            }],
+            (None, None) => return Err(D::Error::missing_field("public_keys")),
+        };
+
+        if public_keys.is_empty() {
+            return Err(D::Error::invalid_length(0, &"at least one public key"));
+        }
+
+        let public_key = match raw.public_key {
+            Some(public_key) => public_key,
+            None => public_keys[0].public_key.clone(),
+        };
+
+        let key_validity_url = match raw.key_validity_url {
+            Some(key_validity_url) => key_validity_url,
+            None => match public_keys[0].key_validity_url {
+                Some(ref key_validity_url) => key_validity_url.clone(),
+                None => return Err(D::Error::missing_field("key_validity_url")),
+            },
+        };
+
+        Ok(ThirdPartyInviteEventContent {
+            display_name: raw.display_name,
+            key_validity_url,
+            public_key,
+            public_keys,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{PublicKey, ThirdPartyInviteEventContent};
+    use room::create::RoomVersion;
+
+    #[test]
+    fn old_format_is_wrapped_in_public_keys() {
+        let content = from_str::<ThirdPartyInviteEventContent>(
+            r#"{
+                "display_name": "Alice Margatroid",
+                "key_validity_url": "https://magic.forest/verifykey",
+                "public_key": "abc123"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(content.public_key, "abc123");
+        assert_eq!(
+            content.public_keys,
+            vec![PublicKey {
+                key_validity_url: Some("https://magic.forest/verifykey".to_string()),
+                public_key: "abc123".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn new_format() {
+        let content = from_str::<ThirdPartyInviteEventContent>(
+            r#"{
+                "display_name": "Alice Margatroid",
+                "public_keys": [
+                    {"key_validity_url": "https://magic.forest/verifykey", "public_key": "abc123"},
+                    {"public_key": "def456"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(content.public_key, "abc123");
+        assert_eq!(content.key_validity_url, "https://magic.forest/verifykey");
+        assert_eq!(content.public_keys.len(), 2);
+        assert_eq!(content.public_keys[1].key_validity_url, None);
+    }
+
+    #[test]
+    fn both_formats() {
+        let json = r#"{
+            "display_name": "Alice Margatroid",
+            "key_validity_url": "https://magic.forest/verifykey",
+            "public_key": "abc123",
+            "public_keys": [{"public_key": "def456"}]
+        }"#;
+        let content = from_str::<ThirdPartyInviteEventContent>(json).unwrap();
+
+        assert_eq!(content.public_key, "abc123");
+        assert_eq!(content.public_keys.len(), 1);
+        assert_eq!(
+            to_value(&content).unwrap(),
+            from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let content = |public_keys: &str| {
+            from_str::<ThirdPartyInviteEventContent>(&format!(
+                r#"{{"display_name": "Alice Margatroid", "public_keys": {}}}"#,
+                public_keys
+            ))
+            .unwrap()
+        };
+
+        let unique = content(
+            r#"[
+                {"key_validity_url": "https://magic.forest/verifykey", "public_key": "abc123"},
+                {"public_key": "def456"},
+                {"public_key": "ghi789"}
+            ]"#,
+        );
+        assert!(!unique.has_duplicate_keys());
+        assert_eq!(
+            unique.key_validity_urls().collect::<Vec<_>>(),
+            vec![Some("https://magic.forest/verifykey"), None, None]
+        );
+
+        assert!(content(
+            r#"[
+                {"key_validity_url": "https://magic.forest/verifykey", "public_key": "abc123"},
+                {"key_validity_url": "https://magic.forest/verifykey", "public_key": "def456"}
+            ]"#,
+        )
+        .has_duplicate_keys());
+        assert!(content(
+            r#"[
+                {"key_validity_url": "https://magic.forest/verifykey", "public_key": "abc123"},
+                {"public_key": "abc123"}
+            ]"#
+        )
+        .has_duplicate_keys());
+    }
+
+    #[test]
+    fn missing_keys_are_rejected() {
+        assert!(from_str::<ThirdPartyInviteEventContent>(
+            r#"{"display_name": "Alice Margatroid"}"#
+        )
+        .is_err());
+        assert!(from_str::<ThirdPartyInviteEventContent>(
+            r#"{"display_name": "Alice Margatroid", "public_keys": []}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn redact() {
+        let content = from_str::<ThirdPartyInviteEventContent>(
+            r#"{
+                "display_name": "Alice Margatroid",
+                "key_validity_url": "https://magic.forest/verifykey",
+                "public_key": "abc123"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_value(content.redact(&RoomVersion::V1)).unwrap(),
+            from_str::<Value>("{}").unwrap()
+        );
+    }
+}
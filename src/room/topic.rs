@@ -1,13 +1,23 @@
 //! Types for the *m.room.topic* event.
 
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
 state_event! {
     /// A topic is a short message detailing what is currently being discussed in the room.
     pub struct TopicEvent(TopicEventContent) {}
 }
 
 /// The payload of a `TopicEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TopicEventContent {
     /// The topic text.
     pub topic: String,
 }
+
+impl TopicEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
@@ -0,0 +1,134 @@
+//! Types for the *m.space.child* event.
+
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
+state_event! {
+    /// A room that is part of a space.
+    ///
+    /// The state key is the ID of the child room.
+    pub struct SpaceChildEvent(SpaceChildEventContent) {}
+}
+
+/// The payload of a `SpaceChildEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SpaceChildEventContent {
+    /// A string used to sort the children of the space lexicographically.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    /// Whether or not the administrators of the space recommend joining the room.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested: Option<bool>,
+    /// Servers that can be used to join the room.
+    ///
+    /// The room is only part of the space if this is non-empty.
+    #[serde(default)]
+    pub via: Vec<String>,
+}
+
+/// An error when the `order` of an *m.space.child* event is not valid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidOrderError;
+
+impl SpaceChildEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    ///
+    /// Without `via`, the redacted event removes the room from the space.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+
+    /// Whether or not `order` is a valid order: between 1 and 50 printable ASCII characters.
+    pub fn is_valid_order(order: &str) -> bool {
+        !order.is_empty()
+            && order.len() <= 50
+            && order.bytes().all(|byte| (0x20..=0x7e).contains(&byte))
+    }
+
+    /// Checks that the `order` of this content, if any, is valid.
+    ///
+    /// Clients should ignore an invalid order rather than the whole event.
+    pub fn validate(&self) -> Result<(), InvalidOrderError> {
+        match self.order {
+            Some(ref order) if !SpaceChildEventContent::is_valid_order(order) => {
+                Err(InvalidOrderError)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{InvalidOrderError, SpaceChildEventContent};
+    use collections::all::StateEvent;
+    use room::create::RoomVersion;
+
+    #[test]
+    fn round_trip() {
+        let json = r#"{
+            "content": {"order": "lexicographically_compared", "suggested": true, "via": ["example.org"]},
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1,
+            "room_id": "!space:example.org",
+            "sender": "@alice:example.org",
+            "state_key": "!room:example.org",
+            "type": "m.space.child"
+        }"#;
+        let event = from_str::<StateEvent>(json).unwrap();
+
+        match event {
+            StateEvent::SpaceChild(ref event) => assert_eq!(
+                event.content,
+                SpaceChildEventContent {
+                    order: Some("lexicographically_compared".to_string()),
+                    suggested: Some(true),
+                    via: vec!["example.org".to_string()],
+                }
+            ),
+            _ => panic!("expected a space child event"),
+        }
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+
+    #[test]
+    fn orders() {
+        assert!(SpaceChildEventContent::is_valid_order("a"));
+        assert!(SpaceChildEventContent::is_valid_order("~ !Zz09"));
+        assert!(SpaceChildEventContent::is_valid_order(&"a".repeat(50)));
+
+        assert!(!SpaceChildEventContent::is_valid_order(""));
+        assert!(!SpaceChildEventContent::is_valid_order(&"a".repeat(51)));
+        assert!(!SpaceChildEventContent::is_valid_order("café"));
+        assert!(!SpaceChildEventContent::is_valid_order("tab\there"));
+    }
+
+    #[test]
+    fn validate() {
+        let content = |order: Option<&str>| SpaceChildEventContent {
+            order: order.map(|order| order.to_string()),
+            suggested: None,
+            via: vec!["example.org".to_string()],
+        };
+
+        assert_eq!(content(None).validate(), Ok(()));
+        assert_eq!(content(Some("a")).validate(), Ok(()));
+        assert_eq!(content(Some("")).validate(), Err(InvalidOrderError));
+        assert_eq!(content(Some("ünïcödé")).validate(), Err(InvalidOrderError));
+    }
+
+    #[test]
+    fn redact() {
+        let content = from_str::<SpaceChildEventContent>(
+            r#"{"order": "a", "suggested": true, "via": ["example.org"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_value(content.redact(&RoomVersion::V11)).unwrap(),
+            from_str::<Value>("{}").unwrap()
+        );
+    }
+}
@@ -0,0 +1,3 @@
+//! Modules for events in the *m.space* namespace.
+
+pub mod child;
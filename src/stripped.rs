@@ -1,10 +1,14 @@
 //! "Stripped-down" versions of the core state events.
 //!
-//! Each "stripped" event includes only the `content`, `type`, and `state_key` fields of its full
-//! version. These stripped types are useful for APIs where the a is providing the content of a
-//! state event to be created, when the other fields can be inferred from a larger context, or where
-//! the other fields are otherwise inapplicable.
+//! Each "stripped" event includes only the `content`, `type`, `state_key`, and optionally `sender`
+//! fields of its full version. These stripped types are useful for APIs where the user is providing
+//! the content of a state event to be created, when the other fields can be inferred from a larger
+//! context, or where the other fields are otherwise inapplicable.
 
+// *m.room.aliases* is deprecated, but these events are still received from older servers.
+#![allow(deprecated)]
+
+use ruma_identifiers::UserId;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{from_value, Value};
@@ -24,7 +28,7 @@ use room::topic::TopicEventContent;
 use EventType;
 
 /// A stripped-down version of a state event that is included along with some other events.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum StrippedState {
     /// A stripped-down version of the *m.room.aliases* event.
     RoomAliases(StrippedRoomAliases),
@@ -64,17 +68,43 @@ pub enum StrippedState {
 }
 
 /// A "stripped-down" version of a core state event.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct StrippedStateContent<C> {
     /// Data specific to the event type.
     pub content: C,
     /// The type of the event.
     #[serde(rename = "type")]
     pub event_type: EventType,
+    /// The user who sent the event.
+    ///
+    /// This is `None` for events from servers implementing older versions of the specification.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender: Option<UserId>,
     /// A key that determines which piece of room state the event represents.
     pub state_key: String,
 }
 
+impl StrippedState {
+    /// The type of the event.
+    pub fn event_type(&self) -> &EventType {
+        match *self {
+            StrippedState::RoomAliases(ref event) => &event.event_type,
+            StrippedState::RoomAvatar(ref event) => &event.event_type,
+            StrippedState::RoomCanonicalAlias(ref event) => &event.event_type,
+            StrippedState::RoomCreate(ref event) => &event.event_type,
+            StrippedState::RoomGuestAccess(ref event) => &event.event_type,
+            StrippedState::RoomHistoryVisibility(ref event) => &event.event_type,
+            StrippedState::RoomJoinRules(ref event) => &event.event_type,
+            StrippedState::RoomMember(ref event) => &event.event_type,
+            StrippedState::RoomName(ref event) => &event.event_type,
+            StrippedState::RoomPowerLevels(ref event) => &event.event_type,
+            StrippedState::RoomThirdPartyInvite(ref event) => &event.event_type,
+            StrippedState::RoomTopic(ref event) => &event.event_type,
+        }
+    }
+}
+
 impl Serialize for StrippedState {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -256,12 +286,33 @@ pub type StrippedRoomTopic = StrippedStateContent<TopicEventContent>;
 
 #[cfg(test)]
 mod tests {
-    use super::{StrippedRoomTopic, StrippedState};
+    use std::fmt::Debug;
+
+    use super::{
+        StrippedRoomAliases, StrippedRoomAvatar, StrippedRoomCanonicalAlias, StrippedRoomCreate,
+        StrippedRoomGuestAccess, StrippedRoomHistoryVisibility, StrippedRoomJoinRules,
+        StrippedRoomMember, StrippedRoomName, StrippedRoomPowerLevels,
+        StrippedRoomThirdPartyInvite, StrippedRoomTopic, StrippedState,
+    };
     use room::join_rules::JoinRule;
     use room::topic::TopicEventContent;
     use serde_json::{from_str, to_string};
     use EventType;
 
+    assert_impl_all!(StrippedRoomAliases: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomAvatar: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomCanonicalAlias: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomCreate: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomGuestAccess: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomHistoryVisibility: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomJoinRules: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomName: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomPowerLevels: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomThirdPartyInvite: Clone, Debug, Eq, PartialEq);
+    assert_impl_all!(StrippedRoomTopic: Clone, Debug, Eq, PartialEq);
+    // Member content compares the signatures of a third party invite as JSON, which is not `Eq`.
+    assert_impl_all!(StrippedRoomMember: Clone, Debug, PartialEq);
+
     #[test]
     fn serialize_stripped_state_event() {
         let content = StrippedRoomTopic {
@@ -270,6 +321,7 @@ mod tests {
             },
             state_key: "".to_string(),
             event_type: EventType::RoomTopic,
+            sender: None,
         };
 
         let event = StrippedState::RoomTopic(content);
@@ -8,14 +8,14 @@ event! {
 }
 
 /// The payload of a `TagEvent`.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TagEventContent {
     /// A map of tag names to tag info.
     pub tags: HashMap<String, TagInfo>,
 }
 
 /// Information about a tag.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TagInfo {
     /// Value to use for lexicographically ordering rooms with this tag.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -0,0 +1,94 @@
+//! Types for the *m.voice_broadcast_info* event, as proposed in MSC3912.
+
+use room::create::RoomVersion;
+use RedactedStateEventContent;
+
+state_event! {
+    /// The state of a live voice broadcast.
+    ///
+    /// The state key is the ID of the user doing the broadcast. The audio itself is sent as voice
+    /// messages referencing the event that started the broadcast.
+    pub struct VoiceBroadcastInfoEvent(VoiceBroadcastInfoEventContent) {}
+}
+
+/// The payload of a `VoiceBroadcastInfoEvent`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VoiceBroadcastInfoEventContent {
+    /// The length in seconds of each voice message of the broadcast.
+    ///
+    /// Only sent when the broadcast is started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_length: Option<u32>,
+    /// The ID of the device doing the broadcast.
+    pub device_id: String,
+    /// The state of the broadcast.
+    pub state: VoiceBroadcastState,
+}
+
+impl VoiceBroadcastInfoEventContent {
+    /// The content of this event after a redaction, which keeps no field in any room version.
+    pub fn redact(&self, _room_version: &RoomVersion) -> RedactedStateEventContent {
+        RedactedStateEventContent {}
+    }
+}
+
+/// The state of a voice broadcast.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum VoiceBroadcastState {
+    /// The broadcast has started.
+    #[serde(rename = "started")]
+    Starting,
+    /// The broadcast has been paused.
+    #[serde(rename = "paused")]
+    Paused,
+    /// The broadcast has been resumed after a pause.
+    #[serde(rename = "resumed")]
+    Resumed,
+    /// The broadcast has ended.
+    #[serde(rename = "stopped")]
+    Stopped,
+}
+
+impl_enum! {
+    VoiceBroadcastState {
+        Starting => "started",
+        Paused => "paused",
+        Resumed => "resumed",
+        Stopped => "stopped",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, to_value, Value};
+
+    use super::{VoiceBroadcastInfoEventContent, VoiceBroadcastState};
+    use collections::all::StateEvent;
+
+    #[test]
+    fn round_trip() {
+        let json = r#"{
+            "content": {"chunk_length": 30, "device_id": "ABCDEFG", "state": "started"},
+            "event_id": "$143273582443PhrSn:example.org",
+            "origin_server_ts": 1,
+            "room_id": "!n8f893n9:example.com",
+            "sender": "@alice:example.org",
+            "state_key": "@alice:example.org",
+            "type": "m.voice_broadcast_info"
+        }"#;
+        let event = from_str::<StateEvent>(json).unwrap();
+
+        match event {
+            StateEvent::VoiceBroadcastInfo(ref event) => assert_eq!(
+                event.content,
+                VoiceBroadcastInfoEventContent {
+                    chunk_length: Some(30),
+                    device_id: "ABCDEFG".to_string(),
+                    state: VoiceBroadcastState::Starting,
+                }
+            ),
+            _ => panic!("expected a voice broadcast info event"),
+        }
+        assert_eq!(to_value(&event).unwrap(), from_str::<Value>(json).unwrap());
+    }
+}
@@ -0,0 +1,88 @@
+extern crate ruma_events;
+extern crate serde_json;
+
+use ruma_events::room::member::{MemberEvent, MembershipState};
+use ruma_events::stripped::StrippedState;
+use serde_json::{from_str, to_value, Value};
+
+/// An invite for a third party invitation, as Synapse sends it in the timeline of a sync response.
+const SYNAPSE_INVITE: &str = r#"{
+    "content": {
+        "avatar_url": "mxc://example.org/SEsfnsuifSDFSSEF",
+        "displayname": "Alice Margatroid",
+        "is_direct": true,
+        "membership": "invite",
+        "reason": "Looking forward to working with you",
+        "third_party_invite": {
+            "display_name": "alice",
+            "signed": {
+                "mxid": "@alice:example.org",
+                "signatures": {
+                    "magic.forest": {
+                        "ed25519:3": "fQpGIW1Snz+pwLZu6sTy2aHy/DYWWTspTJRPyNp0PKkymfIsNffysMl6ObMMFdIJhk6g6pwlIqZ54rxo8SLmAg"
+                    }
+                },
+                "token": "abc123"
+            }
+        }
+    },
+    "event_id": "$143273582443PhrSn:example.org",
+    "invite_room_state": [
+        {
+            "content": {"name": "Example Room"},
+            "sender": "@example:example.org",
+            "state_key": "",
+            "type": "m.room.name"
+        },
+        {
+            "content": {"join_rule": "invite"},
+            "sender": "@example:example.org",
+            "state_key": "",
+            "type": "m.room.join_rules"
+        }
+    ],
+    "origin_server_ts": 1432735824653,
+    "room_id": "!jEsUZKDJdhlrceRyVU:example.org",
+    "sender": "@example:example.org",
+    "state_key": "@alice:example.org",
+    "type": "m.room.member",
+    "unsigned": {
+        "age": 1234,
+        "prev_content": {"membership": "leave"},
+        "prev_sender": "@alice:example.org",
+        "replaces_state": "$151957878228ssqrJ:example.org"
+    }
+}"#;
+
+#[test]
+fn synapse_member_event_round_trips() {
+    let event = from_str::<MemberEvent>(SYNAPSE_INVITE).unwrap();
+
+    assert_eq!(event.content.membership, MembershipState::Invite);
+    assert_eq!(event.content.is_direct, Some(true));
+    assert_eq!(
+        event.content.displayname,
+        Some("Alice Margatroid".to_string())
+    );
+    assert_eq!(
+        event.content.reason,
+        Some("Looking forward to working with you".to_string())
+    );
+    assert_eq!(event.previous_membership(), Some(MembershipState::Leave));
+
+    let third_party_invite = event.content.third_party_invite.as_ref().unwrap();
+    assert_eq!(third_party_invite.display_name, Some("alice".to_string()));
+    assert_eq!(third_party_invite.signed.token, "abc123");
+
+    let invite_room_state = event.invite_room_state.as_ref().unwrap();
+    assert_eq!(invite_room_state.len(), 2);
+    match invite_room_state[0] {
+        StrippedState::RoomName(ref event) => assert_eq!(event.content.name, "Example Room"),
+        _ => panic!("expected a stripped m.room.name event"),
+    }
+
+    assert_eq!(
+        to_value(&event).unwrap(),
+        from_str::<Value>(SYNAPSE_INVITE).unwrap()
+    );
+}
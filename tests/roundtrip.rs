@@ -0,0 +1,282 @@
+extern crate ruma_events;
+extern crate serde_json;
+
+use ruma_events::collections::all::Event;
+use serde_json::{from_str, from_value, to_value, Value};
+
+/// The number of variants of `Event`, which `variant_index` must cover.
+const EVENT_VARIANTS: usize = 41;
+
+/// The position of the variant of `event` in `Event`.
+///
+/// This match has no wildcard arm, so adding a variant to `Event` fails to compile until it is
+/// listed here, and the test then fails until `events` contains an event of that type.
+fn variant_index(event: &Event) -> usize {
+    match *event {
+        Event::CallAnswer(_) => 0,
+        Event::CallCandidates(_) => 1,
+        Event::CallHangup(_) => 2,
+        Event::CallInvite(_) => 3,
+        Event::CallMember(_) => 4,
+        Event::Direct(_) => 5,
+        Event::KeyVerificationAccept(_) => 6,
+        Event::KeyVerificationDone(_) => 7,
+        Event::KeyVerificationKey(_) => 8,
+        Event::KeyVerificationMac(_) => 9,
+        Event::KeyVerificationReady(_) => 10,
+        Event::PollEnd(_) => 11,
+        Event::PollResponse(_) => 12,
+        Event::PollStart(_) => 13,
+        Event::Presence(_) => 14,
+        Event::Reaction(_) => 15,
+        Event::Receipt(_) => 16,
+        Event::RoomAliases(_) => 17,
+        Event::RoomAvatar(_) => 18,
+        Event::RoomCanonicalAlias(_) => 19,
+        Event::RoomCreate(_) => 20,
+        Event::RoomEncrypted(_) => 21,
+        Event::RoomGuestAccess(_) => 22,
+        Event::RoomHistoryVisibility(_) => 23,
+        Event::RoomJoinRules(_) => 24,
+        Event::RoomMember(_) => 25,
+        Event::RoomMessage(_) => 26,
+        Event::RoomName(_) => 27,
+        Event::RoomPinnedEvents(_) => 28,
+        Event::RoomPowerLevels(_) => 29,
+        Event::RoomRedaction(_) => 30,
+        Event::RoomServerAcl(_) => 31,
+        Event::RoomThirdPartyInvite(_) => 32,
+        Event::RoomTopic(_) => 33,
+        Event::SpaceChild(_) => 34,
+        Event::Tag(_) => 35,
+        Event::Typing(_) => 36,
+        Event::VoiceBroadcastInfo(_) => 37,
+        Event::Custom(_) => 38,
+        Event::CustomRoom(_) => 39,
+        Event::CustomState(_) => 40,
+    }
+}
+
+fn basic_event(event_type: &str, content: &str, sender: Option<&str>) -> String {
+    match sender {
+        Some(sender) => format!(
+            r#"{{"content":{},"sender":"{}","type":"{}"}}"#,
+            content, sender, event_type
+        ),
+        None => format!(r#"{{"content":{},"type":"{}"}}"#, content, event_type),
+    }
+}
+
+fn room_event(event_type: &str, content: &str, state_key: Option<&str>) -> String {
+    let state_key = match state_key {
+        Some(state_key) => format!(r#""state_key":"{}","#, state_key),
+        None => String::new(),
+    };
+
+    format!(
+        r#"{{"content":{},"event_id":"$h29iv0s8:example.com","origin_server_ts":1,"room_id":"!n8f893n9:example.com","sender":"@carl:example.com",{}"type":"{}"}}"#,
+        content, state_key, event_type
+    )
+}
+
+#[test]
+fn every_event_type_round_trips() {
+    let events = vec![
+        room_event(
+            "m.call.answer",
+            r#"{"answer":{"sdp":"v=0","type":"answer"},"call_id":"abc","version":0}"#,
+            None,
+        ),
+        room_event(
+            "m.call.candidates",
+            r#"{"call_id":"abc","candidates":[{"candidate":"candidate:0 1 UDP 2122252543 192.0.2.1 54400 typ host","sdpMLineIndex":0,"sdpMid":"audio"}],"version":0}"#,
+            None,
+        ),
+        room_event("m.call.hangup", r#"{"call_id":"abc","version":0}"#, None),
+        room_event(
+            "m.call.invite",
+            r#"{"call_id":"abc","lifetime":30000,"offer":{"sdp":"v=0","type":"offer"},"version":0}"#,
+            None,
+        ),
+        room_event(
+            "m.call.member",
+            r#"{"memberships":[]}"#,
+            Some("@carl:example.com"),
+        ),
+        basic_event(
+            "m.direct",
+            r#"{"@alice:example.com":["!n8f893n9:example.com"]}"#,
+            None,
+        ),
+        basic_event(
+            "m.key.verification.accept",
+            r#"{"commitment":"fQpGIW1Snz","hash":"sha256","key_agreement_protocol":"curve25519","message_authentication_code":"hkdf-hmac-sha256","method":"m.sas.v1","short_authentication_string":["decimal","emoji"],"transaction_id":"S0meUniqueAndOpaqueString"}"#,
+            Some("@alice:example.com"),
+        ),
+        room_event(
+            "m.key.verification.done",
+            r#"{"m.relates_to":{"event_id":"$request:example.com","rel_type":"m.reference"}}"#,
+            None,
+        ),
+        basic_event(
+            "m.key.verification.key",
+            r#"{"key":"fQpGIW1Snz","transaction_id":"S0meUniqueAndOpaqueString"}"#,
+            Some("@alice:example.com"),
+        ),
+        basic_event(
+            "m.key.verification.mac",
+            r#"{"keys":"2Wptgo4CwmLo","mac":{"ed25519:ABCDEF":"fQpGIW1Snz"},"transaction_id":"S0meUniqueAndOpaqueString"}"#,
+            Some("@alice:example.com"),
+        ),
+        room_event(
+            "m.key.verification.ready",
+            r#"{"from_device":"ABCDEFG","m.relates_to":{"event_id":"$request:example.com","rel_type":"m.reference"},"methods":["m.sas.v1"]}"#,
+            None,
+        ),
+        room_event(
+            "m.poll.end",
+            r#"{"m.relates_to":{"event_id":"$poll:example.com","rel_type":"m.reference"},"org.matrix.msc3381.poll.end":{}}"#,
+            None,
+        ),
+        room_event(
+            "m.poll.response",
+            r#"{"m.relates_to":{"event_id":"$poll:example.com","rel_type":"m.reference"},"org.matrix.msc3381.poll.response":{"answers":["a"]}}"#,
+            None,
+        ),
+        room_event(
+            "m.poll.start",
+            r#"{"org.matrix.msc3381.poll.start":{"answers":[{"id":"a","org.matrix.msc3381.poll.answer":{"body":"Yes"}}],"kind":"org.matrix.msc3381.poll.disclosed","max_selections":1,"question":{"body":"Ruma?"}}}"#,
+            None,
+        ),
+        basic_event(
+            "m.presence",
+            r#"{"presence":"online"}"#,
+            Some("@alice:example.com"),
+        ),
+        room_event(
+            "m.reaction",
+            r#"{"m.relates_to":{"event_id":"$a:example.com","key":"+1","rel_type":"m.annotation"}}"#,
+            None,
+        ),
+        basic_event(
+            "m.receipt",
+            r#"{"$a:example.com":{"m.read":{"@alice:example.com":{"ts":1}}}}"#,
+            None,
+        ),
+        room_event(
+            "m.room.aliases",
+            r##"{"aliases":["#ruma:example.com"]}"##,
+            Some("example.com"),
+        ),
+        room_event(
+            "m.room.avatar",
+            r#"{"url":"mxc://example.com/abc"}"#,
+            Some(""),
+        ),
+        room_event(
+            "m.room.canonical_alias",
+            r##"{"alias":"#ruma:example.com"}"##,
+            Some(""),
+        ),
+        room_event(
+            "m.room.create",
+            r#"{"creator":"@carl:example.com","m.federate":true,"room_version":"1"}"#,
+            Some(""),
+        ),
+        room_event(
+            "m.room.encrypted",
+            r#"{"algorithm":"m.megolm.v1.aes-sha2","ciphertext":"AwgAEnAC","session_id":"X3lUlvLE"}"#,
+            None,
+        ),
+        room_event(
+            "m.room.guest_access",
+            r#"{"guest_access":"can_join"}"#,
+            Some(""),
+        ),
+        room_event(
+            "m.room.history_visibility",
+            r#"{"history_visibility":"shared"}"#,
+            Some(""),
+        ),
+        room_event("m.room.join_rules", r#"{"join_rule":"public"}"#, Some("")),
+        room_event(
+            "m.room.member",
+            r#"{"membership":"join"}"#,
+            Some("@carl:example.com"),
+        ),
+        room_event(
+            "m.room.message",
+            r#"{"body":"Hello","msgtype":"m.text"}"#,
+            None,
+        ),
+        room_event("m.room.name", r#"{"name":"Ruma"}"#, Some("")),
+        room_event(
+            "m.room.pinned_events",
+            r#"{"pinned":["$a:example.com"]}"#,
+            Some(""),
+        ),
+        room_event(
+            "m.room.power_levels",
+            r#"{"ban":100,"users":{"@carl:example.com":100}}"#,
+            Some(""),
+        ),
+        r#"{"content":{},"event_id":"$h29iv0s8:example.com","origin_server_ts":1,"redacts":"$a:example.com","room_id":"!n8f893n9:example.com","sender":"@carl:example.com","type":"m.room.redaction"}"#.to_string(),
+        room_event(
+            "m.room.server_acl",
+            r#"{"allow":["*"],"allow_ip_literals":false,"deny":["evil.com"]}"#,
+            Some(""),
+        ),
+        room_event(
+            "m.room.third_party_invite",
+            r#"{"display_name":"alice","key_validity_url":"https://magic.forest/verifykey","public_key":"abc123","public_keys":[{"key_validity_url":"https://magic.forest/verifykey","public_key":"abc123"}]}"#,
+            Some("abc123"),
+        ),
+        room_event("m.room.topic", r#"{"topic":"Ruma"}"#, Some("")),
+        room_event(
+            "m.space.child",
+            r#"{"order":"a","suggested":true,"via":["example.com"]}"#,
+            Some("!child:example.com"),
+        ),
+        basic_event(
+            "m.tag",
+            r#"{"tags":{"m.favourite":{"order":"0.5"}}}"#,
+            None,
+        ),
+        basic_event(
+            "m.typing",
+            r#"{"user_ids":["@alice:example.com"]}"#,
+            None,
+        ),
+        room_event(
+            "m.voice_broadcast_info",
+            r#"{"device_id":"ABCDEFG","state":"stopped"}"#,
+            Some("@carl:example.com"),
+        ),
+        basic_event("org.example.custom", r#"{"a":1}"#, None),
+        room_event("org.example.custom_room", r#"{"a":1}"#, None),
+        room_event("org.example.custom_state", r#"{"a":1}"#, Some("")),
+    ];
+
+    let mut covered = vec![false; EVENT_VARIANTS];
+
+    for json in events {
+        let value = from_str::<Value>(&json).unwrap();
+        let event = from_value::<Event>(value.clone()).unwrap();
+
+        assert_eq!(
+            event.event_type().to_string(),
+            value["type"].as_str().unwrap()
+        );
+        assert_eq!(to_value(&event).unwrap(), value, "{}", json);
+        assert_eq!(
+            from_value::<Event>(to_value(&event).unwrap()).unwrap(),
+            event,
+            "{}",
+            json
+        );
+
+        covered[variant_index(&event)] = true;
+    }
+
+    assert!(covered.iter().all(|&covered| covered), "{:?}", covered);
+}